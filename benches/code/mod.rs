@@ -1,7 +1,10 @@
 use std::{hint::black_box, pin::pin};
 
-use criterion::{BenchmarkId, Criterion, criterion_group};
-use iter_merge::{ArrayStorage, VecStorage, comparators::tie_breaker};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group};
+use iter_merge::{
+    ArrayStorage, VecStorage,
+    comparators::{ByOrd, ByOrdBranchless, tie_breaker},
+};
 use itertools::kmerge;
 use rand::prelude::*;
 
@@ -198,8 +201,6 @@ fn bench_configs(c: &mut Criterion) {
     group.bench_function("Stable", |b| {
         b.iter(|| {
             VecStorage::from_iter(make_iters(N_ITERS, &vec))
-                .into_builder()
-                .tie_breaker(tie_breaker::Unspecified)
                 .build()
                 .into_vec()
         });
@@ -273,4 +274,133 @@ fn collect(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_itertools, bench_configs, collect);
+fn bench_radix(c: &mut Criterion) {
+    let iter_counts = [8, 64, 256, 1024, 4096];
+    let n_els = 2_usize.pow(20);
+    let random = StdRng::seed_from_u64(0)
+        .random_iter()
+        .take(n_els)
+        .collect::<Vec<u64>>();
+    let mut group = c.benchmark_group("Radix partitioned (sorted sources)");
+    for &n_iters in &iter_counts {
+        let mut vec = random.clone();
+        vec.chunks_mut(n_els / n_iters)
+            .for_each(<[u64]>::sort_unstable);
+        vec = black_box(vec);
+        group.bench_function(BenchmarkId::new("Flat heap", n_iters), |b| {
+            b.iter(|| {
+                VecStorage::from_iter(make_iters(n_iters, &vec))
+                    .into_builder()
+                    .tie_breaker(tie_breaker::Unspecified)
+                    .build()
+                    .for_each(consume)
+            });
+        });
+        vec = black_box(vec);
+        group.bench_function(BenchmarkId::new("Radix partitioned", n_iters), |b| {
+            b.iter(|| {
+                VecStorage::from_iter(make_iters(n_iters, &vec))
+                    .radix_partitioned()
+                    .for_each(consume)
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_cached_keys(c: &mut Criterion) {
+    let iter_counts = [8, 64, 1024, 8192];
+    let n_els = 2_usize.pow(18);
+    let mut rng = StdRng::seed_from_u64(0);
+    // Boxed large records with a small key
+    let records = (0..n_els)
+        .map(|_| Box::new((rng.random::<u64>(), [0_u64; 15])))
+        .collect::<Vec<_>>();
+    let mut group = c.benchmark_group("Cached keys (boxed records)");
+    for &n_iters in &iter_counts {
+        let mut vec = records.clone();
+        vec.chunks_mut(n_els / n_iters)
+            .for_each(|chunk| chunk.sort_unstable_by_key(|record| record.0));
+        let it_len = n_els / n_iters;
+        let iters = |vec: &[Box<(u64, [u64; 15])>]| {
+            (0..n_iters)
+                .map(|n| vec[n * it_len..(n + 1) * it_len].to_vec())
+                .collect::<Vec<_>>()
+        };
+        vec = black_box(vec);
+        group.bench_function(BenchmarkId::new("min_by_heap_key", n_iters), |b| {
+            b.iter_batched(
+                || iters(&vec),
+                |iters| {
+                    VecStorage::from_iter(iters)
+                        .into_builder()
+                        .min_by_heap_key(|record| record.0)
+                        .build()
+                        .for_each(consume)
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        vec = black_box(vec);
+        // Same order, without the cached keys
+        group.bench_function(BenchmarkId::new("min_by_key", n_iters), |b| {
+            b.iter_batched(
+                || iters(&vec),
+                |iters| {
+                    VecStorage::from_iter(iters)
+                        .into_builder()
+                        .min_by_key(|record| record.0)
+                        .build()
+                        .for_each(consume)
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn bench_branchless(c: &mut Criterion) {
+    let iter_counts = [8, 64, 1024];
+    let n_els = 2_usize.pow(20);
+    let mut vec = StdRng::seed_from_u64(0)
+        .random_iter()
+        .take(n_els)
+        .collect::<Vec<u64>>();
+    let mut group = c.benchmark_group("Branchless comparator (random)");
+    for &n_iters in &iter_counts {
+        vec = black_box(vec);
+        group.bench_function(BenchmarkId::new("ByOrd", n_iters), |b| {
+            b.iter(|| {
+                VecStorage::from_iter(make_iters(n_iters, &vec))
+                    .into_builder()
+                    .min_by(ByOrd)
+                    .tie_breaker(tie_breaker::Unspecified)
+                    .build()
+                    .for_each(consume)
+            });
+        });
+        vec = black_box(vec);
+        group.bench_function(BenchmarkId::new("ByOrdBranchless", n_iters), |b| {
+            b.iter(|| {
+                VecStorage::from_iter(make_iters(n_iters, &vec))
+                    .into_builder()
+                    .min_by(ByOrdBranchless)
+                    .tie_breaker(tie_breaker::Unspecified)
+                    .build()
+                    .for_each(consume)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_itertools,
+    bench_configs,
+    collect,
+    bench_radix,
+    bench_cached_keys,
+    bench_branchless
+);
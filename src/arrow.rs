@@ -0,0 +1,197 @@
+//! Merging of the [Arrow](https://docs.rs/arrow) record batches sorted by a key column,
+//! enabled by the `arrow` feature.
+//!
+//! Every [`RecordBatch`] (or array) is a source of the merge, its rows have to be sorted by
+//! the sort column according to the [`SortOptions`]. The merge compares only the sort
+//! columns and produces the `(batch_index, row_index)` pairs of the rows in merged order, in
+//! the format expected by [`interleave`](arrow_select::interleave::interleave) and
+//! [`interleave_record_batch`], which gather the merged rows from all of the batches.
+//!
+//! Rows with equal keys are ordered by the index of their batch, then by their row index.
+//!
+//! # Example
+//! ```
+//! use std::sync::Arc;
+//!
+//! use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+//! use arrow_schema::SortOptions;
+//! use iter_merge::arrow::{merge_batches, merge_indices};
+//!
+//! let batch = |ts: Vec<i64>, host: Vec<&str>| {
+//!     RecordBatch::try_from_iter([
+//!         ("ts", Arc::new(Int64Array::from(ts)) as ArrayRef),
+//!         ("host", Arc::new(StringArray::from(host)) as ArrayRef),
+//!     ])
+//!     .unwrap()
+//! };
+//! let batches = [batch(vec![1, 4, 4], vec!["a"; 3]), batch(vec![2, 4], vec!["b"; 2])];
+//!
+//! let indices = merge_indices(&batches, 0, SortOptions::default()).unwrap();
+//! assert_eq!(indices, vec![(0, 0), (1, 0), (0, 1), (0, 2), (1, 1)]);
+//!
+//! let merged = merge_batches(&batches, 0, SortOptions::default()).unwrap();
+//! assert_eq!(merged, batch(vec![1, 2, 4, 4, 4], vec!["a", "b", "a", "a", "b"]));
+//! ```
+
+use std::{format, string::String, vec::Vec};
+
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_ord::ord::make_comparator;
+use arrow_schema::{ArrowError, SortOptions};
+use arrow_select::{concat::concat, interleave::interleave_record_batch};
+
+use crate::VecStorage;
+
+/// Merges the sorted key columns, returning `(column_index, row_index)` pairs in merged order.
+///
+/// # Errors
+/// Returns an error if the columns have different data types, or if the data type can't be
+/// compared.
+///
+/// # Example
+/// ```
+/// use arrow_array::{Array, UInt32Array};
+/// use arrow_schema::SortOptions;
+/// use iter_merge::arrow::merge_column_indices;
+///
+/// let a = UInt32Array::from(vec![Some(4), Some(1), None]);
+/// let b = UInt32Array::from(vec![Some(5), Some(2)]);
+/// let descending = SortOptions {
+///     descending: true,
+///     nulls_first: false,
+/// };
+/// let indices = merge_column_indices(&[&a as &dyn Array, &b], descending).unwrap();
+/// assert_eq!(indices, vec![(1, 0), (0, 0), (1, 1), (0, 1), (0, 2)]);
+/// ```
+pub fn merge_column_indices(
+    columns: &[&dyn Array], options: SortOptions,
+) -> Result<Vec<(usize, usize)>, ArrowError> {
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+    // All of the rows are compared by their positions in the concatenated column, with one
+    // comparator instead of one per pair of the columns
+    let keys = concat(columns)?;
+    let cmp = make_comparator(&keys, &keys, options)?;
+    let mut end = 0;
+    let sources = columns.iter().map(|column| {
+        let start = end;
+        end += column.len();
+        (start..end).zip(0..column.len())
+    });
+    let merged = VecStorage::from_iter(sources)
+        .into_builder()
+        .min_by_func(|a: &(usize, usize), b: &(usize, usize)| cmp(a.0, b.0))
+        .build();
+    Ok(merged
+        .source_tagged()
+        .map(|(column, (_, row))| (column, row))
+        .collect())
+}
+
+/// Merges the `batches` sorted by the column with the index `sort_column`, returning
+/// `(batch_index, row_index)` pairs in merged order, see the [module docs](self).
+///
+/// # Errors
+/// Returns an error if a batch doesn't have the `sort_column`, or if the sort columns can't
+/// be merged, see [`merge_column_indices`].
+pub fn merge_indices(
+    batches: &[RecordBatch], sort_column: usize, options: SortOptions,
+) -> Result<Vec<(usize, usize)>, ArrowError> {
+    let columns = batches
+        .iter()
+        .map(|batch| {
+            batch
+                .columns()
+                .get(sort_column)
+                .map(ArrayRef::as_ref)
+                .ok_or_else(|| {
+                    ArrowError::InvalidArgumentError(format!(
+                        "sort column {sort_column} is out of bounds for a batch with {} columns",
+                        batch.num_columns()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    merge_column_indices(&columns, options)
+}
+
+/// Merges the `batches` sorted by the column with the index `sort_column` into a single
+/// batch, see the [module docs](self).
+///
+/// # Errors
+/// Returns an error if there are no batches, if the batches have different schemas, or if
+/// they can't be merged, see [`merge_indices`].
+pub fn merge_batches(
+    batches: &[RecordBatch], sort_column: usize, options: SortOptions,
+) -> Result<RecordBatch, ArrowError> {
+    if batches.is_empty() {
+        return Err(ArrowError::InvalidArgumentError(String::from(
+            "no record batches to merge",
+        )));
+    }
+    let indices = merge_indices(batches, sort_column, options)?;
+    let batches: Vec<&RecordBatch> = batches.iter().collect();
+    interleave_record_batch(&batches, &indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, vec};
+
+    use arrow_array::{Float64Array, Int32Array, StringArray};
+
+    use super::*;
+
+    fn batch(keys: Vec<Option<i32>>, values: Vec<&str>) -> RecordBatch {
+        RecordBatch::try_from_iter([
+            ("value", Arc::new(StringArray::from(values)) as ArrayRef),
+            ("key", Arc::new(Int32Array::from(keys)) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn merges_batches() {
+        let batches = [
+            batch(vec![None, Some(2), Some(5)], vec!["a0", "a1", "a2"]),
+            batch(vec![], vec![]),
+            batch(vec![None, Some(2), Some(3)], vec!["c0", "c1", "c2"]),
+        ];
+        let merged = merge_batches(&batches, 1, SortOptions::default()).unwrap();
+        let expected = batch(
+            vec![None, None, Some(2), Some(2), Some(3), Some(5)],
+            vec!["a0", "c0", "a1", "c1", "c2", "a2"],
+        );
+        assert_eq!(merged, expected);
+
+        let nulls_last = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let batches = [
+            batch(vec![Some(1), None], vec!["a0", "a1"]),
+            batch(vec![Some(0), Some(4)], vec!["b0", "b1"]),
+        ];
+        let indices = merge_indices(&batches, 1, nulls_last).unwrap();
+        assert_eq!(indices, vec![(1, 0), (0, 0), (1, 1), (0, 1)]);
+    }
+
+    #[test]
+    fn errors() {
+        let batches = [batch(vec![Some(1)], vec!["a"])];
+        assert!(matches!(
+            merge_indices(&batches, 2, SortOptions::default()),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+        assert!(matches!(
+            merge_batches(&[], 0, SortOptions::default()),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+        assert_eq!(merge_column_indices(&[], SortOptions::default()).unwrap(), vec![]);
+
+        let ints = Int32Array::from(vec![1]);
+        let floats = Float64Array::from(vec![1.0]);
+        assert!(merge_column_indices(&[&ints, &floats], SortOptions::default()).is_err());
+    }
+}
@@ -4,15 +4,48 @@
 //! or use [`ByOrd`] in builder functions [`{min|max}_by`](crate::merge_iter::Builder::min_by)
 //! to compare items using [`Ord`] trait.
 //!
-//! Comparators can be chained by using [`Chain::new`].
+//! Comparators can be chained by using [`Chain::new`] or by grouping them into a tuple (of up to
+//! 6 comparators) that applies them in order, "less than" predicates are converted into
+//! comparators by [`ByLessThan::new`].
 //!
-//! The rest of the structures here have no public constructors, they are constructed by various
-//! [`Builder`](crate::merge_iter::Builder) methods.
+//! [`Comparator`] is object safe: `&dyn Comparator<T>` (and `Box<dyn Comparator<T>>` with the
+//! `alloc` feature) are comparators too, so one [`MergeIter`](crate::MergeIter) type can be
+//! used with comparators chosen at runtime. Comparators are also implemented for `Rc` and `Arc`
+//! of comparators, for `fn(&T, &T) -> Ordering` pointers and for `dyn Fn(&T, &T) -> Ordering`
+//! closures, so they can be stored in structures and shared between merges.
+//!
+//! Wrapping and key-based comparators ([`MaxFirst`], [`Directed`], [`ByLessThan`], [`ByKey`],
+//! [`ByKeyWith`], [`RankTable`], [`ByMut`], [`NoneFirst`] and [`NoneLast`]) have public `new`
+//! constructors, unit comparators like [`ByOrd`] are constructed directly. The rest of the
+//! structures here are constructed by various [`Builder`](crate::merge_iter::Builder) methods.
+
+use core::{cell::RefCell, cmp::Ordering};
 
-use core::cmp::Ordering;
+/// Implements [`Reversible`] by wrapping the comparators into [`MaxFirst`]
+macro_rules! impl_reversible {
+    ($([$($gen:tt)*] $ty:ty),+ $(,)?) => {$(
+        impl<$($gen)*> Reversible for $ty {
+            type Reversed = MaxFirst<Self>;
+
+            #[inline]
+            fn reverse(self) -> MaxFirst<Self> {
+                MaxFirst(self)
+            }
+        }
+    )+};
+}
 
+#[cfg(feature = "collation")]
+mod collated;
+#[cfg(feature = "natural")]
+mod natural;
 pub mod tie_breaker;
 
+#[cfg(feature = "collation")]
+pub use collated::Collated;
+#[cfg(feature = "natural")]
+pub use natural::Natural;
+
 /// Trait used to compare elements of [`MergeIter`](crate::MergeIter)
 ///
 /// Implementations should produce a consistent total ordering, see [`Ord`]
@@ -20,154 +53,1777 @@ pub mod tie_breaker;
 ///
 /// Producing non-total or inconsistent ordering may result in incorrect behavior
 /// (i.e. items are yielded in a wrong order) but will not result in UB.
-pub trait Comparator<T>: Sized {
+///
+/// `T` may be unsized, e.g. the comparators of `str` compare the keys borrowed by
+/// [`ByKeyWith`].
+#[rustversion::attr(
+    since(1.78),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` can't compare items of type `{T}`",
+        label = "`{Self}` is not a comparator of `{T}`",
+        note = "the default comparator `ByOrd` requires the items to implement `Ord`, \
+                otherwise choose the comparator with one of `Builder` methods: `min_by_key`, \
+                `min_by_func`, `min_by_partial`, `min_by` or their `max_` counterparts"
+    )
+)]
+pub trait Comparator<T: ?Sized> {
     /// Compares two elements and returns an [`Ordering`]
     fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering;
+
+    /// Returns `true` if the elements are equal according to this comparator, ignoring the
+    /// [tie breakers](tie_breaker), which only order the items by their position.
+    ///
+    /// Used by [`MergeIter::dedup`](crate::MergeIter::dedup). Defaults to
+    /// [`compare`](Self::compare) returning [`Ordering::Equal`].
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.compare(a, b).is_eq()
+    }
+
+    /// Returns `true` if the merge skips the duplicates of the yielded items, see
+    /// [`Deduplicated`]
+    #[doc(hidden)]
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if `next` is a duplicate of the yielded `prev`. Only called if
+    /// [`deduplicates`](Self::deduplicates) returns `true`.
+    #[doc(hidden)]
+    #[inline]
+    fn is_duplicate(&self, _prev: &T, _next: &T) -> bool {
+        false
+    }
+
+    /// Compares the peeked items of two sources of the merge, given the
+    /// [ranks](crate::internal::BaseStorage::source_rank) of the sources if
+    /// [`compares_ranks`](Self::compares_ranks) returns `true`, `(0, 0)` otherwise. See
+    /// [`ByPriority`](tie_breaker::ByPriority). Defaults to [`compare`](Self::compare).
+    #[doc(hidden)]
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, _ranks: (u64, u64)) -> Ordering {
+        self.compare(a, b)
+    }
+
+    /// Returns `true` if [`compare_sources`](Self::compare_sources) uses the ranks of the
+    /// sources, so the heap has to look them up
+    #[doc(hidden)]
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        false
+    }
+
+    /// Returns the comparator that compares the items with `next` if this comparator
+    /// considers them equal, see [`Chain`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{
+    ///     VecStorage,
+    ///     comparators::{ByOrd, Comparator, Reversible},
+    /// };
+    ///
+    /// let cmp = ByOrd.map_key(|(day, _): &(u8, i32)| *day).then(
+    ///     ByOrd
+    ///         .map_key(|(_, amount): &(u8, i32)| *amount)
+    ///         .reverse(),
+    /// );
+    /// let res = VecStorage::from_iter([vec![(1, 5), (2, 7)], vec![(1, 9), (2, 3)]])
+    ///     .into_builder()
+    ///     .min_by(cmp)
+    ///     .build()
+    ///     .into_vec();
+    /// assert_eq!(res, vec![(1, 9), (1, 5), (2, 7), (2, 3)]);
+    /// # }
+    /// ```
+    #[inline]
+    fn then<C>(self, next: C) -> Chain<Self, C>
+    where
+        Self: Sized,
+        C: Comparator<T>,
+    {
+        Chain { first: self, next }
+    }
+
+    /// Returns the comparator of `U` items that compares their keys, produced by `func`, with
+    /// this comparator
+    #[inline]
+    fn map_key<U, F>(self, func: F) -> MapKey<Self, F>
+    where
+        Self: Sized,
+        T: Sized,
+        F: Fn(&U) -> T,
+    {
+        MapKey {
+            comparator: self,
+            func,
+        }
+    }
+}
+
+impl<T: ?Sized, C> Comparator<T> for &C
+where
+    C: Comparator<T> + ?Sized,
+{
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        C::compare(self, a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        C::equivalent(self, a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        C::deduplicates(self)
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        C::is_duplicate(self, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        C::compare_sources(self, a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        C::compares_ranks(self)
+    }
+}
+
+/// Boxed comparator of `T` items, see [`min_by_dyn`](crate::merge_iter::Builder::min_by_dyn)
+#[cfg(feature = "alloc")]
+pub type DynComparator<'a, T> = alloc::boxed::Box<dyn Comparator<T> + 'a>;
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized, C> Comparator<T> for alloc::boxed::Box<C>
+where
+    C: Comparator<T> + ?Sized,
+{
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        C::compare(self, a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        C::equivalent(self, a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        C::deduplicates(self)
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        C::is_duplicate(self, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        C::compare_sources(self, a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        C::compares_ranks(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized, C> Comparator<T> for alloc::rc::Rc<C>
+where
+    C: Comparator<T> + ?Sized,
+{
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        C::compare(self, a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        C::equivalent(self, a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        C::deduplicates(self)
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        C::is_duplicate(self, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        C::compare_sources(self, a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        C::compares_ranks(self)
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<T: ?Sized, C> Comparator<T> for alloc::sync::Arc<C>
+where
+    C: Comparator<T> + ?Sized,
+{
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        C::compare(self, a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        C::equivalent(self, a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        C::deduplicates(self)
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        C::is_duplicate(self, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        C::compare_sources(self, a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        C::compares_ranks(self)
+    }
+}
+
+impl<T: ?Sized> Comparator<T> for fn(&T, &T) -> Ordering {
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        self(a, b)
+    }
+}
+
+// Closure trait objects are comparators, so `Box<dyn Fn(&T, &T) -> Ordering>` (as well as
+// `&dyn Fn`, `Rc<dyn Fn>` and `Arc<dyn Fn + Send + Sync>`) can be used without the `ByFunc`
+// wrapper, which can't hold an unsized closure.
+macro_rules! impl_comparator_dyn_fn {
+    ($($bounds:tt)*) => {
+        impl<T: ?Sized> Comparator<T> for dyn Fn(&T, &T) -> Ordering $($bounds)* + '_ {
+            #[inline]
+            fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+                self(a, b)
+            }
+        }
+    };
+}
+
+impl_comparator_dyn_fn!();
+impl_comparator_dyn_fn!(+ Send);
+impl_comparator_dyn_fn!(+ Send + Sync);
+
+/// Comparator that compares the keys of the items with the inner comparator
+///
+/// Construct via [`Comparator::map_key`]
+#[derive(Debug, Clone)]
+pub struct MapKey<C, F> {
+    comparator: C,
+    func: F,
+}
+
+impl<T, C, F, K> Comparator<T> for MapKey<C, F>
+where
+    C: Comparator<K>,
+    F: Fn(&T) -> K,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.comparator.compare(&(self.func)(a), &(self.func)(b))
+    }
+
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.comparator.equivalent(&(self.func)(a), &(self.func)(b))
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        self.comparator.deduplicates()
+    }
+
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        self.comparator.is_duplicate(&(self.func)(prev), &(self.func)(next))
+    }
+
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        self.comparator.compare_sources(&(self.func)(a), &(self.func)(b), ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.comparator.compares_ranks()
+    }
+}
+
+/// Wrapper that reverses a comparator.
+///
+/// Our internal data stuctures are all min-first, so to get
+/// max-first we're just inverting the order of operands passed to
+/// comparators.
+#[derive(Debug, Clone)]
+pub struct MaxFirst<C>(pub(crate) C);
+
+impl<C> MaxFirst<C> {
+    #[inline]
+    #[doc(hidden)]
+    pub const fn new<T: ?Sized>(comparator: C) -> Self
+    where
+        C: Comparator<T>,
+    {
+        Self(comparator)
+    }
+}
+
+impl<T: ?Sized, C> Comparator<T> for MaxFirst<C>
+where
+    C: Comparator<T>,
+{
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(b, a)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.0.equivalent(b, a)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        self.0.deduplicates()
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        self.0.is_duplicate(prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, (a_rank, b_rank): (u64, u64)) -> Ordering {
+        self.0.compare_sources(b, a, (b_rank, a_rank))
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.0.compares_ranks()
+    }
+}
+
+impl<C: tie_breaker::StableAcrossSources> tie_breaker::StableAcrossSources for MaxFirst<C> {}
+
+/// Direction of the merge, see [`Directed`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Smallest item first
+    #[default]
+    Ascending,
+    /// Largest item first
+    Descending,
+}
+
+impl Direction {
+    /// Returns the opposite direction
+    #[must_use]
+    #[inline]
+    pub const fn reversed(self) -> Self {
+        match self {
+            Self::Ascending => Self::Descending,
+            Self::Descending => Self::Ascending,
+        }
+    }
+}
+
+/// Wrapper that orders the items by the inner comparator in the [`Direction`] chosen at
+/// runtime.
+///
+/// Unlike with [`MaxFirst`], the direction is a field, so changing it keeps the type of the
+/// comparator, see [`MergeIter::set_direction`](crate::MergeIter::set_direction).
+#[derive(Debug, Clone)]
+pub struct Directed<C> {
+    comparator: C,
+    direction: Direction,
+}
+
+impl<C> Directed<C> {
+    /// Wraps the `comparator`, ordering the items in the `direction`
+    #[inline]
+    pub const fn new(comparator: C, direction: Direction) -> Self {
+        Self {
+            comparator,
+            direction,
+        }
+    }
+
+    /// Returns the direction of the order
+    #[inline]
+    pub const fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Sets the direction of the order
+    #[inline]
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.direction = direction;
+    }
+
+    /// Returns the inner comparator
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.comparator
+    }
+}
+
+impl<T: ?Sized, C> Comparator<T> for Directed<C>
+where
+    C: Comparator<T>,
+{
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match self.direction {
+            Direction::Ascending => self.comparator.compare(a, b),
+            Direction::Descending => self.comparator.compare(b, a),
+        }
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        match self.direction {
+            Direction::Ascending => self.comparator.equivalent(a, b),
+            Direction::Descending => self.comparator.equivalent(b, a),
+        }
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        self.comparator.deduplicates()
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        self.comparator.is_duplicate(prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, (a_rank, b_rank): (u64, u64)) -> Ordering {
+        match self.direction {
+            Direction::Ascending => self.comparator.compare_sources(a, b, (a_rank, b_rank)),
+            Direction::Descending => self.comparator.compare_sources(b, a, (b_rank, a_rank)),
+        }
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.comparator.compares_ranks()
+    }
+}
+
+impl<C: tie_breaker::StableAcrossSources> tie_breaker::StableAcrossSources for Directed<C> {}
+
+/// Wrapper that makes the merge skip the items equivalent to the previous yielded item,
+/// otherwise compares like the inner comparator.
+///
+/// Constructed by [`Builder::dedup`](crate::merge_iter::Builder::dedup), the heap drops the
+/// duplicates while popping the items, so the [`MergeIter`](crate::MergeIter) keeps all of
+/// its methods.
+#[derive(Debug, Clone)]
+pub struct Deduplicated<C>(pub(crate) C);
+
+impl<C> Deduplicated<C> {
+    /// Returns the inner comparator
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<T: ?Sized, C> Comparator<T> for Deduplicated<C>
+where
+    C: Comparator<T>,
+{
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.0.equivalent(a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        self.0.equivalent(prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        self.0.compare_sources(a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.0.compares_ranks()
+    }
+}
+
+impl<C: tie_breaker::StableAcrossSources> tie_breaker::StableAcrossSources for Deduplicated<C> {}
+
+/// Wrapper that makes the merge skip the items with the same key as the previous yielded
+/// item, otherwise compares like the inner comparator.
+///
+/// Constructed by [`Builder::dedup_by_key`](crate::merge_iter::Builder::dedup_by_key)
+#[derive(Debug, Clone)]
+pub struct DeduplicatedByKey<C, F> {
+    comparator: C,
+    key: F,
+}
+
+impl<C, F> DeduplicatedByKey<C, F> {
+    #[inline]
+    pub(crate) const fn new(comparator: C, key: F) -> Self {
+        Self { comparator, key }
+    }
+
+    /// Returns the inner comparator
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.comparator
+    }
+}
+
+impl<T: ?Sized, C, F, K> Comparator<T> for DeduplicatedByKey<C, F>
+where
+    C: Comparator<T>,
+    F: Fn(&T) -> K,
+    K: PartialEq,
+{
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.comparator.compare(a, b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.comparator.equivalent(a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        true
+    }
+
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        (self.key)(prev) == (self.key)(next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        self.comparator.compare_sources(a, b, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.comparator.compares_ranks()
+    }
+}
+
+impl<C: tie_breaker::StableAcrossSources, F> tie_breaker::StableAcrossSources
+    for DeduplicatedByKey<C, F>
+{
+}
+
+/// Comparator that can be reversed, see [`Builder::reverse`](crate::merge_iter::Builder::reverse)
+///
+/// Implemented for the comparators of this crate: [`MaxFirst`] is unwrapped, [`Directed`]
+/// flips its direction, [`ByFloat`] keeps the placement of NaNs, the rest of them are wrapped
+/// into [`MaxFirst`]. Custom comparators may implement it the same way.
+///
+/// Together with [`Comparator::then`] and [`Comparator::map_key`] it allows building the
+/// comparators fluently, e.g. `ByOrd.map_key(|item: &(u8, i32)| item.1).reverse()`.
+pub trait Reversible {
+    /// Comparator with the reversed order
+    type Reversed;
+
+    /// Returns the comparator with the reversed order
+    fn reverse(self) -> Self::Reversed;
+}
+
+impl<C> Reversible for MaxFirst<C> {
+    type Reversed = C;
+
+    #[inline]
+    fn reverse(self) -> C {
+        self.0
+    }
+}
+
+impl<C> Reversible for Directed<C> {
+    type Reversed = Self;
+
+    #[inline]
+    fn reverse(mut self) -> Self {
+        self.direction = self.direction.reversed();
+        self
+    }
+}
+
+impl_reversible!(
+    [] ByOrd,
+    [C1, C2] Chain<C1, C2>,
+    [F] ByFunc<F>,
+    [F] ByKey<F>,
+    [] ByPartialOrd,
+    [F] ByLessThan<F>,
+    [] ByOrdBranchless,
+    [] ByDeref,
+    [] ByTotalOrd,
+    [C, F] MapKey<C, F>,
+    [F] ByKeyRef<F>,
+    [F, C] ByKeyWith<F, C>,
+    [C] ByMut<C>,
+    [C] NoneFirst<C>,
+    [C] NoneLast<C>,
+    [] ByCachedKey,
+    [R] RankTable<R>,
+    [C] Deduplicated<C>,
+    [C, F] DeduplicatedByKey<C, F>,
+);
+
+impl Reversible for ByFloat {
+    type Reversed = Self;
+
+    /// Reverses the order of the numbers, keeping the placement of the NaNs, the same way
+    /// [`max_by_float`](crate::merge_iter::Builder::max_by_float) does
+    #[inline]
+    fn reverse(self) -> Self {
+        Self {
+            descending: !self.descending,
+            ..self
+        }
+    }
+}
+
+/// Calls the second comparator if the first one returns [`Ordering::Equal`].
+#[derive(Debug, Clone)]
+pub struct Chain<C1, C2> {
+    first: C1,
+    next: C2,
+}
+
+impl<C1, C2> Chain<C1, C2> {
+    /// If the first comparator returns [`Ordering::Equal`] - compare
+    /// elements using `next`.
+    ///
+    /// Similar to [`Ordering::then_with`]
+    #[inline]
+    pub const fn new<T>(first: C1, next: C2) -> Self
+    where
+        C1: Comparator<T>,
+        C2: Comparator<T>,
+        T: ?Sized,
+    {
+        Self { first, next }
+    }
+}
+
+impl<T, C1, C2> Comparator<T> for Chain<C1, C2>
+where
+    C1: Comparator<T>,
+    C2: Comparator<T>,
+    T: ?Sized,
+{
+    #[inline]
+    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+        match self.first.compare(a, b) {
+            Ordering::Equal => self.next.compare(a, b),
+            other => other,
+        }
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.first.equivalent(a, b) && self.next.equivalent(a, b)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        match self.first.compare_sources(a, b, ranks) {
+            Ordering::Equal => self.next.compare_sources(a, b, ranks),
+            other => other,
+        }
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.first.compares_ranks() || self.next.compares_ranks()
+    }
+}
+
+// Stable if the last comparator in the chain is stable
+impl<C1, C2: tie_breaker::StableAcrossSources> tie_breaker::StableAcrossSources for Chain<C1, C2> {}
+
+// Tuples of comparators apply them in order, like nested `Chain`s
+macro_rules! impl_comparator_tuple {
+    ($($c:ident),+; $last:ident) => {
+        impl<T, $($c,)+ $last> Comparator<T> for ($($c,)+ $last)
+        where
+            $($c: Comparator<T>,)+
+            $last: Comparator<T>,
+            T: ?Sized,
+        {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
+                let ($($c,)+ $last) = self;
+                $(
+                    match $c.compare(a, b) {
+                        Ordering::Equal => {}
+                        other => return other,
+                    }
+                )+
+                $last.compare(a, b)
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn equivalent(&self, a: &T, b: &T) -> bool {
+                let ($($c,)+ $last) = self;
+                $($c.equivalent(a, b) &&)+ $last.equivalent(a, b)
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+                let ($($c,)+ $last) = self;
+                $(
+                    match $c.compare_sources(a, b, ranks) {
+                        Ordering::Equal => {}
+                        other => return other,
+                    }
+                )+
+                $last.compare_sources(a, b, ranks)
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn compares_ranks(&self) -> bool {
+                let ($($c,)+ $last) = self;
+                $($c.compares_ranks() ||)+ $last.compares_ranks()
+            }
+        }
+
+        // Stable if the last comparator is stable
+        impl<$($c,)+ $last> tie_breaker::StableAcrossSources for ($($c,)+ $last)
+        where
+            $last: tie_breaker::StableAcrossSources,
+        {
+        }
+
+        impl_reversible!([$($c,)+ $last] ($($c,)+ $last));
+    };
+}
+
+impl_comparator_tuple!(C1; C2);
+impl_comparator_tuple!(C1, C2; C3);
+impl_comparator_tuple!(C1, C2, C3; C4);
+impl_comparator_tuple!(C1, C2, C3, C4; C5);
+impl_comparator_tuple!(C1, C2, C3, C4, C5; C6);
+
+/// Comparator that uses [`Ord`] to compare items, default for the [`MergeIter`](crate::MergeIter).
+///
+/// # Example
+/// Max-first merge:
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::ByOrd};
+/// let res = VecStorage::from_iter([vec![3, 2], vec![4, 1]])
+///     .into_builder()
+///     .max_by(ByOrd)
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec![4, 3, 2, 1]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByOrd;
+
+#[rustversion::attr(since(1.85), diagnostic::do_not_recommend)]
+impl<T: Ord + ?Sized> Comparator<T> for ByOrd {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        Ord::cmp(a, b)
+    }
+}
+
+/// Types that can be compared without branching, see [`ByOrdBranchless`]
+///
+/// Implemented for the primitive integers and for byte arrays.
+pub trait BranchlessCmp {
+    /// Compares two values, agreeing with their [`Ord`] implementation
+    fn branchless_cmp(&self, other: &Self) -> Ordering;
+}
+
+/// Converts the sign of `cmp` (-1, 0 or 1) into [`Ordering`], compiles into a no-op
+#[inline]
+const fn ordering_from_sign(cmp: i8) -> Ordering {
+    match cmp {
+        -1 => Ordering::Less,
+        0 => Ordering::Equal,
+        _ => Ordering::Greater,
+    }
+}
+
+macro_rules! impl_branchless_cmp {
+    ($($t:ty),*) => {$(
+        impl BranchlessCmp for $t {
+            #[inline]
+            fn branchless_cmp(&self, other: &Self) -> Ordering {
+                ordering_from_sign(i8::from(*self > *other) - i8::from(*self < *other))
+            }
+        }
+    )*};
+}
+
+impl_branchless_cmp!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl<const N: usize> BranchlessCmp for [u8; N] {
+    #[inline]
+    fn branchless_cmp(&self, other: &Self) -> Ordering {
+        // keeps the sign of the first differing pair of bytes without an early exit
+        let mut cmp = 0_i8;
+        for (a, b) in self.iter().zip(other) {
+            let byte_cmp = i8::from(a > b) - i8::from(a < b);
+            cmp |= byte_cmp & -i8::from(cmp == 0);
+        }
+        ordering_from_sign(cmp)
+    }
+}
+
+/// Comparator that uses the [`BranchlessCmp`] implementation of the items
+///
+/// Orders the items the same way as [`ByOrd`], but computes the [`Ordering`] arithmetically
+/// instead of with the conditional jumps, which are mispredicted half of the time when
+/// merging random data. In the included benchmarks over random `u64`s it's 1.3-1.7x faster
+/// with 8-64 sources and on par with `ByOrd` with 1024 sources, where the cache misses
+/// dominate. Benchmark your workload: the branches are predicted well when the sources are
+/// long runs of the items that are less (or greater) than the others.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::ByOrdBranchless};
+/// let res = VecStorage::from_iter([vec![1_u64, 4], vec![2, 3]])
+///     .into_builder()
+///     .min_by(ByOrdBranchless)
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec![1, 2, 3, 4]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByOrdBranchless;
+
+impl<T: BranchlessCmp> Comparator<T> for ByOrdBranchless {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.branchless_cmp(b)
+    }
+}
+
+/// Comparator of smart pointers (and references) that compares their pointees by [`Ord`]
+///
+/// Works with any [`Deref`](core::ops::Deref) item, like `Box<T>`, `Rc<T>`, `Arc<T>` or `&T`,
+/// even when the pointer itself doesn't implement [`Ord`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::ByDeref};
+/// let boxed = |items: [u32; 2]| items.map(Box::new).to_vec();
+/// let res = VecStorage::from_iter([boxed([1, 4]), boxed([2, 3])])
+///     .into_builder()
+///     .min_by(ByDeref)
+///     .build()
+///     .map(|item| *item);
+/// assert!(res.eq([1, 2, 3, 4]));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByDeref;
+
+impl<P> Comparator<P> for ByDeref
+where
+    P: core::ops::Deref,
+    P::Target: Ord,
+{
+    #[inline]
+    fn compare(&self, a: &P, b: &P) -> Ordering {
+        Ord::cmp(&**a, &**b)
+    }
+}
+
+/// Types with a total order that isn't their [`PartialOrd`] implementation, like the floats'
+/// [`f64::total_cmp`]
+///
+/// Implement this for other float-like types to compare them with [`ByTotalOrd`].
+pub trait TotalCmp {
+    /// Compares two values according to their total order
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl TotalCmp for f32 {
+    #[inline]
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
+
+impl TotalCmp for f64 {
+    #[inline]
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// Comparator that uses the [`TotalCmp`] implementation of the items, e.g.
+/// [`f64::total_cmp`] for floats
+///
+/// Negative NaNs are yielded before all other values and positive NaNs after them.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::ByTotalOrd};
+/// let res = VecStorage::from_iter([vec![0.0, 1.5], vec![-1.0, -0.0]])
+///     .into_builder()
+///     .min_by(ByTotalOrd)
+///     .build()
+///     .into_vec();
+/// // `==` treats the zeroes as equal, compare the bits to check their order
+/// let bits: Vec<u64> = res.iter().map(|x: &f64| x.to_bits()).collect();
+/// assert_eq!(bits, [-1.0, -0.0, 0.0, 1.5].map(f64::to_bits));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByTotalOrd;
+
+impl<T: TotalCmp> Comparator<T> for ByTotalOrd {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.total_cmp(b)
+    }
+}
+
+/// Floating point types that may be NaN, compared by [`ByFloat`]
+pub trait Float: PartialOrd {
+    /// Returns `true` if this value is NaN
+    fn is_nan(&self) -> bool;
+}
+
+impl Float for f32 {
+    #[inline]
+    fn is_nan(&self) -> bool {
+        f32::is_nan(*self)
+    }
+}
+
+impl Float for f64 {
+    #[inline]
+    fn is_nan(&self) -> bool {
+        f64::is_nan(*self)
+    }
+}
+
+/// Where [`{min|max}_by_float`](crate::merge_iter::Builder::min_by_float) places the NaNs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// NaNs are yielded before all of the numbers
+    First,
+    /// NaNs are yielded after all of the numbers
+    Last,
+    /// NaNs are skipped as they're taken from the iterators, they're never yielded
+    Skip,
+}
+
+/// Iterator of the [`Builder`](crate::merge_iter::Builder) storage after
+/// [`{min|max}_by_float`](crate::merge_iter::Builder::min_by_float), skipping NaNs if the
+/// policy is [`NanPolicy::Skip`]
+pub type NanFiltered<IT> = core::iter::Filter<IT, fn(&<IT as Iterator>::Item) -> bool>;
+
+/// Comparator of floats that orders NaNs according to the [`NanPolicy`]. Unlike
+/// [`ByTotalOrd`], signs of NaNs and zeroes are ignored.
+///
+/// Reversing it reverses the order of the numbers, NaNs stay where the policy places them.
+///
+/// Construct via [`{min|max}_by_float`](crate::merge_iter::Builder::min_by_float)
+#[derive(Debug, Clone, Copy)]
+pub struct ByFloat {
+    policy: NanPolicy,
+    descending: bool,
+}
+
+impl ByFloat {
+    #[inline]
+    pub(crate) const fn new(policy: NanPolicy, descending: bool) -> Self {
+        Self { policy, descending }
+    }
+}
+
+impl<T: Float> Comparator<T> for ByFloat {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        // Skipped NaNs never reach the comparator
+        let nan = if self.policy == NanPolicy::First {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+        let (a, b) = if self.descending { (b, a) } else { (a, b) };
+        let nan = if self.descending { nan.reverse() } else { nan };
+        match (a.is_nan(), b.is_nan()) {
+            (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (true, false) => nan,
+            (false, true) => nan.reverse(),
+            (true, true) => Ordering::Equal,
+        }
+    }
+}
+
+/// What [`ByPartialOrd`] does with the items that [`PartialOrd::partial_cmp`] can't compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Incomparable {
+    /// Incomparable items are treated as equal (and ordered by the tie breaker)
+    #[default]
+    Equal,
+    /// Items that are incomparable with themselves (like NaN) are yielded before the rest of
+    /// the items, other incomparable pairs are treated as equal
+    First,
+    /// Items that are incomparable with themselves (like NaN) are yielded after the rest of
+    /// the items, other incomparable pairs are treated as equal
+    Last,
+    /// Panic, naming the item type in the message
+    Panic,
+}
+
+/// Comparator that uses [`PartialOrd`] implementation of the items, resolving incomparable
+/// pairs according to the [`Incomparable`] policy
+///
+/// Construct via [`{min|max}_by_partial`](crate::merge_iter::Builder::min_by_partial)
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::Incomparable};
+/// let res = VecStorage::from_iter([vec![1.0, 3.0], vec![0.5, 2.0]])
+///     .into_builder()
+///     .min_by_partial(Incomparable::Panic)
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec![0.5, 1.0, 2.0, 3.0]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByPartialOrd(pub(crate) Incomparable);
+
+impl<T: PartialOrd> Comparator<T> for ByPartialOrd {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match a.partial_cmp(b) {
+            Some(ord) => ord,
+            None => match self.0 {
+                Incomparable::Equal => Ordering::Equal,
+                Incomparable::First => unordered_cmp(a, b),
+                Incomparable::Last => unordered_cmp(b, a),
+                Incomparable::Panic => panic!(
+                    "incomparable items of type {} encountered",
+                    core::any::type_name::<T>()
+                ),
+            },
+        }
+    }
+}
+
+/// Orders the items that are incomparable with themselves before the rest of the items
+#[inline]
+fn unordered_cmp<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    let unordered = |item: &T| item.partial_cmp(item).is_none();
+    match (unordered(a), unordered(b)) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Comparator that uses a function to compare items
+///
+/// Construct via [`{min|max}_by_func`](crate::merge_iter::Builder::min_by_func)
+#[derive(Debug, Clone)]
+pub struct ByFunc<F>(pub(crate) F);
+
+impl<T, F> Comparator<T> for ByFunc<F>
+where
+    F: Fn(&T, &T) -> Ordering,
+    T: ?Sized,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(a, b)
+    }
+}
+
+/// Comparator that uses a "less than" predicate to compare items, like
+/// [`itertools::kmerge_by`](https://docs.rs/itertools/latest/itertools/fn.kmerge_by.html)
+///
+/// Items are equal if neither of them is less than the other one, so the predicate is called
+/// twice for such pairs.
+///
+/// Construct via [`{min|max}_by_less_than`](crate::merge_iter::Builder::min_by_less_than)
+#[derive(Debug, Clone)]
+pub struct ByLessThan<F>(pub(crate) F);
+
+impl<F> ByLessThan<F> {
+    /// Constructs the comparator from the strict weak ordering predicate `less`: `less(a, b)`
+    /// returns `true` if `a` should be yielded before `b`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{comparators::ByLessThan, merge_with};
+    ///
+    /// let shorter = |a: &&str, b: &&str| a.len() < b.len();
+    /// let merged = merge_with([vec!["a", "ccc"], vec!["bb", "dd"]], ByLessThan::new(shorter));
+    /// assert_eq!(merged.into_vec(), vec!["a", "bb", "dd", "ccc"]);
+    /// # }
+    /// ```
+    #[inline]
+    pub const fn new<T: ?Sized>(less: F) -> Self
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        Self(less)
+    }
+}
+
+impl<T, F> Comparator<T> for ByLessThan<F>
+where
+    F: Fn(&T, &T) -> bool,
+    T: ?Sized,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        if self.0(a, b) {
+            Ordering::Less
+        } else if self.0(b, a) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+/// Comparator that uses a key to compare items
+///
+/// Construct via [`{min|max}_by_key`](crate::merge_iter::Builder::min_by_key)
+#[derive(Debug, Clone)]
+pub struct ByKey<F>(pub(crate) F);
+
+impl<F> ByKey<F> {
+    /// Constructs the comparator that compares the keys produced by `func`
+    #[inline]
+    pub const fn new<T, K>(func: F) -> Self
+    where
+        F: Fn(&T) -> K,
+        K: Ord,
+    {
+        Self(func)
+    }
+}
+
+/// Builds a comparator that compares the items by several keys, in lexicographic order:
+/// items with equal first keys are compared by the second ones and so on.
+///
+/// Every argument is a key function, see [`ByKey`]. Use [`core::cmp::Reverse`] to compare
+/// a key in descending order. The arguments of the closures must be annotated with the item
+/// type.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use core::cmp::Reverse;
+///
+/// use iter_merge::{VecStorage, keys};
+///
+/// struct Event {
+///     ts: u64,
+///     seq: u32,
+/// }
+/// let ev = |ts, seq| Event { ts, seq };
+///
+/// let merged = VecStorage::from_iter([vec![ev(1, 1), ev(2, 5)], vec![ev(1, 2), ev(2, 4)]])
+///     .into_builder()
+///     .min_by(keys!(|e: &Event| e.ts, |e: &Event| Reverse(e.seq)))
+///     .build();
+/// assert!(merged.map(|e| (e.ts, e.seq)).eq([(1, 2), (1, 1), (2, 5), (2, 4)]));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! keys {
+    ($key:expr $(,)?) => {
+        $crate::comparators::ByKey::new($key)
+    };
+    ($key:expr, $($rest:expr),+ $(,)?) => {
+        $crate::comparators::Comparator::then(
+            $crate::comparators::ByKey::new($key),
+            $crate::keys!($($rest),+),
+        )
+    };
+}
+
+impl<T, F, K> Comparator<T> for ByKey<F>
+where
+    F: Fn(&T) -> K,
+    K: Ord,
+    T:,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(a).cmp(&self.0(b))
+    }
+}
+
+/// Comparator that uses a key borrowed from the item to compare items
+///
+/// Unlike [`ByKey`], the key isn't cloned out of the item, e.g. for `String` fields.
+///
+/// Construct via [`{min|max}_by_key_ref`](crate::merge_iter::Builder::min_by_key_ref)
+#[derive(Debug, Clone)]
+pub struct ByKeyRef<F>(pub(crate) F);
+
+impl<T, F, K> Comparator<T> for ByKeyRef<F>
+where
+    F: for<'a> Fn(&'a T) -> &'a K,
+    K: Ord + ?Sized,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(a).cmp(self.0(b))
+    }
+}
+
+/// Comparator that compares the keys borrowed from the items with another comparator, e.g.
+/// compares `&str` keys case-insensitively
+///
+/// Owned keys can be compared by [`Comparator::map_key`].
+///
+/// Construct via [`ByKeyWith::new`] or
+/// [`{min|max}_by_key_with`](crate::merge_iter::Builder::min_by_key_with)
+#[derive(Debug, Clone)]
+pub struct ByKeyWith<F, C> {
+    key: F,
+    comparator: C,
+}
+
+impl<F, C> ByKeyWith<F, C> {
+    /// Constructs the comparator that compares the keys borrowed by `key` with `comparator`
+    #[inline]
+    pub const fn new<T, K>(key: F, comparator: C) -> Self
+    where
+        F: for<'a> Fn(&'a T) -> &'a K,
+        K: ?Sized,
+        C: Comparator<K>,
+    {
+        Self { key, comparator }
+    }
+}
+
+impl<T, F, K, C> Comparator<T> for ByKeyWith<F, C>
+where
+    F: for<'a> Fn(&'a T) -> &'a K,
+    K: ?Sized,
+    C: Comparator<K>,
+{
+    // Leaving decision to inline this to the compiler because F can be long
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.comparator.compare((self.key)(a), (self.key)(b))
+    }
+
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.comparator.equivalent((self.key)(a), (self.key)(b))
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        self.comparator.deduplicates()
+    }
+
+    fn is_duplicate(&self, prev: &T, next: &T) -> bool {
+        self.comparator.is_duplicate((self.key)(prev), (self.key)(next))
+    }
+
+    fn compare_sources(&self, a: &T, b: &T, ranks: (u64, u64)) -> Ordering {
+        self.comparator.compare_sources((self.key)(a), (self.key)(b), ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.comparator.compares_ranks()
+    }
+}
+
+/// Comparator that orders the items by the position of the equal value in a table, e.g. the
+/// log levels by their severity
+///
+/// The table is an array, a slice or (with the `alloc` feature) a `Vec`. Items that aren't
+/// in the table are greater than the ones that are, and equal to each other. The table is
+/// searched linearly, so it's meant for a handful of categories.
+///
+/// # Examples
+///
+/// Severity, then time:
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{
+///     VecStorage,
+///     comparators::{ByOrd, Comparator, RankTable},
+/// };
+///
+/// let by_level = RankTable::new(["ERROR", "WARN", "INFO"]).map_key(|log: &(&str, u32)| log.0);
+/// let by_time = ByOrd.map_key(|log: &(&str, u32)| log.1);
+/// let res = VecStorage::from_iter([
+///     vec![("ERROR", 5), ("INFO", 1)],
+///     vec![("ERROR", 2), ("WARN", 3), ("DEBUG", 0)],
+/// ])
+/// .into_builder()
+/// .min_by((by_level, by_time))
+/// .build()
+/// .into_vec();
+/// assert_eq!(
+///     res,
+///     vec![("ERROR", 2), ("ERROR", 5), ("WARN", 3), ("INFO", 1), ("DEBUG", 0)]
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RankTable<R>(R);
+
+impl<R> RankTable<R> {
+    /// Constructs the comparator that orders the values in the same order as in `table`
+    #[inline]
+    pub const fn new(table: R) -> Self {
+        Self(table)
+    }
+}
+
+/// Returns the position of `item` in `table` or `table.len()` if it's not there
+#[inline]
+fn rank_in<T, K>(table: &[K], item: &T) -> usize
+where
+    T: PartialEq<K> + ?Sized,
+{
+    table
+        .iter()
+        .position(|key| item == key)
+        .unwrap_or(table.len())
+}
+
+macro_rules! impl_rank_table {
+    ($(#[$attr:meta])* [$($gen:tt)*] $table:ty) => {
+        $(#[$attr])*
+        impl<$($gen)*> RankTable<$table> {
+            /// Returns the position of `item` in the table, or the length of the table if the
+            /// item isn't there
+            #[inline]
+            pub fn rank<T>(&self, item: &T) -> usize
+            where
+                T: PartialEq<K> + ?Sized,
+            {
+                rank_in(&self.0[..], item)
+            }
+        }
+
+        $(#[$attr])*
+        impl<$($gen)*, T> Comparator<T> for RankTable<$table>
+        where
+            T: PartialEq<K> + ?Sized,
+        {
+            #[inline]
+            fn compare(&self, a: &T, b: &T) -> Ordering {
+                self.rank(a).cmp(&self.rank(b))
+            }
+        }
+    };
+}
+
+impl_rank_table!([K, const N: usize] [K; N]);
+impl_rank_table!(['t, K] &'t [K]);
+impl_rank_table!(#[cfg(feature = "alloc")] [K] alloc::vec::Vec<K>);
+
+/// Comparator with a mutable state, e.g. a cache or a counter of the comparisons
+///
+/// Implemented for `FnMut(&T, &T) -> Ordering` closures. Wrap it into [`ByMut`] to use it as a
+/// [`Comparator`].
+pub trait ComparatorMut<T> {
+    /// Compares two elements and returns an [`Ordering`]
+    fn compare_mut(&mut self, a: &T, b: &T) -> Ordering;
+}
+
+impl<T, F> ComparatorMut<T> for F
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    #[inline]
+    fn compare_mut(&mut self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// [`Comparator`] that calls the [`ComparatorMut`], borrowing it mutably from a [`RefCell`]
+///
+/// Pass the reference to it to the [`Builder`](crate::merge_iter::Builder) to inspect the state
+/// of the comparator after the merge:
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use core::cmp::Ordering;
+///
+/// use iter_merge::{VecStorage, comparators::ByMut};
+///
+/// let mut comparisons = 0;
+/// let counting = ByMut::new(|a: &i32, b: &i32| -> Ordering {
+///     comparisons += 1;
+///     a.cmp(b)
+/// });
+/// let merged = VecStorage::from_iter([vec![1, 4], vec![2, 3]])
+///     .into_builder()
+///     .min_by(&counting)
+///     .build();
+/// assert_eq!(merged.into_vec(), vec![1, 2, 3, 4]);
+/// drop(counting);
+/// assert!(comparisons > 0);
+/// # }
+/// ```
+///
+/// [`ComparatorMut`] only compares the items, so the tie breakers that compare the sources of
+/// the items (e.g. [`ByPriority`](tie_breaker::ByPriority)) are chained after this comparator,
+/// e.g. `ByMut::new(func).then(ByPriority)`.
+///
+/// # Panics
+/// Comparison panics if the [`ComparatorMut`] calls itself through this comparator.
+#[derive(Debug, Clone, Default)]
+pub struct ByMut<C>(RefCell<C>);
+
+impl<C> ByMut<C> {
+    /// Wraps the stateful comparator
+    #[inline]
+    pub const fn new(comparator: C) -> Self {
+        Self(RefCell::new(comparator))
+    }
+
+    /// Returns the mutable reference to the wrapped comparator
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut C {
+        self.0.get_mut()
+    }
+
+    /// Unwraps the comparator
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.0.into_inner()
+    }
 }
 
-impl<T, C> Comparator<T> for &C
+impl<T, C> Comparator<T> for ByMut<C>
 where
-    C: Comparator<T>,
+    C: ComparatorMut<T>,
 {
     #[inline]
-    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
-        C::compare(self, a, b)
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.borrow_mut().compare_mut(a, b)
     }
 }
 
-/// Wrapper that reverses a comparator.
+/// Compares the `Option`s, `none` is the result of comparing [`None`] with a value
+#[inline]
+fn compare_options<T, C: Comparator<T>>(
+    cmp: &C, a: &Option<T>, b: &Option<T>, none: Ordering,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp.compare(a, b),
+        (None, Some(_)) => none,
+        (Some(_), None) => none.reverse(),
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compares the `Option`s like [`compare_options`], the values are compared with the
+/// [ranks](Comparator::compare_sources) of their sources
+#[inline]
+fn compare_option_sources<T, C: Comparator<T>>(
+    cmp: &C, a: &Option<T>, b: &Option<T>, none: Ordering, ranks: (u64, u64),
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp.compare_sources(a, b, ranks),
+        _ => compare_options(cmp, a, b, none),
+    }
+}
+
+#[inline]
+fn equivalent_options<T, C: Comparator<T>>(cmp: &C, a: &Option<T>, b: &Option<T>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp.equivalent(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[inline]
+fn duplicate_options<T, C: Comparator<T>>(cmp: &C, prev: &Option<T>, next: &Option<T>) -> bool {
+    match (prev, next) {
+        (Some(prev), Some(next)) => cmp.is_duplicate(prev, next),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Comparator of [`Option`]s that compares the values with the inner comparator and treats
+/// [`None`] as smaller than any value
 ///
-/// Our internal data stuctures are all min-first, so to get
-/// max-first we're just inverting the order of operands passed to
-/// comparators.
-#[derive(Debug, Clone)]
-pub struct MaxFirst<C>(pub(crate) C);
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::{ByOrd, NoneFirst}};
+///
+/// let res = VecStorage::from_iter([vec![None, Some(2)], vec![Some(1), Some(3)]])
+///     .into_builder()
+///     .min_by(NoneFirst::new(ByOrd))
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec![None, Some(1), Some(2), Some(3)]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NoneFirst<C>(C);
 
-impl<C> MaxFirst<C> {
+impl<C> NoneFirst<C> {
+    /// Wraps the comparator of the values
     #[inline]
-    #[doc(hidden)]
-    pub const fn new<T>(comparator: C) -> Self
-    where
-        C: Comparator<T>,
-    {
+    pub const fn new(comparator: C) -> Self {
         Self(comparator)
     }
 }
 
-impl<T, C> Comparator<T> for MaxFirst<C>
-where
-    C: Comparator<T>,
-{
+impl<T, C: Comparator<T>> Comparator<Option<T>> for NoneFirst<C> {
     #[inline]
-    fn compare(&self, a: &T, b: &T) -> Ordering {
-        self.0.compare(b, a)
+    fn compare(&self, a: &Option<T>, b: &Option<T>) -> Ordering {
+        compare_options(&self.0, a, b, Ordering::Less)
     }
-}
 
-/// Calls the second comparator if the first one returns [`Ordering::Equal`].
-#[derive(Debug, Clone)]
-pub struct Chain<C1, C2> {
-    first: C1,
-    next: C2,
-}
+    #[inline]
+    fn equivalent(&self, a: &Option<T>, b: &Option<T>) -> bool {
+        equivalent_options(&self.0, a, b)
+    }
 
-impl<C1, C2> Chain<C1, C2> {
-    /// If the first comparator returns [`Ordering::Equal`] - compare
-    /// elements using `next`.
-    ///
-    /// Similar to [`Ordering::then_with`]
     #[inline]
-    pub const fn new<T>(first: C1, next: C2) -> Self
-    where
-        C1: Comparator<T>,
-        C2: Comparator<T>,
-        T:,
-    {
-        Self { first, next }
+    fn deduplicates(&self) -> bool {
+        self.0.deduplicates()
     }
-}
 
-impl<T, C1, C2> Comparator<T> for Chain<C1, C2>
-where
-    C1: Comparator<T>,
-    C2: Comparator<T>,
-    T:,
-{
     #[inline]
-    fn compare<'a>(&self, a: &'a T, b: &'a T) -> Ordering {
-        match self.first.compare(a, b) {
-            Ordering::Equal => self.next.compare(a, b),
-            other => other,
-        }
+    fn is_duplicate(&self, prev: &Option<T>, next: &Option<T>) -> bool {
+        duplicate_options(&self.0, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &Option<T>, b: &Option<T>, ranks: (u64, u64)) -> Ordering {
+        compare_option_sources(&self.0, a, b, Ordering::Less, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.0.compares_ranks()
     }
 }
 
-/// Comparator that uses [`Ord`] to compare items, default for the [`MergeIter`](crate::MergeIter).
+/// Comparator of [`Option`]s that compares the values with the inner comparator and treats
+/// [`None`] as larger than any value, e.g. for the gap markers that shouldn't overtake the
+/// data
 ///
 /// # Example
-/// Max-first merge:
-///
 /// ```
 /// # #[cfg(feature = "alloc")]
 /// # {
-/// use iter_merge::{VecStorage, comparators::ByOrd};
-/// let res = VecStorage::from_iter([vec![3, 2], vec![4, 1]])
+/// use iter_merge::{VecStorage, comparators::{ByOrd, NoneLast}};
+///
+/// let res = VecStorage::from_iter([vec![Some(2), None], vec![Some(1), Some(3)]])
 ///     .into_builder()
-///     .max_by(ByOrd)
+///     .min_by(NoneLast::new(ByOrd))
 ///     .build()
 ///     .into_vec();
-/// assert_eq!(res, vec![4, 3, 2, 1]);
+/// assert_eq!(res, vec![Some(1), Some(2), Some(3), None]);
 /// # }
 /// ```
 #[derive(Debug, Clone, Copy)]
-pub struct ByOrd;
+pub struct NoneLast<C>(C);
 
-impl<T: Ord> Comparator<T> for ByOrd {
+impl<C> NoneLast<C> {
+    /// Wraps the comparator of the values
     #[inline]
-    fn compare(&self, a: &T, b: &T) -> Ordering {
-        Ord::cmp(a, b)
+    pub const fn new(comparator: C) -> Self {
+        Self(comparator)
     }
 }
 
-/// Comparator that uses a function to compare items
+impl<T, C: Comparator<T>> Comparator<Option<T>> for NoneLast<C> {
+    #[inline]
+    fn compare(&self, a: &Option<T>, b: &Option<T>) -> Ordering {
+        compare_options(&self.0, a, b, Ordering::Greater)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &Option<T>, b: &Option<T>) -> bool {
+        equivalent_options(&self.0, a, b)
+    }
+
+    #[inline]
+    fn deduplicates(&self) -> bool {
+        self.0.deduplicates()
+    }
+
+    #[inline]
+    fn is_duplicate(&self, prev: &Option<T>, next: &Option<T>) -> bool {
+        duplicate_options(&self.0, prev, next)
+    }
+
+    #[inline]
+    fn compare_sources(&self, a: &Option<T>, b: &Option<T>, ranks: (u64, u64)) -> Ordering {
+        compare_option_sources(&self.0, a, b, Ordering::Greater, ranks)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        self.0.compares_ranks()
+    }
+}
+
+/// Comparator of `(key, item)` pairs that compares only the keys
 ///
-/// Construct via [`{min|max}_by_func`](crate::merge_iter::Builder::min_by_func)
-#[derive(Debug, Clone)]
-pub struct ByFunc<F>(pub(crate) F);
+/// Construct via [`{min|max}_by_cached_key`](crate::merge_iter::Builder::min_by_cached_key)
+#[derive(Debug, Clone, Copy)]
+pub struct ByCachedKey;
 
-impl<T, F> Comparator<T> for ByFunc<F>
-where
-    F: Fn(&T, &T) -> Ordering,
-    T:,
-{
-    // Leaving decision to inline this to the compiler because F can be long
-    fn compare(&self, a: &T, b: &T) -> Ordering {
-        self.0(a, b)
+impl<K: Ord, T> Comparator<(K, T)> for ByCachedKey {
+    #[inline]
+    fn compare(&self, a: &(K, T), b: &(K, T)) -> Ordering {
+        a.0.cmp(&b.0)
     }
 }
 
-/// Comparator that uses a key to compare items
+/// Iterator yielding the items of the inner iterator paired with their keys, compared by
+/// [`ByCachedKey`]
 ///
-/// Construct via [`{min|max}_by_key`](crate::merge_iter::Builder::min_by_key)
+/// Construct via [`{min|max}_by_cached_key`](crate::merge_iter::Builder::min_by_cached_key)
 #[derive(Debug, Clone)]
-pub struct ByKey<F>(pub(crate) F);
+pub struct CachedKeys<IT, F> {
+    pub(crate) iter: IT,
+    pub(crate) key: F,
+}
 
-impl<T, F, K> Comparator<T> for ByKey<F>
+impl<IT, F, K> Iterator for CachedKeys<IT, F>
 where
-    F: Fn(&T) -> K,
-    K: Ord,
-    T:,
+    IT: Iterator,
+    F: FnMut(&IT::Item) -> K,
 {
-    // Leaving decision to inline this to the compiler because F can be long
-    fn compare(&self, a: &T, b: &T) -> Ordering {
-        self.0(a).cmp(&self.0(b))
+    type Item = (K, IT::Item);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        Some(((self.key)(&item), item))
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<IT, F, K> core::iter::ExactSizeIterator for CachedKeys<IT, F>
+where
+    IT: core::iter::ExactSizeIterator,
+    F: FnMut(&IT::Item) -> K,
+{
+}
+
+impl<IT, F, K> core::iter::FusedIterator for CachedKeys<IT, F>
+where
+    IT: core::iter::FusedIterator,
+    F: FnMut(&IT::Item) -> K,
+{
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+
+    /// Merges the sources with the priorities 0 and 1 with the comparator `cmp`, see
+    /// [`ByPriority`](tie_breaker::ByPriority)
+    #[cfg(feature = "alloc")]
+    fn merge_prioritized<T, C: Comparator<T>>(
+        sources: [alloc::vec::Vec<T>; 2], cmp: C,
+    ) -> alloc::vec::Vec<T> {
+        let mut storage = crate::VecStorage::new();
+        for (priority, source) in (0..).zip(sources) {
+            storage.push_with_priority(source, priority);
+        }
+        storage.into_builder().min_by(cmp).build().into_vec()
+    }
+
     #[test]
     fn comparators() {
         let [a, b] = [1_i32, 2];
@@ -210,4 +1866,410 @@ mod tests {
             .is_eq()
         );
     }
+
+    #[test]
+    fn by_partial_ord() {
+        let [a, b, nan] = [1.0_f64, 2.0, f64::NAN];
+        assert!(ByPartialOrd(Incomparable::Panic).compare(&a, &b).is_lt());
+        assert!(ByPartialOrd(Incomparable::Equal).compare(&nan, &b).is_eq());
+        for (policy, nan_first) in [(Incomparable::First, true), (Incomparable::Last, false)] {
+            let cmp = ByPartialOrd(policy);
+            assert_eq!(cmp.compare(&nan, &b).is_lt(), nan_first);
+            assert_eq!(cmp.compare(&b, &nan).is_lt(), !nan_first);
+            assert!(cmp.compare(&nan, &nan).is_eq());
+            assert!(cmp.compare(&a, &b).is_lt());
+        }
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::vec;
+
+            let res = crate::VecStorage::from_iter([vec![1.0, 3.0, nan], vec![2.0, nan]])
+                .into_builder()
+                .min_by_partial(Incomparable::Last)
+                .build()
+                .into_vec();
+            assert_eq!(res[..3], [1.0, 2.0, 3.0]);
+            assert!(res[3..].iter().all(|x| x.is_nan()));
+        }
+        assert!(MaxFirst(ByPartialOrd(Incomparable::Panic)).compare(&a, &b).is_gt());
+    }
+
+    #[test]
+    #[should_panic(expected = "incomparable items of type f64")]
+    fn by_partial_ord_panic() {
+        let _ = ByPartialOrd(Incomparable::Panic).compare(&1.0, &f64::NAN);
+    }
+
+    #[test]
+    fn by_ord_branchless() {
+        let values = [i64::MIN, -7, -1, 0, 1, 7, i64::MAX];
+        for a in values {
+            for b in values {
+                assert_eq!(ByOrdBranchless.compare(&a, &b), a.cmp(&b));
+                let (a, b) = (a as u8, b as u8);
+                assert_eq!(ByOrdBranchless.compare(&a, &b), a.cmp(&b));
+            }
+        }
+        let arrays = [[0_u8, 0, 0], [0, 0, 1], [0, 255, 0], [1, 0, 0], [255, 255, 255]];
+        for a in arrays {
+            for b in arrays {
+                assert_eq!(ByOrdBranchless.compare(&a, &b), a.cmp(&b));
+            }
+        }
+        assert!(ByOrdBranchless.compare(&[0_u8; 0], &[]).is_eq());
+        assert!(ByOrdBranchless.reverse().compare(&1_u32, &2).is_gt());
+    }
+
+    #[test]
+    fn by_deref() {
+        let (a, b) = (1, 2);
+        assert!(ByDeref.compare(&&a, &&b).is_lt());
+        assert!(ByDeref.reverse().compare(&&a, &&b).is_gt());
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::{boxed::Box, rc::Rc, string::String, sync::Arc};
+
+            assert!(ByDeref.compare(&Box::new(2), &Box::new(1)).is_gt());
+            assert!(ByDeref.equivalent(&Rc::new(1), &Rc::new(1)));
+            let (a, b) = (Arc::new(String::from("a")), Arc::new(String::from("b")));
+            assert!(ByDeref.compare(&a, &b).is_lt());
+            // unsized pointees
+            let (a, b): (Box<str>, Box<str>) = ("b".into(), "ab".into());
+            assert!(ByDeref.compare(&a, &b).is_gt());
+        }
+    }
+
+    #[test]
+    fn by_total_ord() {
+        assert!(ByTotalOrd.compare(&-0.0_f64, &0.0).is_lt());
+        assert!(ByTotalOrd.compare(&f32::NAN, &f32::INFINITY).is_gt());
+        assert!(ByTotalOrd.compare(&-f64::NAN, &f64::NEG_INFINITY).is_lt());
+        assert!(ByTotalOrd.compare(&f64::NAN, &f64::NAN).is_eq());
+        assert!(ByTotalOrd.reverse().compare(&1.0_f32, &2.0).is_gt());
+    }
+
+    #[test]
+    fn by_float() {
+        let first = ByFloat::new(NanPolicy::First, false);
+        let last = ByFloat::new(NanPolicy::Last, false);
+        assert!(first.compare(&f64::NAN, &f64::NEG_INFINITY).is_lt());
+        assert!(first.compare(&1.0, &-f64::NAN).is_gt());
+        assert!(last.compare(&-f32::NAN, &f32::INFINITY).is_gt());
+        assert!(last.compare(&f32::NAN, &-f32::NAN).is_eq());
+        assert!(last.compare(&-0.0, &0.0).is_eq());
+        assert!(last.compare(&1.0, &2.0).is_lt());
+        let (first, last) = (first.reverse(), last.reverse());
+        assert!(first.compare(&f64::NAN, &f64::INFINITY).is_lt());
+        assert!(first.compare(&1.0, &2.0).is_gt());
+        assert!(last.compare(&f32::NAN, &f32::NEG_INFINITY).is_gt());
+        assert!(last.reverse().compare(&f32::NAN, &f32::INFINITY).is_gt());
+        assert!(last.reverse().compare(&1.0, &2.0).is_lt());
+    }
+
+    #[test]
+    fn by_key_ref() {
+        fn name(v: &([u8; 2], u8)) -> &[u8] {
+            &v.0
+        }
+        let by_name = ByKeyRef(name);
+        let [a, b] = [(*b"ab", 2), (*b"ba", 1)];
+        assert!(by_name.compare(&a, &b).is_lt());
+        assert!(by_name.equivalent(&a, &(*b"ab", 3)));
+        assert!(by_name.reverse().compare(&a, &b).is_gt());
+    }
+
+    #[test]
+    fn by_key_with() {
+        let ignore_case = ByLessThan::new(|a: &str, b: &str| {
+            a.bytes()
+                .map(|c| c.to_ascii_lowercase())
+                .lt(b.bytes().map(|c| c.to_ascii_lowercase()))
+        });
+        let by_name = ByKeyWith::new(|v: &(&str, u8)| v.0, ignore_case);
+        let [a, b] = [("Ab", 2), ("aC", 1)];
+        assert!(by_name.compare(&a, &b).is_lt());
+        assert!(by_name.equivalent(&a, &("aB", 3)));
+        assert!(by_name.reverse().compare(&a, &b).is_gt());
+        let by_prefix = ByKeyWith::new(|v: &[u8; 3]| &v[..2], ByOrd);
+        assert!(by_prefix.equivalent(&[1, 2, 3], &[1, 2, 0]));
+        assert!(by_prefix.compare(&[1, 2, 3], &[1, 3, 0]).is_lt());
+    }
+
+    #[test]
+    fn by_key_with_sources() {
+        fn key(v: &(u8, char)) -> &u8 {
+            &v.0
+        }
+        let by_priority = ByKeyWith::new(key, (ByOrd, tie_breaker::ByPriority));
+        assert!(by_priority.compares_ranks());
+        assert!(by_priority.compare(&(1, 'a'), &(1, 'b')).is_eq());
+        assert!(by_priority.compare_sources(&(1, 'a'), &(1, 'b'), (0, 1)).is_gt());
+        assert!(by_priority.compare_sources(&(0, 'a'), &(1, 'b'), (0, 1)).is_lt());
+        let dedup = ByKeyWith::new(key, Deduplicated(ByOrd));
+        assert!(dedup.deduplicates());
+        assert!(dedup.is_duplicate(&(1, 'a'), &(1, 'b')));
+        assert!(!dedup.is_duplicate(&(1, 'a'), &(2, 'a')));
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::vec;
+
+            let sources = [vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (3, 'b')]];
+            let res = merge_prioritized(sources, by_priority);
+            assert_eq!(res, [(1, 'b'), (1, 'a'), (2, 'a'), (3, 'b')]);
+        }
+    }
+
+    #[test]
+    fn rank_table() {
+        let levels = RankTable::new(["ERROR", "WARN", "INFO"]);
+        assert_eq!(levels.rank(&"WARN"), 1);
+        assert_eq!(levels.rank(&"TRACE"), 3);
+        assert!(levels.compare(&"ERROR", &"INFO").is_lt());
+        assert!(levels.compare(&"DEBUG", &"INFO").is_gt());
+        assert!(levels.equivalent(&"DEBUG", &"TRACE"));
+        assert!(levels.reverse().compare(&"ERROR", &"WARN").is_gt());
+        let digits = [3, 1, 2];
+        let by_slice = RankTable::new(&digits[..]);
+        assert!(by_slice.compare(&3, &1).is_lt());
+        assert!(by_slice.compare(&0, &2).is_gt());
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::{string::String, vec};
+
+            let by_vec = RankTable::new(vec!["b", "a"]);
+            assert!(by_vec.compare(&String::from("b"), &String::from("a")).is_lt());
+        }
+    }
+
+    #[test]
+    fn by_mut() {
+        struct Memo {
+            calls: usize,
+            last: Option<(i32, i32, Ordering)>,
+        }
+        impl ComparatorMut<i32> for Memo {
+            fn compare_mut(&mut self, a: &i32, b: &i32) -> Ordering {
+                if let Some((x, y, ord)) = self.last {
+                    if (x, y) == (*a, *b) {
+                        return ord;
+                    }
+                }
+                self.calls += 1;
+                let ord = a.cmp(b);
+                self.last = Some((*a, *b, ord));
+                ord
+            }
+        }
+        let mut cmp = ByMut::new(Memo {
+            calls: 0,
+            last: None,
+        });
+        assert!(cmp.compare(&1, &2).is_lt());
+        assert!(cmp.compare(&1, &2).is_lt());
+        assert!(Comparator::equivalent(&&cmp, &3, &3));
+        assert_eq!(cmp.get_mut().calls, 2);
+        let cmp = cmp.reverse();
+        assert!(cmp.compare(&1, &2).is_gt());
+        assert_eq!(cmp.0.into_inner().calls, 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn by_mut_sources() {
+        use alloc::vec;
+
+        let by_key = |a: &(u8, char), b: &(u8, char)| a.0.cmp(&b.0);
+        let sources = [vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (3, 'b')]];
+        let res = merge_prioritized(sources, ByMut::new(by_key).then(tie_breaker::ByPriority));
+        assert_eq!(res, [(1, 'b'), (1, 'a'), (2, 'a'), (3, 'b')]);
+    }
+
+    #[test]
+    fn none_first_last() {
+        let first = NoneFirst::new(ByOrd);
+        let last = NoneLast::new(MaxFirst(ByOrd));
+        assert!(first.compare(&None, &Some(i32::MIN)).is_lt());
+        assert!(first.compare(&Some(2), &Some(1)).is_gt());
+        assert!(first.compare(&None::<i32>, &None).is_eq());
+        assert!(last.compare(&None, &Some(i32::MAX)).is_gt());
+        assert!(last.compare(&Some(2), &None).is_lt());
+        assert!(last.compare(&Some(2), &Some(1)).is_lt());
+        assert!(last.equivalent(&None::<i32>, &None));
+        assert!(!last.equivalent(&Some(1), &None));
+        assert!(first.reverse().compare(&None, &Some(1)).is_gt());
+    }
+
+    #[test]
+    fn none_first_last_sources() {
+        let first = NoneFirst::new((ByOrd, tie_breaker::ByPriority));
+        let last = NoneLast::new((ByOrd, tie_breaker::ByPriority));
+        assert!(Comparator::<Option<u8>>::compares_ranks(&first));
+        assert!(Comparator::<Option<u8>>::compares_ranks(&last));
+        assert!(first.compare(&Some(1), &Some(1)).is_eq());
+        assert!(first.compare_sources(&Some(1), &Some(1), (0, 1)).is_gt());
+        assert!(first.compare_sources(&None, &Some(1), (0, 1)).is_lt());
+        assert!(last.compare_sources(&Some(1), &Some(1), (1, 0)).is_lt());
+        assert!(last.compare_sources(&None, &Some(1), (1, 0)).is_gt());
+        assert!(last.compare_sources(&None::<i32>, &None, (0, 1)).is_eq());
+        let first_dedup: &dyn Comparator<Option<i32>> = &NoneFirst::new(Deduplicated(ByOrd));
+        let last_dedup: &dyn Comparator<Option<i32>> = &NoneLast::new(Deduplicated(ByOrd));
+        for dedup in [first_dedup, last_dedup] {
+            assert!(dedup.deduplicates());
+            assert!(dedup.is_duplicate(&Some(1), &Some(1)));
+            assert!(dedup.is_duplicate(&None, &None));
+            assert!(!dedup.is_duplicate(&Some(1), &None));
+            assert!(!dedup.is_duplicate(&Some(1), &Some(2)));
+        }
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::vec;
+
+            fn value(item: &(Option<u8>, char)) -> &Option<u8> {
+                &item.0
+            }
+            let sources = [vec![(None, 'a'), (Some(1), 'a')], vec![(None, 'b'), (Some(1), 'b')]];
+            let res = merge_prioritized(sources, ByKeyWith::new(value, first));
+            assert_eq!(res, [(None, 'a'), (None, 'b'), (Some(1), 'b'), (Some(1), 'a')]);
+            let sources = [vec![(Some(1), 'a'), (None, 'a')], vec![(Some(1), 'b'), (None, 'b')]];
+            let res = merge_prioritized(sources, ByKeyWith::new(value, last));
+            assert_eq!(res, [(Some(1), 'b'), (Some(1), 'a'), (None, 'a'), (None, 'b')]);
+        }
+    }
+
+    #[test]
+    fn keys_macro() {
+        let cmp = crate::keys!(|v: &(u8, i8, char)| v.0, |v: &(u8, i8, char)| -v.1,);
+        assert!(cmp.compare(&(1, 5, 'a'), &(2, 9, 'a')).is_lt());
+        assert!(cmp.compare(&(1, 5, 'a'), &(1, 9, 'a')).is_gt());
+        assert!(cmp.equivalent(&(1, 5, 'a'), &(1, 5, 'b')));
+        let single = crate::keys!(|v: &(u8, i8)| v.1);
+        assert!(single.compare(&(1, 5), &(2, 3)).is_gt());
+    }
+
+    #[test]
+    fn by_less_than() {
+        let lt = ByLessThan::new(|a: &i32, b: &i32| a < b);
+        assert!(lt.compare(&1, &2).is_lt());
+        assert!(lt.compare(&2, &1).is_gt());
+        assert!(lt.compare(&2, &2).is_eq());
+        assert!(MaxFirst(lt).compare(&1, &2).is_gt());
+    }
+
+    #[test]
+    fn dyn_comparators() {
+        let a = ByOrd;
+        let cmp: &dyn Comparator<i32> = &a;
+        assert!(cmp.compare(&1, &2).is_lt());
+        assert!((&cmp).equivalent(&2, &2));
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::boxed::Box;
+
+            let cmp: DynComparator<'_, i32> = Box::new(MaxFirst(ByOrd));
+            assert!(cmp.compare(&1, &2).is_gt());
+            assert!(Chain::new(cmp, tie_breaker::InsertionOrder).compare(&2, &1).is_lt());
+        }
+    }
+
+    #[test]
+    fn shared_comparators() {
+        type Func = dyn Fn(&i32, &i32) -> Ordering;
+
+        fn desc(a: &i32, b: &i32) -> Ordering {
+            b.cmp(a)
+        }
+        let ptr: fn(&i32, &i32) -> Ordering = desc;
+        assert!(ptr.compare(&1, &2).is_gt());
+        assert!(ptr.equivalent(&2, &2));
+        let closure: &Func = &|a, b| a.cmp(b);
+        assert!(closure.compare(&1, &2).is_lt());
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::{boxed::Box, rc::Rc, sync::Arc};
+
+            type SyncFunc = dyn Fn(&i32, &i32) -> Ordering + Send + Sync;
+
+            let boxed: Box<Func> = Box::new(desc);
+            assert!(boxed.compare(&1, &2).is_gt());
+            let rc = Rc::new(ByOrd);
+            assert!(Chain::new(Rc::clone(&rc), ptr).compare(&1, &2).is_lt());
+            assert!(rc.compare(&1, &2).is_lt());
+            let arc: Arc<SyncFunc> = Arc::new(desc);
+            let shared = Arc::clone(&arc);
+            assert!(shared.compare(&1, &2).is_gt());
+            assert!(arc.equivalent(&1, &1));
+        }
+    }
+
+    #[test]
+    fn tuples() {
+        fn is_stable<C: tie_breaker::StableAcrossSources>(_: &C) {}
+
+        let by_abs = ByOrd.map_key(|v: &i32| v.abs());
+        let cmp = (by_abs, ByOrd);
+        assert!(cmp.compare(&-2, &2).is_lt());
+        assert!(cmp.compare(&-3, &2).is_gt());
+        assert!(!cmp.equivalent(&-2, &2));
+        let cmp = (
+            ByOrd.map_key(|v: &(u8, u8, u8)| v.0),
+            tie_breaker::Unspecified,
+            MaxFirst(ByOrd),
+        );
+        assert!(cmp.compare(&(1, 0, 0), &(2, 9, 9)).is_lt());
+        assert!(cmp.compare(&(1, 0, 0), &(1, 9, 9)).is_gt());
+        assert!(cmp.equivalent(&(1, 2, 3), &(1, 2, 3)));
+        assert!(cmp.reverse().compare(&(1, 0, 0), &(2, 0, 0)).is_gt());
+        is_stable(&(ByOrd, tie_breaker::InsertionOrder));
+    }
+
+    #[test]
+    fn map_key_sources() {
+        let by_priority = Chain::new::<u8>(ByOrd, tie_breaker::ByPriority)
+            .map_key(|item: &(u8, char)| item.0);
+        assert!(by_priority.compares_ranks());
+        assert!(by_priority.compare(&(1, 'a'), &(1, 'b')).is_eq());
+        assert!(by_priority.compare_sources(&(1, 'a'), &(1, 'b'), (0, 1)).is_gt());
+        assert!(by_priority.compare_sources(&(0, 'a'), &(1, 'b'), (0, 1)).is_lt());
+        let dedup = Deduplicated(ByOrd).map_key(|item: &(u8, char)| item.0);
+        assert!(dedup.deduplicates());
+        assert!(dedup.is_duplicate(&(1, 'a'), &(1, 'b')));
+        assert!(!dedup.is_duplicate(&(1, 'a'), &(2, 'a')));
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::vec;
+
+            let sources = || [vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (3, 'b')]];
+            let res = merge_prioritized(sources(), by_priority);
+            assert_eq!(res, [(1, 'b'), (1, 'a'), (2, 'a'), (3, 'b')]);
+            let [first, second] = sources();
+            let merged = crate::merge([first, second]).with_comparator(dedup);
+            assert!(merged.map(|item| item.0).eq([1, 2, 3]));
+        }
+    }
+
+    #[test]
+    fn combinators() {
+        let by_abs = ByOrd.map_key(|v: &i32| v.abs());
+        assert!(by_abs.compare(&-3, &2).is_gt());
+        assert!(by_abs.equivalent(&-2, &2));
+        let cmp = by_abs.then(ByOrd.reverse());
+        assert!(cmp.compare(&-2, &2).is_gt());
+        assert!(cmp.compare(&-1, &2).is_lt());
+        assert!(!cmp.equivalent(&-2, &2));
+    }
+
+    #[test]
+    fn reverse() {
+        let [a, b] = [1_i32, 2];
+        let max = ByOrd.reverse();
+        assert!(Comparator::compare(&max, &a, &b).is_gt());
+        let min: ByOrd = max.reverse();
+        assert!(Comparator::compare(&min, &a, &b).is_lt());
+        let by_key = ByKey(|v: &i32| -v).reverse();
+        assert!(Comparator::compare(&by_key, &a, &b).is_lt());
+        let by_func = ByFunc(|a: &i32, b: &i32| a.cmp(b)).reverse();
+        assert!(Comparator::compare(&by_func, &a, &b).is_gt());
+        let twice = ByOrd.map_key(|v: &i32| *v).reverse().reverse();
+        assert!(twice.compare(&a, &b).is_lt());
+    }
 }
@@ -0,0 +1,91 @@
+//! Locale-aware collation of strings, enabled by the `collation` feature
+
+use core::cmp::Ordering;
+
+use icu_collator::Collator;
+
+use super::{Comparator, MaxFirst, Reversible};
+
+/// Comparator of strings that orders them according to the collation rules of a locale,
+/// implemented by the [`icu_collator`] crate
+///
+/// Compares any items that implement [`AsRef<str>`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use icu_collator::{Collator, CollatorOptions};
+/// use iter_merge::{VecStorage, comparators::Collated};
+///
+/// // in Swedish "ö" is a separate letter, sorted after "z"
+/// let swedish = Collator::try_new(&"sv".parse().unwrap(), CollatorOptions::new()).unwrap();
+/// let res = VecStorage::from_iter([vec!["apa", "zon"], vec!["öl"]])
+///     .into_builder()
+///     .min_by(Collated::new(swedish))
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec!["apa", "zon", "öl"]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Collated(Collator);
+
+impl Collated {
+    /// Constructs the comparator that compares the strings with `collator`
+    #[inline]
+    pub const fn new(collator: Collator) -> Self {
+        Self(collator)
+    }
+
+    /// Returns the wrapped collator
+    #[inline]
+    pub fn into_inner(self) -> Collator {
+        self.0
+    }
+}
+
+impl From<Collator> for Collated {
+    #[inline]
+    fn from(collator: Collator) -> Self {
+        Self(collator)
+    }
+}
+
+impl<T: AsRef<str> + ?Sized> Comparator<T> for Collated {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(a.as_ref(), b.as_ref())
+    }
+}
+
+impl_reversible!([] Collated);
+
+#[cfg(test)]
+mod tests {
+    use icu_collator::{CollatorOptions, Strength};
+
+    use super::*;
+
+    fn collated(locale: &str, strength: Option<Strength>) -> Collated {
+        let mut options = CollatorOptions::new();
+        options.strength = strength;
+        Collator::try_new(&locale.parse().unwrap(), options)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn collated_locales() {
+        let root = collated("und", None);
+        assert!(root.compare("öl", "zon").is_lt());
+        assert!(root.compare("a", "B").is_lt());
+        assert!(root.compare("résumé", "resume").is_gt());
+        let swedish = collated("sv", None);
+        assert!(swedish.compare("öl", "zon").is_gt());
+        assert!(swedish.reverse().compare("öl", "zon").is_lt());
+        let primary = collated("und", Some(Strength::Primary));
+        assert!(primary.equivalent("Résumé", "resume"));
+    }
+}
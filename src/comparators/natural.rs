@@ -0,0 +1,113 @@
+//! Natural ordering of strings, enabled by the `natural` feature
+
+use core::cmp::Ordering;
+
+use super::{Comparator, MaxFirst, Reversible};
+
+/// Comparator of strings that compares the runs of digits by their numeric values:
+/// `"file2" < "file10"`
+///
+/// The rest of the characters are compared by their code points. Numbers that differ only in
+/// the leading zeroes are equal, if there's no other difference the string with fewer leading
+/// zeroes in the first such number is the smaller one: `"a1" < "a01"`.
+///
+/// Compares any items that implement [`AsRef<str>`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::Natural};
+///
+/// let res = VecStorage::from_iter([vec!["log2.txt", "log10.txt"], vec!["log9.txt"]])
+///     .into_builder()
+///     .min_by(Natural)
+///     .build()
+///     .into_vec();
+/// assert_eq!(res, vec!["log2.txt", "log9.txt", "log10.txt"]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Natural;
+
+impl<T: AsRef<str> + ?Sized> Comparator<T> for Natural {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        natural_cmp(a.as_ref().as_bytes(), b.as_ref().as_bytes())
+    }
+}
+
+impl_reversible!([] Natural);
+
+/// Returns the end of the run of digits starting at `start`
+fn digits_end(s: &[u8], start: usize) -> usize {
+    s[start..]
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .map_or(s.len(), |len| start + len)
+}
+
+/// Returns the position of the first non-zero digit of the run, or of its last digit
+fn significant_start(s: &[u8], start: usize, end: usize) -> usize {
+    s[start..end - 1]
+        .iter()
+        .position(|&c| c != b'0')
+        .map_or(end - 1, |len| start + len)
+}
+
+// Bytewise comparison of UTF-8 is the same as the comparison of code points, digits are ASCII
+// so they're never a part of multibyte characters
+fn natural_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    // Difference in the leading zeroes of the first number that has them
+    let mut zeroes = Ordering::Equal;
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let (end_a, end_b) = (digits_end(a, i), digits_end(b, j));
+            let (sig_a, sig_b) = (
+                significant_start(a, i, end_a),
+                significant_start(b, j, end_b),
+            );
+            let (num_a, num_b) = (&a[sig_a..end_a], &b[sig_b..end_b]);
+            match num_a.len().cmp(&num_b.len()).then_with(|| num_a.cmp(num_b)) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+            if zeroes.is_eq() {
+                zeroes = (sig_a - i).cmp(&(sig_b - j));
+            }
+            (i, j) = (end_a, end_b);
+        } else {
+            match a[i].cmp(&b[j]) {
+                Ordering::Equal => (i, j) = (i + 1, j + 1),
+                other => return other,
+            }
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j)).then(zeroes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural() {
+        let cmp = |a: &str, b: &str| Natural.compare(&a, &b);
+        assert!(cmp("file2", "file10").is_lt());
+        assert!(cmp("file10", "file9").is_gt());
+        assert!(cmp("a1b2", "a1b10").is_lt());
+        assert!(cmp("a01", "a1").is_gt());
+        assert!(cmp("a01b", "a1c").is_lt());
+        assert!(cmp("a001x2", "a01x02").is_gt());
+        assert!(cmp("0", "00").is_lt());
+        assert!(cmp("abc", "abc").is_eq());
+        assert!(cmp("ab", "abc").is_lt());
+        assert!(cmp("a", "1").is_gt());
+        assert!(cmp("10", "9a").is_gt());
+        assert!(cmp("é2", "é10").is_lt());
+        assert!(cmp("", "").is_eq());
+        assert!(Natural.reverse().compare(&"x2", &"x10").is_gt());
+    }
+}
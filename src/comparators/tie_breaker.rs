@@ -9,6 +9,56 @@
 //! [`Unspecified`] tie-breaker always returns [`Ordering::Equal`]. This makes the
 //! [`MergeIter`](crate::MergeIter) a bit faster, but the order of polled iterators with equal
 //! items is unstable (may change if the initial iterator list is modified in any way)
+//!
+//! [`ByPriority`] ignores the order of insertion: it compares the priorities of the sources,
+//! stored once per source in the storage.
+//!
+//! [`BySequence`] compares the explicit sequence numbers of the sources, stored once per
+//! source like the priorities. Unlike the address-based tie breakers, it doesn't depend on the
+//! layout of the storage.
+//!
+//! # Stability
+//! [`MergeIter`](crate::MergeIter) provides two distinct guarantees:
+//! * stability within a source: items of one iterator always keep their relative order.
+//!   This is inherent and holds for every comparator.
+//! * stability across sources: equal items from different iterators are yielded in the order
+//!   of insertion of their iterators. This is provided by the tie breaker, comparators that
+//!   guarantee it implement [`StableAcrossSources`].
+//!
+//! Functions that rely on the latter can require it in their signature:
+//! ```
+//! # #[cfg(feature = "alloc")]
+//! # {
+//! use iter_merge::{
+//!     MergeIter, VecStorage,
+//!     comparators::{Comparator, tie_breaker::StableAcrossSources},
+//!     storage::Storage,
+//! };
+//!
+//! fn collect_stable<S, CMP>(iter: MergeIter<S, CMP>) -> Vec<(u8, char)>
+//! where
+//!     S: Storage,
+//!     S::IT: Iterator<Item = (u8, char)>,
+//!     CMP: Comparator<(u8, char)> + StableAcrossSources,
+//! {
+//!     iter.into_vec()
+//! }
+//!
+//! let storage = VecStorage::from_iter([vec![(1, 'a')], vec![(1, 'b')]]);
+//! let res = collect_stable(storage.into_builder().min_by_key(|item| item.0).build());
+//! assert_eq!(res, vec![(1, 'a'), (1, 'b')]);
+//! # }
+//! ```
+//! The same function doesn't accept a [`MergeIter`](crate::MergeIter) with the
+//! [`Unspecified`] tie breaker:
+//! ```compile_fail
+//! # use iter_merge::{MergeIter, VecStorage, comparators::{Comparator, tie_breaker::{StableAcrossSources, Unspecified}}, storage::Storage};
+//! # fn collect_stable<S, CMP>(iter: MergeIter<S, CMP>) -> Vec<(u8, char)>
+//! # where S: Storage, S::IT: Iterator<Item = (u8, char)>, CMP: Comparator<(u8, char)> + StableAcrossSources,
+//! # { iter.into_vec() }
+//! let storage = VecStorage::from_iter([vec![(1, 'a')], vec![(1, 'b')]]);
+//! collect_stable(storage.into_builder().tie_breaker(Unspecified).min_by_key(|item| item.0).build());
+//! ```
 
 use core::cmp::Ordering;
 
@@ -23,6 +73,11 @@ impl<T> Comparator<T> for InsertionOrder {
     fn compare(&self, a: &T, b: &T) -> Ordering {
         addr_from_ref(a).cmp(&addr_from_ref(b))
     }
+
+    #[inline]
+    fn equivalent(&self, _a: &T, _b: &T) -> bool {
+        true
+    }
 }
 
 /// If two items are equal the item from later-inserted iterator will be yielded first
@@ -34,12 +89,26 @@ impl<T> Comparator<T> for ReverseInsertionOrder {
     fn compare(&self, a: &T, b: &T) -> Ordering {
         addr_from_ref(b).cmp(&addr_from_ref(a))
     }
+
+    #[inline]
+    fn equivalent(&self, _a: &T, _b: &T) -> bool {
+        true
+    }
 }
 
+/// Marker for the comparators that yield equal items from different iterators in a
+/// deterministic order, defined by the order of insertion of the iterators.
+///
+/// See [the module documentation](self#stability).
+pub trait StableAcrossSources {}
+
+impl StableAcrossSources for InsertionOrder {}
+impl StableAcrossSources for ReverseInsertionOrder {}
+
 /// If two items are equal they are yielded in unspecified order. This improves
 /// the performance a bit.
 #[derive(Debug, Clone, Copy)]
-pub struct Unspecified; //TODO: measure
+pub struct Unspecified;
 
 impl<T> Comparator<T> for Unspecified {
     #[inline]
@@ -48,6 +117,118 @@ impl<T> Comparator<T> for Unspecified {
     }
 }
 
+/// If two items are equal the item from the source with the higher priority will be yielded
+/// first, regardless of the order of insertion. Sources with equal priorities are yielded in
+/// unspecified order, chain with [`InsertionOrder`] to order them.
+///
+/// The priority is set once per source, as its
+/// [rank](crate::internal::BaseStorage::source_rank), by
+/// [`VecStorage::push_with_priority`](crate::VecStorage::push_with_priority), the items
+/// themselves are not wrapped. Sources of the storages that don't keep the ranks, like
+/// [`ArrayStorage`](crate::ArrayStorage), have equal priorities. Like the address-based tie
+/// breakers, it only orders the items of the sources, an item outside of the merge (e.g. the
+/// bound of [`MergeIter::take_until`](crate::MergeIter::take_until)) is equal to all of them.
+///
+/// # Example
+/// The primary replica wins the ties:
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::tie_breaker::ByPriority};
+///
+/// let mut storage = VecStorage::new();
+/// storage.push_with_priority(vec![(1, "stale"), (2, "stale")], 0);
+/// storage.push_with_priority(vec![(2, "primary")], 1);
+/// let merged = storage
+///     .into_builder()
+///     .min_by_key(|record| record.0)
+///     .tie_breaker(ByPriority)
+///     .build();
+/// assert!(merged.eq([(1, "stale"), (2, "primary"), (2, "stale")]));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByPriority;
+
+impl<T> Comparator<T> for ByPriority {
+    #[inline]
+    fn compare(&self, _a: &T, _b: &T) -> Ordering {
+        Ordering::Equal
+    }
+
+    #[inline]
+    fn equivalent(&self, _a: &T, _b: &T) -> bool {
+        true
+    }
+
+    #[inline]
+    fn compare_sources(&self, _a: &T, _b: &T, (a_rank, b_rank): (u64, u64)) -> Ordering {
+        b_rank.cmp(&a_rank)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        true
+    }
+}
+
+/// If two items are equal the item from the source with the smaller sequence number will be
+/// yielded first
+///
+/// Sequence numbers are assigned in the order of insertion by
+/// [`VecStorage::push_sequenced`](crate::VecStorage::push_sequenced) and
+/// [`MergeIter::add_iter_sequenced`](crate::MergeIter::add_iter_sequenced), or explicitly by
+/// [`VecStorage::push_with_priority`](crate::VecStorage::push_with_priority), so the order
+/// doesn't depend on the addresses of the items. Like [`ByPriority`], it reads the sequence
+/// number from the [rank](crate::internal::BaseStorage::source_rank) of the source, the items
+/// themselves are not wrapped.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{VecStorage, comparators::tie_breaker::BySequence};
+///
+/// let mut storage = VecStorage::new();
+/// storage.push_sequenced(vec![(1, 'a'), (3, 'b')]);
+/// storage.push_sequenced(vec![(3, 'c')]);
+/// let mut merged = storage
+///     .into_builder()
+///     .min_by_key(|record| record.0)
+///     .tie_breaker(BySequence)
+///     .build();
+/// assert_eq!(merged.next(), Some((1, 'a')));
+/// merged.add_iter_sequenced(vec![(2, 'd'), (3, 'e')]);
+/// assert!(merged.eq([(2, 'd'), (3, 'b'), (3, 'c'), (3, 'e')]));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BySequence;
+
+impl<T> Comparator<T> for BySequence {
+    #[inline]
+    fn compare(&self, _a: &T, _b: &T) -> Ordering {
+        Ordering::Equal
+    }
+
+    #[inline]
+    fn equivalent(&self, _a: &T, _b: &T) -> bool {
+        true
+    }
+
+    #[inline]
+    fn compare_sources(&self, _a: &T, _b: &T, (a_rank, b_rank): (u64, u64)) -> Ordering {
+        a_rank.cmp(&b_rank)
+    }
+
+    #[inline]
+    fn compares_ranks(&self) -> bool {
+        true
+    }
+}
+
+impl StableAcrossSources for BySequence {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +242,53 @@ mod tests {
         assert!(Unspecified.compare(&arr[0], &arr[1]).is_eq());
         assert!(Unspecified.compare(&arr[1], &arr[0]).is_eq());
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn by_priority() {
+        use crate::{VecStorage, comparators::Chain};
+
+        let mut s = VecStorage::new();
+        s.push_with_priority([(1, 'a'), (2, 'a')], 0);
+        s.push_with_priority([(2, 'b'), (3, 'b')], 2);
+        s.push_with_priority([(2, 'c'), (4, 'c')], 0);
+        s.push_with_priority([(1, 'd'), (3, 'd')], 1);
+        let mut m = s
+            .into_builder()
+            .min_by_key(|item: &(i32, char)| item.0)
+            .tie_breaker(Chain::new::<(i32, char)>(ByPriority, InsertionOrder))
+            .build();
+        assert_eq!(m.next(), Some((1, 'd')));
+        assert!(m.peek_source().is_some());
+        assert!(m.eq([
+            (1, 'a'),
+            (2, 'b'),
+            (2, 'a'),
+            (2, 'c'),
+            (3, 'b'),
+            (3, 'd'),
+            (4, 'c'),
+        ]));
+        // Items outside of the merge aren't ranked
+        assert!(ByPriority.compare(&1, &2).is_eq());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn by_sequence() {
+        use crate::VecStorage;
+
+        let mut s = VecStorage::new();
+        s.push_with_priority([(1, 'a'), (2, 'a')], 3);
+        s.push_with_priority([(1, 'b'), (2, 'b')], 1);
+        s.push_with_priority([(2, 'c'), (3, 'c')], 2);
+        let m = s
+            .into_builder()
+            .min_by_key(|item: &(i32, char)| item.0)
+            .tie_breaker(BySequence)
+            .build();
+        assert!(m.eq([(1, 'b'), (1, 'a'), (2, 'b'), (2, 'c'), (2, 'a'), (3, 'c')]));
+        assert!(BySequence.compare(&1, &2).is_eq());
+        assert!(BySequence.equivalent(&1, &2));
+    }
 }
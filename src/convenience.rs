@@ -1,10 +1,11 @@
 #![allow(clippy::type_complexity)]
+use alloc::{collections::TryReserveError, vec::Vec};
 use core::cmp::Ordering;
 
 use crate::{
     MergeIter, VecStorage,
-    comparators::{ByFunc, ByKey, Chain, tie_breaker},
-    merge_iter::DefaultMergeIter,
+    comparators::{ByFunc, ByKey, ByLessThan, Chain, Comparator, tie_breaker},
+    merge_iter::{DefaultMergeIter, MergeIterWith},
     storage::InternalVecStorage,
 };
 
@@ -23,6 +24,25 @@ where
     VecStorage::from_iter(iters).build()
 }
 
+/// Fallible version of [`merge`], returning an error if the allocator reports a failure,
+/// see [`VecStorage::try_build`]
+///
+/// # Errors
+/// Returns an error if it fails to allocate the storage.
+pub fn try_merge<IT>(
+    iters: IT,
+) -> Result<
+    DefaultMergeIter<InternalVecStorage<<IT::Item as IntoIterator>::IntoIter>>,
+    TryReserveError,
+>
+where
+    IT: IntoIterator,
+    IT::Item: IntoIterator,
+    <IT::Item as IntoIterator>::Item: Ord,
+{
+    VecStorage::try_from_iter(iters)?.try_build()
+}
+
 /// Constructs a new [`MergeIter`] with default parameters:
 /// * Uses [`VecStorage`]
 /// * Yields smallest items according to `func`
@@ -36,7 +56,6 @@ pub fn merge_by<IT, F>(
 where
     IT: IntoIterator,
     IT::Item: IntoIterator,
-    <IT::Item as IntoIterator>::Item: Ord,
     F: Fn(&<IT::Item as IntoIterator>::Item, &<IT::Item as IntoIterator>::Item) -> Ordering,
 {
     VecStorage::from_iter(iters)
@@ -45,6 +64,30 @@ where
         .build()
 }
 
+/// Constructs a new [`MergeIter`] with default parameters:
+/// * Uses [`VecStorage`]
+/// * Yields item `a` before item `b` if `less_than(a, b)` returns `true`
+/// * Equal items are yielded in order of their respective iterators
+///
+/// Drop-in replacement for
+/// [`itertools::kmerge_by`](https://docs.rs/itertools/latest/itertools/fn.kmerge_by.html).
+pub fn kmerge_by<IT, F>(
+    iters: IT, less_than: F,
+) -> MergeIter<
+    InternalVecStorage<<IT::Item as IntoIterator>::IntoIter>,
+    Chain<ByLessThan<F>, tie_breaker::InsertionOrder>,
+>
+where
+    IT: IntoIterator,
+    IT::Item: IntoIterator,
+    F: Fn(&<IT::Item as IntoIterator>::Item, &<IT::Item as IntoIterator>::Item) -> bool,
+{
+    VecStorage::from_iter(iters)
+        .into_builder()
+        .min_by_less_than(less_than)
+        .build()
+}
+
 /// Constructs a new [`MergeIter`] with default parameters:
 /// * Uses [`VecStorage`]
 /// * Yields smallest items with the smallest key according to `func`
@@ -58,7 +101,6 @@ pub fn merge_by_key<IT, F, K>(
 where
     IT: IntoIterator,
     IT::Item: IntoIterator,
-    <IT::Item as IntoIterator>::Item: Ord,
     F: Fn(&<IT::Item as IntoIterator>::Item) -> K,
     K: Ord,
 {
@@ -68,6 +110,64 @@ where
         .build()
 }
 
+/// Constructs a new [`MergeIter`] with default parameters:
+/// * Uses [`VecStorage`]
+/// * Yields smallest items according to the comparator `cmp`
+/// * Equal items are yielded in order of their respective iterators
+///
+/// Doesn't require the items to implement [`Ord`].
+pub fn merge_with<IT, C>(
+    iters: IT, cmp: C,
+) -> MergeIterWith<InternalVecStorage<<IT::Item as IntoIterator>::IntoIter>, C>
+where
+    IT: IntoIterator,
+    IT::Item: IntoIterator,
+    C: Comparator<<IT::Item as IntoIterator>::Item>,
+{
+    VecStorage::from_iter(iters).build_with(cmp)
+}
+
+/// Merges sorted key columns, returning `(source_index, row_index)` pairs in merged order.
+///
+/// Only the indices are produced, so this is useful for columnar data (e.g. Arrow arrays
+/// sorted by a key column): resulting pairs are in the format expected by
+/// [`arrow::compute::interleave`](https://docs.rs/arrow/latest/arrow/compute/fn.interleave.html)
+/// to gather the merged rows from all of the sources. The `arrow` feature enables the `arrow`
+/// module, which merges the Arrow arrays and record batches directly.
+///
+/// Rows with equal keys are ordered by their source index, then by their row index.
+///
+/// # Example
+/// ```
+/// use iter_merge::interleave_indices;
+///
+/// let a: &[u32] = &[1, 4, 4];
+/// let b: &[u32] = &[];
+/// let c: &[u32] = &[2, 4];
+/// assert_eq!(
+///     interleave_indices([a, b, c]),
+///     vec![(0, 0), (2, 0), (0, 1), (0, 2), (2, 1)],
+/// );
+/// ```
+pub fn interleave_indices<IT>(key_columns: IT) -> Vec<(usize, usize)>
+where
+    IT: IntoIterator,
+    IT::Item: IntoIterator,
+    <IT::Item as IntoIterator>::Item: Ord,
+{
+    // (key, source, row) tuples are never equal, so there's no need for a tie breaker
+    VecStorage::from_iter(key_columns.into_iter().enumerate().map(|(source, keys)| {
+        keys.into_iter()
+            .enumerate()
+            .map(move |(row, key)| (key, source, row))
+    }))
+    .into_builder()
+    .tie_breaker(tie_breaker::Unspecified)
+    .build()
+    .map(|(_key, source, row)| (source, row))
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,9 +177,26 @@ mod tests {
         assert!(merge([[3, 6], [1, 4], [2, 5]]).eq([1, 2, 3, 4, 5, 6]));
     }
 
+    #[test]
+    fn try_merge_works() {
+        use alloc::vec;
+
+        let merged = try_merge([vec![3, 6], vec![], vec![1, 4]]).unwrap();
+        assert!(merged.source_tagged().eq([(2, 1), (0, 3), (2, 4), (0, 6)]));
+    }
+
     #[test]
     fn merge_by_works() {
-        assert!(merge_by([[3, 6], [1, 4], [2, 5]], |a, b| { b.cmp(a) }).eq([3, 6, 2, 5, 1, 4]));
+        assert!(merge_by([[6, 3], [4, 1], [5, 2]], |a, b| { b.cmp(a) }).eq([6, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn kmerge_by_works() {
+        assert!(kmerge_by([[6, 3], [4, 1], [5, 2]], |a, b| a > b).eq([6, 5, 4, 3, 2, 1]));
+        assert!(
+            kmerge_by([[(1, 'a'), (2, 'b')], [(1, 'c'), (2, 'd')]], |a, b| a.0 < b.0)
+                .eq([(1, 'a'), (1, 'c'), (2, 'b'), (2, 'd')])
+        );
     }
 
     #[test]
@@ -89,4 +206,47 @@ mod tests {
                 .eq([-1, 2, -3, 4, -5, 6])
         );
     }
+
+    // Items that don't implement Ord
+    #[derive(Debug, PartialEq)]
+    struct Event(f32);
+
+    struct ByTime;
+
+    impl Comparator<Event> for ByTime {
+        fn compare(&self, a: &Event, b: &Event) -> Ordering {
+            a.0.total_cmp(&b.0)
+        }
+    }
+
+    #[test]
+    fn ord_free_entry_points() {
+        use alloc::vec;
+        use core::pin::pin;
+
+        use crate::ArrayStorage;
+        let events = || [vec![Event(0.5), Event(2.0)], vec![Event(1.0)]];
+        let expected = [Event(0.5), Event(1.0), Event(2.0)];
+        assert_eq!(merge_with(events(), ByTime).into_vec(), expected);
+        assert_eq!(merge_by(events(), |a, b| a.0.total_cmp(&b.0)).into_vec(), expected);
+        assert_eq!(merge_by_key(events(), |e| e.0 as i32).into_vec(), expected);
+        assert_eq!(
+            VecStorage::from_iter(events())
+                .build_with(ByTime)
+                .into_vec(),
+            expected
+        );
+        let s = pin!(ArrayStorage::from_arr(events()));
+        assert_eq!(s.build_with(ByTime).into_vec(), expected);
+    }
+
+    #[test]
+    fn interleave_indices_works() {
+        let cols: [&[i32]; 3] = [&[1, 3, 5], &[], &[1, 2, 6]];
+        assert_eq!(
+            interleave_indices(cols),
+            [(0, 0), (2, 0), (2, 1), (0, 1), (0, 2), (2, 2)]
+        );
+        assert!(interleave_indices::<[&[i32]; 0]>([]).is_empty());
+    }
 }
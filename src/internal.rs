@@ -23,17 +23,33 @@
 //!     ...
 //! ]
 //! ```
+#[macro_use]
+pub(crate) mod trace;
 mod heap;
-use core::mem;
+#[cfg(feature = "stats")]
+pub(crate) mod stats;
+use core::{cmp::Ordering, mem};
 pub(crate) mod nums;
 pub(crate) mod pointers;
+use pointers::ptr_to_usize;
 
-pub(crate) use heap::Heap;
+pub(crate) use heap::{ExtendSink, Heap, precedes};
 mod hole;
 pub(crate) use hole::Hole;
 
 /// Holds within itself one peeked item from the iterator and the iterator itself.
 /// It's like [`iter::Peekable`](core::iter::Peekable), except eager.
+///
+/// [`PeekIter`] can't be zero-sized, merges of zero-sized iterators over zero-sized items are
+/// rejected at compile time:
+/// ```compile_fail
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// let merged = iter_merge::merge([core::iter::repeat(()), core::iter::repeat(())]);
+/// # }
+/// # #[cfg(not(feature = "alloc"))]
+/// # compile_error!();
+/// ```
 #[derive(Debug)]
 pub struct PeekIter<IT: Iterator> {
     /// Item peeked from the iter
@@ -58,7 +74,7 @@ where
 impl<IT: Iterator> PeekIter<IT> {
     const _CHECK: () = assert!(
         mem::size_of::<Self>() > 0,
-        "iter-merge doesn't work when both iterator and item are ZST",
+        "iter-merge doesn't work with zero-sized PeekIters",
     );
 
     /// Create a new [`PeekIter`] from a `peeked_item` and `iter`
@@ -136,11 +152,79 @@ pub unsafe trait BaseStorage {
     /// Caller guarantees that heap elements in `self.len()..new_len` are initialized
     unsafe fn set_len(&mut self, new_len: usize);
 
+    /// Pointer to the start of the contiguous allocation of [`PeekIter`]s, if the storage
+    /// has one.
+    ///
+    /// Offset of a [`PeekIter`] from this pointer is its position in the storage, see
+    /// [`StorageOps::source_index`]. [`PeekIter`]s must be stored in order of insertion for
+    /// the index to be meaningful.
+    #[inline]
+    fn storage(&self) -> Option<*mut PeekIter<Self::IT>> {
+        None
+    }
+
+    /// Maps the position of a [`PeekIter`] in the storage to the index of its source among
+    /// all of the iterators inserted into the storage, including the empty ones.
+    ///
+    /// Defaults to the position itself.
+    #[inline]
+    fn source_id(&self, position: usize) -> usize {
+        position
+    }
+
+    /// Returns the rank of the source with the index `source_id` (see
+    /// [`source_id`](Self::source_id)), compared by the
+    /// [`ByPriority`](crate::comparators::tie_breaker::ByPriority) and
+    /// [`BySequence`](crate::comparators::tie_breaker::BySequence) tie breakers.
+    ///
+    /// Defaults to 0 for every source.
+    #[inline]
+    fn source_rank(&self, _source_id: usize) -> u64 {
+        0
+    }
+
     /// Returns true if [`Self::len`](crate::internal::BaseStorage::len) == 0
     #[inline]
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns `true` if the storage keeps the keys of the peeked items next to the heap, see
+    /// [`HeapKeyStorage`](crate::storage::HeapKeyStorage)
+    #[doc(hidden)]
+    #[inline]
+    fn caches_keys(&self) -> bool {
+        false
+    }
+
+    /// Stores the key of `item` at the position `pos` of the heap. Called by the heap every
+    /// time an item is placed at `pos`, if [`caches_keys`](Self::caches_keys) returns `true`.
+    #[doc(hidden)]
+    #[inline]
+    fn cache_key(&mut self, _pos: usize, _item: &<Self::IT as Iterator>::Item) {}
+
+    /// Swaps the keys cached at the positions `a` and `b` of the heap, following the items
+    #[doc(hidden)]
+    #[inline]
+    fn swap_cached_keys(&mut self, _a: usize, _b: usize) {}
+
+    /// Compares the items `a` and `b` at the positions `a.0` and `b.0` of the heap by their
+    /// cached keys. The items are accessed only to cache the missing keys. Returns `None` if
+    /// the keys are equal, then the items are compared by the comparator of the merge.
+    #[doc(hidden)]
+    #[inline]
+    fn compare_cached(
+        &mut self, _a: (usize, &<Self::IT as Iterator>::Item),
+        _b: (usize, &<Self::IT as Iterator>::Item),
+    ) -> Option<Ordering> {
+        None
+    }
+
+    /// Stops caching the keys. Called when the comparator of the merge is replaced, since the
+    /// cached keys no longer match its order.
+    #[doc(hidden)]
+    #[inline]
+    fn forget_keys(&mut self) {}
 }
 
 /// Provides access to the iterator type within the storage
@@ -228,6 +312,32 @@ pub trait StorageOps: BaseStorage {
         }
     }
 
+    /// Drops the items for which `func` returns `false`, keeping the relative position of the
+    /// rest. `func` may modify the items. Heap order is *not* preserved.
+    ///
+    /// If `func` or the drop of an item panics, the items that were not visited yet are leaked.
+    fn retain(&mut self, mut func: impl FnMut(&mut PeekIter<Self::IT>) -> bool) {
+        let len = self.len();
+        // SAFETY: decreasing length is safe
+        unsafe {
+            self.set_len(0);
+        }
+        let mut kept = 0;
+        for i in 0..len {
+            // SAFETY: heap items up to the original len are valid, kept <= i
+            unsafe {
+                let peek_iter = self.heap().add(i).read();
+                if func(&mut *peek_iter) {
+                    self.heap().add(kept).write(peek_iter);
+                    kept += 1;
+                    self.set_len(kept);
+                } else {
+                    peek_iter.drop_in_place();
+                }
+            }
+        }
+    }
+
     /// Iterates over all items in the heap and calls `func` for each
     /// Only the first two items are in order, order of the rest is *unspecified*.
     #[inline]
@@ -240,6 +350,37 @@ pub trait StorageOps: BaseStorage {
         }
     }
 
+    /// Returns the index of the source of `peek_iter` in order of insertion, see
+    /// [`BaseStorage::storage`] and [`BaseStorage::source_id`].
+    ///
+    /// Returns `None` if the storage doesn't provide the pointer to its allocation.
+    #[inline]
+    fn source_index(&self, peek_iter: *const PeekIter<Self::IT>) -> Option<usize> {
+        let offset = ptr_to_usize(peek_iter).wrapping_sub(ptr_to_usize(self.storage()?));
+        // PeekIter is never zero-sized: every merge is built by `Heap::from_parts`, which
+        // rejects it at compile time
+        Some(self.source_id(offset / mem::size_of::<PeekIter<Self::IT>>()))
+    }
+
+    /// Returns the [rank](BaseStorage::source_rank) of the source of `peek_iter`, 0 if the
+    /// storage doesn't provide the pointer to its allocation.
+    #[inline]
+    fn rank(&self, peek_iter: *const PeekIter<Self::IT>) -> u64 {
+        self.source_index(peek_iter)
+            .map_or(0, |source_id| self.source_rank(source_id))
+    }
+
+    /// Returns the index of the source of the first (smallest) item, see
+    /// [`StorageOps::source_index`].
+    #[inline]
+    fn first_source_index(&self) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        // SAFETY: len >= 1
+        self.source_index(unsafe { *self.first() })
+    }
+
     /// Peeks the first item of the heap
     #[inline]
     fn peek(&self) -> Option<&Item<Self>> {
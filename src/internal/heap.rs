@@ -1,14 +1,155 @@
-use core::{cmp::Ordering, mem, ptr};
+use core::{
+    cmp::Ordering,
+    mem,
+    ptr::{self, addr_of_mut},
+};
 
 use crate::{
     comparators::Comparator,
     internal::{
         Hole, Item, Iter, PeekIter,
         nums::{unchecked_add, unchecked_mul, unchecked_sub},
-        pointers::UniquePtr,
+        pointers::{UniqueOwningPtr, UniquePtr},
     },
     storage::Storage,
 };
+#[cfg(feature = "tracing")]
+use crate::internal::trace::CountingSink;
+#[cfg(feature = "stats")]
+use crate::internal::stats::Counters;
+#[cfg(feature = "alloc-audit")]
+use crate::merge_iter::AllocStats;
+
+/// Returns `true` if `item` precedes `bound` according to the `comparator`.
+///
+/// Items equivalent to the `bound` don't precede it: tie breakers order the items by their
+/// position in the storage, so they can't place the items relative to a `bound` outside of it.
+#[inline]
+pub(crate) fn precedes<T, CMP: Comparator<T>>(comparator: &CMP, item: &T, bound: &T) -> bool {
+    comparator.compare(item, bound).is_lt() && !comparator.equivalent(item, bound)
+}
+
+/// [`Iterator::nth`] that also returns the number of the skipped items (not counting the
+/// returned one).
+///
+/// Only the iterators with the exact [`size_hint`](Iterator::size_hint) skip via their own
+/// `nth`, the rest are advanced one item at a time to count the items when they run out.
+#[cfg(feature = "stats")]
+fn nth_counted<I: Iterator>(iter: &mut I, n: usize) -> (Option<I::Item>, usize) {
+    let (lower, upper) = iter.size_hint();
+    if upper == Some(lower) {
+        return match iter.nth(n) {
+            Some(item) => (Some(item), n),
+            None => (None, lower),
+        };
+    }
+    for skipped in 0..n {
+        if iter.next().is_none() {
+            return (None, skipped);
+        }
+    }
+    (iter.next(), n)
+}
+
+/// Destination for the items drained from the [`Heap`]
+pub(crate) trait Sink<T> {
+    fn push(&mut self, item: T);
+    // The debug checks push the items of the last iterator one by one
+    #[cfg_attr(all(debug_assertions, iter_merge_debug_checks), allow(dead_code))]
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>);
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Sink<T> for alloc::vec::Vec<T> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        self.push(item);
+    }
+
+    #[inline]
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>) {
+        self.extend(iter);
+    }
+}
+
+/// [`Sink`] that skips the items equivalent to the last item pushed into the
+/// [`Vec`](alloc::vec::Vec)
+#[cfg(feature = "alloc")]
+struct DedupSink<'a, T, CMP> {
+    vec: alloc::vec::Vec<T>,
+    comparator: &'a CMP,
+}
+
+#[cfg(feature = "alloc")]
+impl<T, CMP: Comparator<T>> Sink<T> for DedupSink<'_, T, CMP> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        match self.vec.last() {
+            Some(last) if self.comparator.equivalent(last, &item) => {}
+            _ => self.vec.push(item),
+        }
+    }
+
+    #[inline]
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// [`Sink`] that counts the (re)allocations of the [`Vec`](alloc::vec::Vec) it fills
+#[cfg(feature = "alloc-audit")]
+struct AuditSink<T> {
+    vec: alloc::vec::Vec<T>,
+    stats: AllocStats,
+}
+
+#[cfg(feature = "alloc-audit")]
+impl<T> AuditSink<T> {
+    #[inline]
+    fn track(&mut self, func: impl FnOnce(&mut alloc::vec::Vec<T>)) {
+        let old_cap = self.vec.capacity();
+        func(&mut self.vec);
+        if self.vec.capacity() != old_cap {
+            if old_cap == 0 {
+                self.stats.allocations += 1;
+            } else {
+                self.stats.reallocations += 1;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc-audit")]
+impl<T> Sink<T> for AuditSink<T> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        self.track(|vec| vec.push(item));
+    }
+
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>) {
+        // Item by item, a single `extend` may reallocate several times
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// [`Sink`] over any [`Extend`] implementor
+pub(crate) struct ExtendSink<'a, E>(pub(crate) &'a mut E);
+
+impl<T, E: Extend<T>> Sink<T> for ExtendSink<'_, E> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        self.0.extend(Some(item));
+    }
+
+    #[inline]
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>) {
+        self.0.extend(iter);
+    }
+}
 
 /// Min heap organized on storage `S` and ordered by `CMP`.
 /// Heap structure:
@@ -20,6 +161,8 @@ use crate::{
 pub(crate) struct Heap<S, CMP> {
     pub(crate) comparator: CMP,
     pub(crate) storage: S,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: Counters,
 }
 
 impl<CMP, S> Heap<S, CMP>
@@ -28,30 +171,170 @@ where
     S: Storage,
 {
     pub(crate) fn new(comparator: CMP, storage: S) -> Self {
-        let mut res = Self {
-            comparator,
-            storage,
-        };
+        let _span = trace_span!("build", sources = storage.len());
+        let mut res = Self::from_parts(comparator, storage);
         res.heapify_storage();
         res
     }
 
+    /// Constructs the heap without restoring the heap order, [`heapify_storage`] has to be
+    /// called before any other operation.
+    ///
+    /// [`heapify_storage`]: Self::heapify_storage
+    pub(crate) fn new_unordered(comparator: CMP, storage: S) -> Self {
+        Self::from_parts(comparator, storage)
+    }
+
+    fn from_parts(comparator: CMP, storage: S) -> Self {
+        // Zero-sized PeekIters share the address, so neither the heap nor the source indexes
+        // could tell them apart
+        let () = PeekIter::<Iter<S>>::_CHECK;
+        Self {
+            comparator,
+            storage,
+            #[cfg(feature = "stats")]
+            stats: Counters::default(),
+        }
+    }
+
     #[inline]
-    fn cmp(&self, a: &PeekIter<Iter<S>>, b: &PeekIter<Iter<S>>) -> Ordering {
+    fn cmp(&mut self, a: &PeekIter<Iter<S>>, b: &PeekIter<Iter<S>>) -> Ordering {
         debug_assert!(!ptr::eq(a, b), "shouldn't ever compare the item to itself");
-        self.comparator.compare(&a.item, &b.item)
+        #[cfg(feature = "stats")]
+        {
+            self.stats.comparisons += 1;
+        }
+        let ranks = self.ranks(a, b);
+        self.comparator.compare_sources(&a.item, &b.item, ranks)
     }
 
-    fn heapify_storage(&mut self) {
+    /// Looks up the [ranks](crate::internal::BaseStorage::source_rank) of the sources of `a`
+    /// and `b`, if the comparator [compares them](Comparator::compares_ranks)
+    #[inline]
+    fn ranks(&self, a: *const PeekIter<Iter<S>>, b: *const PeekIter<Iter<S>>) -> (u64, u64) {
+        if self.comparator.compares_ranks() {
+            (self.storage.rank(a), self.storage.rank(b))
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Compares the items `a` and `b` at the positions `a_pos` and `b_pos` of the heap, by
+    /// their cached keys if the storage [keeps them](crate::internal::BaseStorage::caches_keys). The first
+    /// element has no cached key: its item changes on every advance.
+    ///
+    /// The keys missing from the cache (the heap wasn't built by
+    /// [`heapify_storage`](Self::heapify_storage), or the storage is a clone) are cached on
+    /// the first comparison.
+    #[inline]
+    fn cmp_at(
+        &mut self, (a_pos, a): (usize, &PeekIter<Iter<S>>), (b_pos, b): (usize, &PeekIter<Iter<S>>),
+    ) -> Ordering {
+        if a_pos != 0 && b_pos != 0 && self.storage.caches_keys() {
+            if let Some(ord) = self
+                .storage
+                .compare_cached((a_pos, &a.item), (b_pos, &b.item))
+            {
+                #[cfg(feature = "stats")]
+                {
+                    self.stats.comparisons += 1;
+                }
+                return ord;
+            }
+        }
+        self.cmp(a, b)
+    }
+
+    /// Caches the key of the item at `pos` of the heap if the storage
+    /// [keeps them](crate::internal::BaseStorage::caches_keys). Has to be called every time an item is placed
+    /// at `pos >= 1`, except for the moves that swap the cached keys along.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee:
+    /// * `pos < self.storage.len()`
+    /// * the element at `pos` can be accessed via reference (&)
+    #[inline]
+    unsafe fn cache_key(&mut self, pos: usize) {
+        if self.storage.caches_keys() {
+            // SAFETY: the caller guarantees it's safe
+            let peek_iter = unsafe { &**self.storage.heap().add(pos) };
+            self.storage.cache_key(pos, &peek_iter.item);
+        }
+    }
+
+    /// Caches the keys of all of the items but the first one, see
+    /// [`cache_key`](Self::cache_key)
+    fn cache_keys(&mut self) {
+        for pos in 1..self.storage.len() {
+            // SAFETY: pos is within the heap, no &mut into the heap exist
+            unsafe {
+                self.cache_key(pos);
+            }
+        }
+    }
+
+    /// Compares the peeked item with `item`, counting the comparison like [`cmp`](Self::cmp).
+    /// Returns `None` if the heap is empty.
+    #[inline]
+    pub(crate) fn cmp_peeked(&mut self, item: &Item<S>) -> Option<Ordering> {
+        let next = self.storage.peek()?;
+        #[cfg(feature = "stats")]
+        {
+            self.stats.comparisons += 1;
+        }
+        Some(self.comparator.compare(next, item))
+    }
+
+    /// Returns `true` if the peeked item is equivalent to `item`, counting the comparison like
+    /// [`cmp`](Self::cmp)
+    #[inline]
+    pub(crate) fn peeked_equivalent(&mut self, item: &Item<S>) -> bool {
+        let Some(next) = self.storage.peek() else {
+            return false;
+        };
+        #[cfg(feature = "stats")]
+        {
+            self.stats.comparisons += 1;
+        }
+        self.comparator.equivalent(next, item)
+    }
+
+    /// Advances every iterator while `func` returns `true` for its peeked item, dropping the
+    /// exhausted iterators, then restores the heap order. Returns the number of discarded items.
+    pub(crate) fn advance_while(&mut self, mut func: impl FnMut(&CMP, &Item<S>) -> bool) -> usize {
+        let Self {
+            comparator,
+            storage,
+            ..
+        } = self;
+        let mut skipped = 0_usize;
+        storage.retain(|peek_iter| {
+            while func(comparator, &peek_iter.item) {
+                skipped = skipped.wrapping_add(1);
+                if peek_iter.advance().is_none() {
+                    // exhausted, the last item is dropped together with the iterator
+                    return false;
+                }
+            }
+            true
+        });
+        self.heapify_storage();
+        skipped
+    }
+
+    pub(crate) fn heapify_storage(&mut self) {
         // This heapify process is done in two phases:
         // 1. First, we perform a bottom-up heapify on the range [1..], ensuring that the heap
         //    rooted at index 1 is a valid min-heap.
         // 2. Then, we explicitly fix the [0,1] pair so that the overall invariant [0] <= [1] holds.
         // This establishes that [1..] forms a valid heap and the minimum element is always at [0],
         // which is essential for the correctness of subsequent heap operations.
+        self.cache_keys();
         if self.storage.len() <= 1 {
             return;
         }
+        let _span = trace_span!("heapify", sources = self.storage.len());
 
         for n in (1..=(self.storage.len() / 2)).rev() {
             // SAFETY: n in range [1; self.storage.len() / 2], it's < self.storage.len()
@@ -65,11 +348,122 @@ where
             let second = self.storage.second();
             if self.cmp(&**first, &**second).is_gt() {
                 ptr::swap_nonoverlapping(first, second, 1);
+                self.cache_key(1);
                 self.sift_down_top();
             }
         }
     }
 
+    /// Returns `true` if the items are sorted by their position in the heap, which implies the
+    /// heap order
+    pub(crate) fn is_sorted(&self) -> bool {
+        let heap = self.storage.heap();
+        (1..self.storage.len()).all(|pos| {
+            // SAFETY: pos - 1 and pos are within the heap, no &mut into the heap exist
+            let (prev, next) = unsafe { (&**heap.add(pos - 1), &**heap.add(pos)) };
+            self.comparator
+                .compare_sources(&prev.item, &next.item, self.ranks(prev, next))
+                .is_le()
+        })
+    }
+
+    /// Take the last element of the heap and move it up the heap, while it's smaller than
+    /// its parent, and finally in place of the first element if it's smaller than it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee:
+    /// * all elements of the heap but the last one are in heap order
+    /// * heap can be mutated and elements can be accessed via reference (&).
+    ///   i.e.: no &mut into the heap
+    pub(crate) unsafe fn sift_up_last(&mut self) {
+        let len = self.storage.len();
+        if len < 2 {
+            return;
+        }
+        // SAFETY: len >= 2, never underflows; the caller guarantees the rest
+        unsafe {
+            self.cache_key(unchecked_sub(len, 1));
+            self.sift_up(unchecked_sub(len, 1));
+        }
+    }
+
+    /// Take an element at `pos` and move it up the heap, while it's smaller than its parent,
+    /// and finally in place of the first element if it's smaller than it.
+    /// Returns `true` if the element was moved.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee:
+    /// * `1 <= pos < self.storage.len()`
+    /// * heap can be mutated and elements can be accessed via reference (&).
+    ///   i.e.: no &mut into the heap
+    unsafe fn sift_up(&mut self, mut pos: usize) -> bool {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.sifts += 1;
+        }
+        let heap = self.storage.heap();
+        let start = pos;
+        // SAFETY: pos and its parents are within the heap
+        unsafe {
+            while pos > 1 {
+                let parent = pos / 2;
+                if self
+                    .cmp_at((pos, &**heap.add(pos)), (parent, &**heap.add(parent)))
+                    .is_ge()
+                {
+                    return pos != start;
+                }
+                ptr::swap_nonoverlapping(heap.add(pos), heap.add(parent), 1);
+                self.storage.swap_cached_keys(pos, parent);
+                pos = parent;
+            }
+            // reached the root, the first element might be larger than it
+            let first = self.storage.first();
+            let second = self.storage.second();
+            if self.cmp(&**second, &**first).is_lt() {
+                // the first element is not larger than any other, so it stays in heap order
+                ptr::swap_nonoverlapping(first, second, 1);
+                self.cache_key(1);
+                return true;
+            }
+        }
+        pos != start
+    }
+
+    /// Restores the heap order after the item of the element at `pos` was changed.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee:
+    /// * `pos < self.storage.len()`
+    /// * all elements of the heap but the one at `pos` are in heap order
+    /// * heap can be mutated and elements can be accessed via reference (&).
+    ///   i.e.: no &mut into the heap
+    pub(crate) unsafe fn fix_at(&mut self, pos: usize) {
+        if self.storage.len() < 2 {
+            return;
+        }
+        // SAFETY: len >= 2, pos is within the heap, the caller guarantees the rest
+        unsafe {
+            if pos == 0 {
+                let first = self.storage.first();
+                let second = self.storage.second();
+                if self.cmp(&**first, &**second).is_gt() {
+                    ptr::swap_nonoverlapping(first, second, 1);
+                    self.cache_key(1);
+                    self.sift_down_top();
+                }
+            } else {
+                self.cache_key(pos);
+                if !self.sift_up(pos) {
+                    self.sift_down_element(pos);
+                }
+            }
+        }
+    }
+
     /// Take an element at the top of the heap and move it down the heap,
     /// while its children are smaller.
     ///
@@ -96,13 +490,17 @@ where
     ///   (therefore `self.storage.len()` >= 2)
     /// * Heap [pos; end) can be mutated and elements at these locations
     ///   can be accessed via reference (&). i.e.: no &mut to the [pos; end)
-    #[inline] // only used in sift_down and heapify
+    #[inline] // only used in sift_down, heapify and fix_at
     unsafe fn sift_down_element(&mut self, pos: usize) {
         let len = self.storage.len();
         #[allow(clippy::checked_conversions)]
         {
             debug_assert!(pos >= 1 && pos < len && len >= 2 && len <= isize::MAX as usize);
         }
+        #[cfg(feature = "stats")]
+        {
+            self.stats.sifts += 1;
+        }
         // SAFETY: The caller guarantees that pos < end <= self.storage.len().
         let mut hole = unsafe { Hole::new(self.storage.heap(), pos) };
         // hole.pos * 2; never overflows because self.storage.len() is <= isize::MAX
@@ -115,9 +513,9 @@ where
             // find the smaller of the two children
             if self
                 // SAFETY: child, child+1 are < len and != hole.pos
-                .cmp(unsafe { &**hole.get(child) }, unsafe {
+                .cmp_at((child, unsafe { &**hole.get(child) }), (child2, unsafe {
                     &**hole.get(child2)
-                })
+                }))
                 .is_gt()
             {
                 child = child2;
@@ -126,11 +524,15 @@ where
             // if we are already in order, stop.
             if self
                 // SAFETY: child is < len and != hole.pos, hole.elt is a valid item
-                .cmp(unsafe { &**hole.elt }, unsafe { &**hole.get(child) })
+                .cmp_at((hole.pos, unsafe { &**hole.elt }), (child, unsafe {
+                    &**hole.get(child)
+                }))
                 .is_le()
             {
                 return;
             }
+            // the key of the hole element follows it
+            self.storage.swap_cached_keys(hole.pos, child);
             // SAFETY: child != pos and is valid element
             unsafe {
                 hole.move_to(child);
@@ -141,11 +543,15 @@ where
         if child == last_el {
             if self
                 // SAFETY: child is < len and != hole.pos, hole.elt is a valid item
-                .cmp(unsafe { &**hole.elt }, unsafe { &**hole.get(child) })
+                .cmp_at((hole.pos, unsafe { &**hole.elt }), (child, unsafe {
+                    &**hole.get(child)
+                }))
                 .is_le()
             {
                 return;
             }
+            // the key of the hole element follows it
+            self.storage.swap_cached_keys(hole.pos, child);
             // SAFETY: child != pos and is valid element
             unsafe {
                 hole.move_to(child);
@@ -153,17 +559,97 @@ where
         }
     }
 
+    /// Lower bound of the number of remaining items
+    fn hint_low(&self) -> usize {
+        if self.comparator.deduplicates() {
+            return usize::from(!self.storage.is_empty());
+        }
+        let mut hint_low = self.storage.len();
+        self.storage
+            .map_items(|it| hint_low = hint_low.saturating_add(it.iter.size_hint().0));
+        hint_low
+    }
+
     #[cfg(feature = "alloc")]
-    pub(crate) fn into_vec(mut self) -> alloc::vec::Vec<Item<S>> {
+    pub(crate) fn into_vec(self) -> alloc::vec::Vec<Item<S>> {
         let mut res = alloc::vec::Vec::new();
-        let mut hint_low = self.storage.len();
-        if hint_low == 0 {
+        if self.storage.is_empty() {
             return res;
         }
-        self.storage
-            .map_items(|it| hint_low = hint_low.saturating_add(it.iter.size_hint().0));
-        res.reserve_exact(hint_low);
+        res.reserve_exact(self.hint_low());
+        self.drain_into(&mut res);
+        res
+    }
+
+    #[cfg(feature = "alloc")]
+    pub(crate) fn into_dedup_vec(self) -> alloc::vec::Vec<Item<S>> {
+        let Self {
+            comparator,
+            storage,
+            #[cfg(feature = "stats")]
+            stats,
+        } = self;
+        let mut sink = DedupSink {
+            vec: alloc::vec::Vec::new(),
+            comparator: &comparator,
+        };
+        // The sink and the heap share the comparator
+        let heap = Heap {
+            comparator: &comparator,
+            storage,
+            #[cfg(feature = "stats")]
+            stats,
+        };
+        if !heap.storage.is_empty() {
+            sink.vec.reserve_exact(heap.hint_low());
+            heap.drain_into(&mut sink);
+        }
+        sink.vec
+    }
 
+    #[cfg(feature = "alloc-audit")]
+    pub(crate) fn into_vec_audited(self) -> (alloc::vec::Vec<Item<S>>, AllocStats) {
+        let mut sink = AuditSink {
+            vec: alloc::vec::Vec::new(),
+            stats: AllocStats::default(),
+        };
+        if !self.storage.is_empty() {
+            let hint_low = self.hint_low();
+            sink.track(|vec| vec.reserve_exact(hint_low));
+            self.drain_into(&mut sink);
+        }
+        (sink.vec, sink.stats)
+    }
+
+    /// Pushes all of the remaining items into `res` in order.
+    pub(crate) fn drain_into<D: Sink<Item<S>>>(self, res: &mut D) {
+        #[cfg(feature = "tracing")]
+        {
+            let _span = trace_span!("drain", sources = self.storage.len());
+            let mut sink = CountingSink {
+                sink: res,
+                items: 0,
+            };
+            self.drain_into_inner(&mut sink);
+            trace_event!(items = sink.items, "drained");
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.drain_into_inner(res);
+    }
+
+    /// Avoids the heap operations while the order of the first item stays correct and
+    /// does not update the heap at all when 2 or fewer iterators remain.
+    fn drain_into_inner<D: Sink<Item<S>>>(mut self, res: &mut D) {
+        if self.comparator.deduplicates() {
+            // Every item has to be checked against the previous one
+            while let Some(item) = self.pop_front_item() {
+                res.push(item);
+            }
+            return;
+        }
+        if self.storage.is_empty() {
+            return;
+        }
         // SAFETY: len >= 1, therefore pointer to first is valid. We won't create other pointers to
         //         the first element in this function, so it's unique.
         let mut first = unsafe { UniquePtr::new(*self.storage.first()) };
@@ -173,6 +659,12 @@ where
             let mut second = unsafe { UniquePtr::new(*self.storage.second()) };
             loop {
                 if let Some(item) = first.advance() {
+                    #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+                    self.debug_check_next(
+                        self.storage.source_index(first.as_ptr()),
+                        &item,
+                        &first.item,
+                    );
                     res.push(item);
                     if self.cmp(&*first, &*second).is_le() {
                         // order is still correct
@@ -186,6 +678,7 @@ where
                             .second()
                             .write(mem::replace(&mut first, second).into_ptr());
                         self.storage.first().write(first.as_ptr());
+                        self.cache_key(1);
                     }
 
                     // SAFETY: heap is not empty
@@ -208,6 +701,7 @@ where
                     unsafe {
                         // last replaces second
                         self.storage.second().write(self.storage.pop_last());
+                        self.storage.swap_cached_keys(1, self.storage.len());
                         // second replaces first
                         self.storage.first().write(second.as_ptr());
                     };
@@ -217,6 +711,7 @@ where
                         // SAFETY: heap updated, there's no way to get another instance of first
                         unsafe { popped.into_owning_ptr() }.into_last_item(),
                     );
+                    trace_event!(remaining = self.storage.len(), "source exhausted");
 
                     if self.storage.len() == 2 {
                         break;
@@ -236,6 +731,12 @@ where
             // We are not updating the heap when there are only two iterators left. Heap remains in
             // the correct state for drop handling, just the order of items may be incorrect
             while let Some(item) = first.advance() {
+                #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+                self.debug_check_next(
+                    self.storage.source_index(first.as_ptr()),
+                    &item,
+                    &first.item,
+                );
                 res.push(item);
                 if self.cmp(&*first, &*second).is_gt() {
                     mem::swap(&mut first, &mut second);
@@ -256,6 +757,7 @@ where
             let popped = mem::replace(&mut first, second);
             // SAFETY: Now heap is in state [second], the only reference to popped is ours
             res.push(unsafe { popped.into_owning_ptr() }.into_last_item());
+            trace_event!(remaining = 1_usize, "source exhausted");
         }
         debug_assert!(self.storage.len() == 1);
         // SAFETY: storage.len() > 0 (storage.len() == 1)
@@ -263,15 +765,121 @@ where
             self.storage.set_len(0);
         }
 
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        let source = self.storage.source_index(first.as_ptr());
         // SAFETY: Now heap is empty, the only reference to first is ours.
         let PeekIter { item, iter } = unsafe { first.into_owning_ptr() }.read();
-        res.push(item);
-        res.extend(iter);
-        res
+        #[cfg(not(all(debug_assertions, iter_merge_debug_checks)))]
+        {
+            res.push(item);
+            res.extend_from(iter);
+        }
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        {
+            let mut prev = item;
+            for next in iter {
+                self.debug_check_next(source, &prev, &next);
+                res.push(mem::replace(&mut prev, next));
+            }
+            res.push(prev);
+        }
     }
 
+    /// Moves items into `res` while they satisfy `func`, keeping the first rejected item
+    /// peeked.
+    ///
+    /// Once a single iterator remains, its items are moved without touching the heap.
+    pub(crate) fn drain_while_into<D: Sink<Item<S>>>(
+        &mut self, res: &mut D, mut func: impl FnMut(&Item<S>) -> bool,
+    ) {
+        if self.comparator.deduplicates() {
+            while self.storage.peek().map_or(false, &mut func) {
+                // SAFETY: heap is not empty
+                res.push(unsafe { self.pop_front_item().unwrap_unchecked() });
+            }
+            return;
+        }
+        while self.storage.len() > 1 {
+            // SAFETY: len >= 2, no mutable references into the heap exist
+            if !func(unsafe { &(**self.storage.first()).item }) {
+                return;
+            }
+            // SAFETY: heap is not empty
+            res.push(unsafe { self.pop_front_item().unwrap_unchecked() });
+        }
+        if self.storage.is_empty() {
+            return;
+        }
+        {
+            #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+            let source = self.storage.first_source_index();
+            // SAFETY: len == 1, no other references into the heap exist
+            let first = unsafe { &mut **self.storage.first() };
+            if !func(&first.item) {
+                return;
+            }
+            for next in first.iter.by_ref() {
+                #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+                self.debug_check_next(source, &first.item, &next);
+                res.push(mem::replace(&mut first.item, next));
+                if !func(&first.item) {
+                    return;
+                }
+            }
+        }
+        trace_event!(remaining = 0_usize, "source exhausted");
+        // SAFETY: heap is not empty
+        res.push(unsafe { self.pop_front_iter().unwrap_unchecked() }.item);
+    }
+
+    /// Replaces the item of the first iterator, then restores the heap order.
+    /// Returns `Err(item)` if the heap is empty.
+    pub(crate) fn replace_first_item(&mut self, item: Item<S>) -> Result<Item<S>, Item<S>> {
+        if self.storage.is_empty() {
+            return Err(item);
+        }
+        // SAFETY: len >= 1, no other references into the heap exist
+        let old = mem::replace(unsafe { &mut (**self.storage.first()).item }, item);
+        // SAFETY: only the first element changed, no references into the heap are live
+        unsafe {
+            self.fix_at(0);
+        }
+        Ok(old)
+    }
+
+    /// Pops the first item. If the comparator [deduplicates](Comparator::deduplicates) the
+    /// merge, the following duplicates of the item are popped and dropped too.
     pub(crate) fn pop_front_item(&mut self) -> Option<Item<S>> {
-        Some(match self.storage.len() {
+        let item = self.pop_next()?;
+        if self.comparator.deduplicates() {
+            self.skip_duplicates(&item);
+        }
+        Some(item)
+    }
+
+    /// Drops the peeked items while they are duplicates of the yielded `item`
+    fn skip_duplicates(&mut self, item: &Item<S>) {
+        while let Some(next) = self.storage.peek() {
+            #[cfg(feature = "stats")]
+            {
+                self.stats.comparisons += 1;
+            }
+            if !self.comparator.is_duplicate(item, next) {
+                return;
+            }
+            drop(self.pop_next());
+        }
+    }
+
+    fn pop_next(&mut self) -> Option<Item<S>> {
+        #[cfg(feature = "stats")]
+        {
+            let source = self.storage.first_source_index();
+            self.stats.item(source);
+        }
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        let source = self.storage.first_source_index();
+        let item = match self.storage.len() {
             2 => {
                 let mut first = unsafe { UniquePtr::new(*self.storage.first()) };
                 let second = unsafe { UniquePtr::new(*self.storage.second()) };
@@ -281,6 +889,7 @@ where
                         unsafe {
                             self.storage.first().write(second.into_ptr());
                             self.storage.second().write(first.into_ptr());
+                            self.cache_key(1);
                         }
                     }
                     item
@@ -289,6 +898,7 @@ where
                     unsafe {
                         self.storage.set_len(1);
                     }
+                    trace_event!(remaining = 1_usize, "source exhausted");
                     unsafe {
                         // second replaces first
                         self.storage.first().write(second.into_ptr());
@@ -301,6 +911,7 @@ where
             1 => {
                 let mut first = unsafe { UniquePtr::new(*self.storage.first()) };
                 first.advance().unwrap_or_else(|| {
+                    trace_event!(remaining = 0_usize, "source exhausted");
                     // SAFETY: len() == 1, first is removed from heap
                     unsafe {
                         self.storage.set_len(0);
@@ -321,6 +932,7 @@ where
                         unsafe {
                             self.storage.first().write(second.into_ptr());
                             self.storage.second().write(first.into_ptr());
+                            self.cache_key(1);
                             // SAFETY: no references to heap are live and len() >= 3
                             self.sift_down_top();
                         }
@@ -330,12 +942,14 @@ where
                     let item = unsafe {
                         // last replaces first
                         self.storage.second().write(self.storage.pop_last());
+                        self.storage.swap_cached_keys(1, self.storage.len());
                         // second replaces first
                         self.storage.first().write(second.into_ptr());
                         // first is no longer accessible from the heap
                         first.into_owning_ptr()
                     }
                     .into_last_item();
+                    trace_event!(remaining = self.storage.len(), "source exhausted");
 
                     // SAFETY: no references to heap are live and len() >= 2
                     unsafe {
@@ -345,7 +959,197 @@ where
                     // SAFETY: len() >= 3, first is removed from heap
                 }
             }
-        })
+        };
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        self.debug_check_order(source, &item);
+        Some(item)
+    }
+
+    /// Panics if the popped `item` from the `source` is greater than the new first item
+    #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+    fn debug_check_order(&mut self, source: Option<usize>, item: &Item<S>) {
+        match self.cmp_peeked(item) {
+            None | Some(Ordering::Greater | Ordering::Equal) => return,
+            Some(Ordering::Less) if self.peeked_equivalent(item) => return,
+            Some(Ordering::Less) => {}
+        }
+        let source = source.expect("storage doesn't support source indexes");
+        let next_source = self
+            .storage
+            .first_source_index()
+            .expect("storage doesn't support source indexes");
+        if next_source == source {
+            panic!("source {source} yielded items out of order");
+        }
+        panic!("merged output is out of order: item from the source {next_source} is smaller \
+            than the item from the source {source}, the comparator is inconsistent");
+    }
+
+    /// Panics if `next`, the item that followed `prev` in the `source`, is smaller than it.
+    /// Used by the paths that advance the iterators without popping the items one by one.
+    #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+    fn debug_check_next(&self, source: Option<usize>, prev: &Item<S>, next: &Item<S>) {
+        if self.comparator.compare(next, prev).is_lt() && !self.comparator.equivalent(next, prev) {
+            let source = source.expect("storage doesn't support source indexes");
+            panic!("source {source} yielded items out of order");
+        }
+    }
+
+    /// Panics if the heap order is broken, which happens if the comparator is inconsistent.
+    /// Used after inserting the sources into a heap that's being consumed.
+    #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+    pub(crate) fn debug_check_heap(&self) {
+        let heap = self.storage.heap();
+        // [0] is the smallest item, [1] is the root of the rest, the children of `pos` are at
+        // `2 * pos` and `2 * pos + 1`, so the parent of every `pos` is at `pos / 2`
+        for pos in 1..self.storage.len() {
+            // SAFETY: pos and pos / 2 are within the heap, no &mut into the heap exist
+            let (parent, child) = unsafe { (&**heap.add(pos / 2), &**heap.add(pos)) };
+            assert!(
+                self.comparator
+                    .compare_sources(&parent.item, &child.item, self.ranks(parent, child))
+                    .is_le(),
+                "heap order is broken, the comparator is inconsistent"
+            );
+        }
+    }
+
+    /// Skips `n` items, returning the next one, see [`Iterator::nth`].
+    ///
+    /// The first iterator skips its items in bulk, without touching the heap, while they stay
+    /// before the head of the runner-up. Once a single iterator remains, its items are skipped
+    /// via its own [`Iterator::nth`].
+    pub(crate) fn nth(&mut self, mut n: usize) -> Option<Item<S>> {
+        if self.comparator.deduplicates() {
+            // Skipped items still hide their duplicates
+            for _ in 0..n {
+                self.pop_front_item()?;
+            }
+            return self.pop_front_item();
+        }
+        while n > 0 && self.storage.len() > 1 {
+            // SAFETY: len >= 2, first and second are distinct, no other references into the
+            // heap exist
+            let mut first = unsafe { UniquePtr::new(*self.storage.first()) };
+            let second = unsafe { UniquePtr::new(*self.storage.second()) };
+            #[cfg(feature = "stats")]
+            let (source, n_before) = (self.storage.source_index(first.as_ptr()), n);
+            let mut exhausted = false;
+            let mut out_of_order = false;
+            while n > 0 {
+                // Skipping the peeked item requires the next one
+                let Some(skipped) = first.advance() else {
+                    exhausted = true;
+                    break;
+                };
+                #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+                self.debug_check_next(
+                    self.storage.source_index(first.as_ptr()),
+                    &skipped,
+                    &first.item,
+                );
+                drop(skipped);
+                n -= 1;
+                if self.cmp(&first, &second).is_gt() {
+                    out_of_order = true;
+                    break;
+                }
+            }
+            if out_of_order {
+                // SAFETY: len >= 2, only the first element changed
+                unsafe {
+                    self.storage.first().write(second.into_ptr());
+                    self.storage.second().write(first.into_ptr());
+                    self.cache_key(1);
+                    self.sift_down_top();
+                }
+            } else if exhausted {
+                // The last item of the first iterator is still in order, it's skipped together
+                // with the iterator
+                drop(self.pop_front_iter());
+                n -= 1;
+            }
+            #[cfg(feature = "stats")]
+            self.stats.items(source, (n_before - n) as u64);
+        }
+        if n > 0 && !self.storage.is_empty() {
+            #[cfg(any(feature = "stats", all(debug_assertions, iter_merge_debug_checks)))]
+            let source = self.storage.first_source_index();
+            // SAFETY: len == 1, no other references into the heap exist
+            let first = unsafe { &mut **self.storage.first() };
+            // The peeked item is the 0th one
+            #[cfg(feature = "stats")]
+            let (next, skipped) = nth_counted(&mut first.iter, n - 1);
+            #[cfg(not(feature = "stats"))]
+            let next = first.iter.nth(n - 1);
+            #[cfg(feature = "stats")]
+            self.stats.items(source, 1 + skipped as u64);
+            match next {
+                Some(item) => {
+                    // The skipped items are never seen, only the ends of the run are compared
+                    #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+                    self.debug_check_next(source, &first.item, &item);
+                    drop(mem::replace(&mut first.item, item));
+                }
+                None => {
+                    trace_event!(remaining = 0_usize, "source exhausted");
+                    drop(self.pop_front_iter());
+                    return None;
+                }
+            }
+        }
+        self.pop_front_item()
+    }
+
+    /// Consumes the heap, returning the greatest of the last items of all iterators.
+    ///
+    /// Last items are written back in place of the peeked items, so the comparator
+    /// (and, most importantly, the tie breaker) sees them at their original addresses.
+    pub(crate) fn last(mut self) -> Option<Item<S>> {
+        if self.comparator.deduplicates() {
+            // The first item of the last run is yielded, not the last one
+            let mut last = None;
+            while let Some(item) = self.pop_front_item() {
+                last = Some(item);
+            }
+            return last;
+        }
+        let mut best: Option<UniqueOwningPtr<Item<S>>> = None;
+        // The (logically uninitialized) PeekIter of the best item, only its address is used
+        // to look up the rank
+        let mut best_source = ptr::null_mut();
+        while !self.storage.is_empty() {
+            // SAFETY: len != 0. Storage no longer owns the popped PeekIter, we're the
+            // only owner of it.
+            let peek_iter = unsafe { self.storage.pop_last() };
+            // SAFETY: peek_iter is valid and owned by us. After this read its memory is
+            // logically uninitialized.
+            let PeekIter { item, iter } = unsafe { peek_iter.read() };
+            let last = iter.last().unwrap_or(item);
+            // SAFETY: item field is valid for writes, it's uninitialized, so we're
+            // not leaking anything; afterwards it's owned by the candidate
+            let candidate = unsafe {
+                let item_ptr = addr_of_mut!((*peek_iter).item);
+                item_ptr.write(last);
+                UniqueOwningPtr::new(item_ptr)
+            };
+            match &best {
+                Some(current)
+                    if self
+                        .comparator
+                        .compare_sources(&candidate, current, self.ranks(peek_iter, best_source))
+                        .is_le() =>
+                {
+                    // candidate is dropped
+                }
+                // previous best (if any) is dropped
+                _ => {
+                    best = Some(candidate);
+                    best_source = peek_iter;
+                }
+            }
+        }
+        best.map(UniqueOwningPtr::read)
     }
 
     pub(crate) fn pop_front_iter(&mut self) -> Option<PeekIter<Iter<S>>> {
@@ -358,6 +1162,7 @@ where
                         .first()
                         .replace(self.storage.second().replace(self.storage.pop_last()))
                         .read();
+                    self.storage.swap_cached_keys(1, self.storage.len());
                 }
                 2 => item = self.storage.first().replace(self.storage.pop_last()).read(),
                 1 => item = self.storage.pop_last().read(),
@@ -369,6 +1174,7 @@ where
                         .first()
                         .replace(self.storage.second().replace(self.storage.pop_last()))
                         .read();
+                    self.storage.swap_cached_keys(1, self.storage.len());
                     self.sift_down_top();
                 }
             }
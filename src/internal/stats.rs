@@ -0,0 +1,33 @@
+//! Counters of the `stats` feature, see [`MergeStats`](crate::merge_iter::MergeStats).
+
+use alloc::vec::Vec;
+
+/// Counters of the operations performed by the [`Heap`](super::Heap)
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counters {
+    pub(crate) comparisons: u64,
+    pub(crate) sifts: u64,
+    /// Items yielded per source index, grown on demand
+    pub(crate) items: Vec<u64>,
+}
+
+impl Counters {
+    /// Records an item yielded from the source with `index`, if the storage knows it
+    #[inline]
+    pub(crate) fn item(&mut self, index: Option<usize>) {
+        self.items(index, 1);
+    }
+
+    /// Records `count` items yielded (or skipped) from the source with `index`, if the storage
+    /// knows it
+    #[inline]
+    pub(crate) fn items(&mut self, index: Option<usize>, count: u64) {
+        let Some(index) = index else {
+            return;
+        };
+        if index >= self.items.len() {
+            self.items.resize(index + 1, 0);
+        }
+        self.items[index] += count;
+    }
+}
@@ -0,0 +1,65 @@
+//! Optional instrumentation via the `tracing` crate.
+//!
+//! Without the `tracing` feature the macros expand to nothing, so the instrumentation
+//! has no cost.
+
+#[cfg(feature = "tracing")]
+use super::heap::Sink;
+
+/// Target of all spans and events emitted by this crate
+#[cfg(feature = "tracing")]
+pub(crate) const TARGET: &str = "iter_merge";
+
+/// Enters a trace-level span until the end of the scope
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        tracing::trace_span!(target: $crate::internal::trace::TARGET, $($args)*).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        $crate::internal::trace::NoSpan
+    };
+}
+
+/// Emits a trace-level event
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($($args:tt)*) => {
+        tracing::trace!(target: $crate::internal::trace::TARGET, $($args)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ($($args:tt)*) => {};
+}
+
+/// Placeholder for the entered span without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+/// [`Sink`] that counts the items pushed into another one
+#[cfg(feature = "tracing")]
+pub(crate) struct CountingSink<'a, D> {
+    pub(crate) sink: &'a mut D,
+    pub(crate) items: usize,
+}
+
+#[cfg(feature = "tracing")]
+impl<T, D: Sink<T>> Sink<T> for CountingSink<'_, D> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        self.items = self.items.wrapping_add(1);
+        self.sink.push(item);
+    }
+
+    #[inline]
+    fn extend_from(&mut self, iter: impl Iterator<Item = T>) {
+        let items = &mut self.items;
+        self.sink.extend_from(iter.inspect(|_| *items = items.wrapping_add(1)));
+    }
+}
@@ -0,0 +1,135 @@
+//! Panic isolation for the sources of the [`MergeIter`](crate::MergeIter).
+//!
+//! Wrap the sources that may panic (e.g. parsers of untrusted input files) in [`Isolated`]:
+//! a panic raised by the source's `next` is caught, the source is dropped from the merge and
+//! the panic payload is passed to the `on_error` hook, while the rest of the sources are
+//! merged as usual.
+
+use core::{any::Any, iter::FusedIterator};
+use std::{
+    boxed::Box,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+/// Payload of the caught panic, see [`std::panic::catch_unwind`]
+pub type Panic = Box<dyn Any + Send + 'static>;
+
+/// Iterator that ends when the inner iterator panics, reporting the panic to the hook.
+///
+/// After the panic the inner iterator is dropped (it may be in an inconsistent state), and
+/// [`Isolated`] yields no more items. Panics raised while dropping the inner iterator are
+/// not caught.
+///
+/// # Example
+/// ```
+/// use std::cell::RefCell;
+///
+/// use iter_merge::{isolate::Isolated, merge};
+///
+/// let errors = RefCell::new(Vec::new());
+/// let on_error = |file| {
+///     let errors = &errors;
+///     move |_panic| errors.borrow_mut().push(file)
+/// };
+/// let parse = |lines: &'static [&'static str]| {
+///     lines.iter().map(|line| line.parse::<i32>().unwrap())
+/// };
+///
+/// let merged = merge([
+///     Isolated::new(parse(&["1", "4", "6"]), on_error("a.log")),
+///     Isolated::new(parse(&["2", "corrupt", "5"]), on_error("b.log")),
+/// ]);
+/// assert!(merged.eq([1, 2, 4, 6]));
+/// assert_eq!(errors.into_inner(), ["b.log"]);
+/// ```
+pub struct Isolated<IT, F> {
+    iter: Option<IT>,
+    on_error: F,
+}
+
+impl<IT: Iterator, F: FnMut(Panic)> Isolated<IT, F> {
+    /// Wraps the `iter`, reporting its panic to `on_error`
+    #[inline]
+    pub fn new<I: IntoIterator<IntoIter = IT>>(iter: I, on_error: F) -> Self {
+        Self {
+            iter: Some(iter.into_iter()),
+            on_error,
+        }
+    }
+}
+
+impl<IT, F> Isolated<IT, F> {
+    /// Returns `true` if the inner iterator has panicked and was dropped
+    #[inline]
+    pub fn has_panicked(&self) -> bool {
+        self.iter.is_none()
+    }
+}
+
+impl<IT: core::fmt::Debug, F> core::fmt::Debug for Isolated<IT, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Isolated")
+            .field("iter", &self.iter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<IT: Iterator, F: FnMut(Panic)> Iterator for Isolated<IT, F> {
+    type Item = IT::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let iter = self.iter.as_mut()?;
+        // The iterator is dropped after the panic, so its broken state is never observed
+        match catch_unwind(AssertUnwindSafe(|| iter.next())) {
+            Ok(item) => item,
+            Err(panic) => {
+                self.iter = None;
+                (self.on_error)(panic);
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // The inner iterator may panic at any point
+        (0, self.iter.as_ref().map_or(Some(0), |iter| iter.size_hint().1))
+    }
+}
+
+impl<IT: FusedIterator, F: FnMut(Panic)> FusedIterator for Isolated<IT, F> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec, vec::Vec};
+    use core::cell::RefCell;
+
+    use super::{Isolated, Panic};
+    use crate::VecStorage;
+
+    #[test]
+    fn isolated() {
+        let errors = RefCell::new(Vec::new());
+        let faulty = |tag: i32, panic_at: i32| {
+            (1..10).map(move |item| {
+                assert!(item != panic_at, "corrupt record {item}");
+                item * 10 + tag
+            })
+        };
+        // Captures the errors by reference, so it's Copy
+        let on_error = |panic: Panic| {
+            errors
+                .borrow_mut()
+                .push(*panic.downcast::<String>().unwrap());
+        };
+        let merged = VecStorage::from_iter([
+            Isolated::new(faulty(1, 3), on_error),
+            Isolated::new(faulty(2, 0), on_error),
+            Isolated::new(faulty(3, 2), on_error),
+        ])
+        .build();
+        let res: Vec<i32> = merged.collect();
+        assert_eq!(res, [11, 12, 13, 21, 22, 32, 42, 52, 62, 72, 82, 92]);
+        assert_eq!(*errors.borrow(), vec!["corrupt record 2", "corrupt record 3"]);
+    }
+}
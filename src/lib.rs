@@ -114,15 +114,42 @@
 //! # Crate Features
 //! - `alloc`: Enables heap-allocated storage with [`VecStorage`] and methods like
 //!   [`MergeIter::into_vec`]
+//! - `alloc-audit`: Enables `MergeIter::into_vec_audited`, reporting the allocations made while
+//!   collecting the items
+//! - `tracing`: Emits trace-level [`tracing`](https://docs.rs/tracing) spans for construction,
+//!   heapify and draining of the [`MergeIter`] (with the number of the drained items), and
+//!   an event for every exhausted source. All of them use the `iter_merge` target
+//! - `std`: Enables `isolate` module, dropping the sources that panic from the merge, and
+//!   `nested` module, dropping the deep trees of nested merges without overflowing the stack
+//! - `stats`: Enables `MergeIter::stats`, counting the comparisons, heap sifts and the items
+//!   yielded from each source
+//! - `natural`: Enables `comparators::Natural`, comparing strings with the runs of digits
+//!   compared as numbers
+//! - `collation`: Enables `comparators::Collated`, comparing strings according to the collation
+//!   rules of a locale with the [`icu_collator`](https://docs.rs/icu_collator) crate
+//! - `arrow`: Enables `arrow` module, merging the [Arrow](https://docs.rs/arrow) record batches
+//!   sorted by a key column into the merged batch or the list of the indices of its rows
+//!
+//! # Debug Checks
+//! Building with `RUSTFLAGS="--cfg iter_merge_debug_checks"` enables assertions (in debug
+//! builds only) that panic with the index of the offending source when a source yields its
+//! items out of order, or when the comparator is inconsistent and the merged output is out of
+//! order. The items skipped or drained in bulk are checked too, and the heap order is validated
+//! after adding or swapping the sources of a merge that's being consumed. See
+//! [`MergeIter::checked`] for the validation in release builds.
 #![no_std]
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 #![cfg_attr(not(feature = "alloc"), allow(unused))]
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub mod comparators;
 pub mod merge_iter;
+pub mod meta;
+pub mod seek;
 pub mod storage;
 
 pub use merge_iter::MergeIter;
@@ -130,6 +157,17 @@ pub use storage::ArrayStorage;
 #[cfg(feature = "alloc")]
 pub use storage::VecStorage;
 
+#[cfg(feature = "alloc")]
+pub mod radix;
+#[cfg(feature = "alloc")]
+pub mod sorted_vec;
+#[cfg(feature = "std")]
+pub mod isolate;
+#[cfg(feature = "std")]
+pub mod nested;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
 #[cfg(feature = "alloc")]
 mod convenience;
 #[cfg(feature = "alloc")]
@@ -1,19 +1,125 @@
 //! Implementation of [`MergeIter`]
 
-use core::iter::FusedIterator;
+use core::{
+    fmt::{Debug, Display},
+    iter::FusedIterator,
+    mem::ManuallyDrop,
+    ptr,
+};
 
 use crate::{
-    comparators::Comparator,
-    internal::{Heap, Item},
+    comparators::{Comparator, Directed, Direction, MaxFirst},
+    internal::{ExtendSink, Heap, Item, Iter, PeekIter, precedes},
+    meta::WithMeta,
+    seek::SeekableSource,
     storage::Storage,
 };
 
+mod adapters;
 mod builder;
 mod into_iters;
-pub use builder::{Builder, DefaultBuilder, DefaultMergeIter};
-pub use into_iters::{ItersIter, UnorderedItersIter};
+#[cfg(feature = "alloc")]
+pub use adapters::GroupBy;
+pub use adapters::{
+    ArrayRemainder, ChainAfter, Checked, CoalesceEqual, Dedup, DedupByKey, DedupWithCount,
+    EndBefore, Interleave, LazyMergeIter, Limit, MetaTagged, RouteBy, Run, SourceTagged,
+    TakeUntil, WeightedInterleave,
+};
+pub use builder::{Builder, DefaultBuilder, DefaultMergeIter, MergeConfig, MergeIterWith};
+pub use into_iters::{IntoSources, ItersIter, UnorderedItersIter};
+
+/// Error signaling that the [`MergeIter`] contained more items than the specified limit
+///
+/// Holds the partial result: items collected before the limit was reached.
+#[derive(Debug, Clone)]
+pub struct ItemLimitExceeded<T>(T);
+
+impl<T> ItemLimitExceeded<T> {
+    /// Returns a reference to the items collected before the limit was reached
+    #[inline]
+    pub const fn partial(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns the items collected before the limit was reached
+    #[inline]
+    pub fn into_partial(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Display for ItemLimitExceeded<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Item limit exceeded")
+    }
+}
+
+#[rustversion::since(1.81)]
+impl<T: Debug> core::error::Error for ItemLimitExceeded<T> {}
+
+/// Error signaling that a source of the [`MergeIter`] yielded its items out of order,
+/// see [`MergeIter::checked`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsortedSource(usize);
+
+impl UnsortedSource {
+    /// Returns the index of the offending source, see [`MergeIter::source_tagged`]
+    #[inline]
+    pub const fn source_index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Display for UnsortedSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Source {} yielded items out of order", self.0)
+    }
+}
+
+#[rustversion::since(1.81)]
+impl core::error::Error for UnsortedSource {}
+
+/// Allocations made by the [`MergeIter`] while collecting the items,
+/// see [`MergeIter::into_vec_audited`]
+#[cfg(feature = "alloc-audit")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Number of the fresh allocations
+    pub allocations: usize,
+    /// Number of the reallocations of the already allocated memory
+    pub reallocations: usize,
+}
+
+/// Operations performed by the [`MergeIter`], see [`MergeIter::stats`]
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Number of the comparator calls
+    pub comparisons: u64,
+    /// Number of the sift up and sift down operations on the heap
+    pub sifts: u64,
+    /// Number of the items yielded from each source, indexed in order of insertion.
+    ///
+    /// Empty if the storage doesn't know the source indexes (see
+    /// [`MergeIter::peek_source`]).
+    pub items_per_source: alloc::vec::Vec<u64>,
+}
+
+/// Snapshot of the progress of the [`MergeIter`], see [`MergeIter::mark`]
+#[derive(Debug, Clone)]
+pub struct Checkpoint<S, CMP>(MergeIter<S, CMP>);
 
 /// Iterator over merged iterators
+///
+/// # Panics
+///
+/// If the comparator or a component iterator panics, the [`MergeIter`] remains safe to use:
+/// no source is lost (if the comparator panicked, the item that was being yielded by the
+/// panicking call is dropped; if an iterator panicked, its peeked item stays in place), but
+/// the items may be yielded in an incorrect order until the heap order is restored by
+/// [`rebuild`](MergeIter::rebuild) (or by any other method rebuilding the heap, e.g.
+/// [`inspect_sources_mut`](MergeIter::inspect_sources_mut), which also allows skipping the
+/// item that made the comparator panic).
 #[derive(Debug, Clone)]
 #[repr(transparent)]
 pub struct MergeIter<S, CMP>(pub(crate) Heap<S, CMP>);
@@ -43,11 +149,13 @@ where
         self.0.into_vec()
     }
 
-    /// Returns a reference to the next item that will be returned by `next()` without
-    /// consuming it.
+    #[cfg(feature = "alloc")]
+    /// Merges items into two [`Vec`](alloc::vec::Vec)s: the items before the first item that
+    /// doesn't precede `bound` according to the comparator (see
+    /// [`take_until`](Self::take_until)), and the rest of the items.
     ///
-    /// This method behaves identically to [`Peekable::peek`] from the standard library:
-    /// it returns a reference to the next item, or `None` if the iterator is empty.
+    /// The tail is collected like in [`into_vec`](Self::into_vec), so it benefits from the
+    /// same optimizations.
     ///
     /// # Examples
     ///
@@ -56,24 +164,101 @@ where
     /// # {
     /// use iter_merge::merge;
     ///
-    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4, 6]]);
+    /// let (current, future) = merge([vec![1, 5, 9], vec![2, 6, 10]]).partition_at(&6);
+    /// assert_eq!(current, vec![1, 2, 5]);
+    /// assert_eq!(future, vec![6, 9, 10]);
+    /// # }
+    /// ```
+    pub fn partition_at(
+        mut self, bound: &Item<S>,
+    ) -> (alloc::vec::Vec<Item<S>>, alloc::vec::Vec<Item<S>>) {
+        let head = self.take_until(bound).collect();
+        (head, self.into_vec())
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Merges items into a [`Vec`](alloc::vec::Vec) while they satisfy the predicate, leaving
+    /// the first rejected item to be yielded next.
     ///
-    /// assert_eq!(merged.peek(), Some(&1));
-    /// assert_eq!(merged.next(), Some(1));
-    /// assert_eq!(merged.peek(), Some(&2));
+    /// Like [`into_vec`](Self::into_vec), once a single iterator remains its items are moved
+    /// without the heap bookkeeping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 5, 9], vec![2, 6, 10]]);
+    /// assert_eq!(merged.into_vec_while(|&item| item < 6), vec![1, 2, 5]);
+    /// assert_eq!(merged.next(), Some(6));
     /// # }
     /// ```
+    pub fn into_vec_while(
+        &mut self, func: impl FnMut(&Item<S>) -> bool,
+    ) -> alloc::vec::Vec<Item<S>> {
+        let mut res = alloc::vec::Vec::new();
+        self.0.drain_while_into(&mut res, func);
+        res
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Merges items into a [`Vec`](alloc::vec::Vec), skipping the items equivalent to the
+    /// previously collected one according to the comparator (see [`dedup`](Self::dedup))
     ///
-    /// [`Peekable::peek`]: core::iter::Peekable::peek
-    #[inline]
-    pub fn peek(&self) -> Option<&Item<S>> {
-        self.0.storage.peek()
+    /// Like [`into_vec`](Self::into_vec) followed by
+    /// [`Vec::dedup_by`](alloc::vec::Vec::dedup_by), but in a single pass: duplicates are never
+    /// written into the output. If the sources are sorted, the result contains only unique
+    /// items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    /// let v = merge([vec![1, 2, 2, 4], vec![2, 3, 4]]).into_dedup_vec();
+    /// assert_eq!(v, vec![1, 2, 3, 4]);
+    /// # }
+    /// ```
+    pub fn into_dedup_vec(self) -> alloc::vec::Vec<Item<S>> {
+        self.0.into_dedup_vec()
     }
 
-    /// Returns the next item of the iterator if it satisfies a predicate.
+    #[cfg(feature = "alloc-audit")]
+    /// Same as [`into_vec`](Self::into_vec), but also reports how many times the resulting
+    /// [`Vec`](alloc::vec::Vec) was allocated and reallocated.
     ///
-    /// This method behaves identically to [`Peekable::next_if`] from the standard library:
-    /// it returns the next item if it satisfies the predicate, otherwise returns `None`.
+    /// Reallocations mean that the [`size_hint`](Iterator::size_hint)s of the sources
+    /// underestimated the number of their items, so the pre-reservation didn't cover the
+    /// whole output. Collection is slower than [`into_vec`](Self::into_vec), this is a
+    /// diagnostic tool.
+    ///
+    /// Requires the `alloc-audit` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc-audit")]
+    /// # {
+    /// use iter_merge::{merge, merge_iter::AllocStats};
+    ///
+    /// let (v, stats) = merge([vec![1, 3, 5], vec![2, 4, 6]]).into_vec_audited();
+    /// assert_eq!(v, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(stats, AllocStats { allocations: 1, reallocations: 0 });
+    /// # }
+    /// ```
+    pub fn into_vec_audited(self) -> (alloc::vec::Vec<Item<S>>, AllocStats) {
+        self.0.into_vec_audited()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Efficiently merges items into a [`Box<[T]>`](alloc::boxed::Box)
+    ///
+    /// Memory is reserved upfront according to the lower bounds of the iterators' size hints.
+    /// If these bounds are exact, the allocation has exactly the required size and it's
+    /// not reallocated to drop the excess capacity.
     ///
     /// # Examples
     ///
@@ -81,192 +266,2627 @@ where
     /// # #[cfg(feature = "alloc")]
     /// # {
     /// use iter_merge::merge;
+    /// let v = merge([vec![1, 3, 5], vec![2, 4, 6]]).into_boxed_slice();
+    /// assert_eq!(&*v, &[1, 2, 3, 4, 5, 6]);
+    /// # }
+    /// ```
+    pub fn into_boxed_slice(self) -> alloc::boxed::Box<[Item<S>]> {
+        self.0.into_vec().into_boxed_slice()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Merges at most `max_items` items into a [`Vec`](alloc::vec::Vec)
     ///
-    /// let mut merged = merge([vec![1, 1, 2, 3], vec![1, 4, 5, 6]]);
+    /// Guards against unbounded (or unexpectedly large) iterators: instead of trying to
+    /// allocate an ever-growing vector, consumption stops once the limit is reached.
     ///
-    /// // Consume all 1s
-    /// while let Some(item) = merged.next_if(|&x| x == 1) {
-    ///     assert_eq!(item, 1);
-    /// }
+    /// # Errors
+    /// Returns [`ItemLimitExceeded`] containing the first `max_items` items if there are more
+    /// items left. The rest of the items are dropped.
     ///
-    /// assert_eq!(merged.next(), Some(2));
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use core::iter::repeat;
+    ///
+    /// use iter_merge::merge;
+    ///
+    /// let v = merge([vec![1, 3], vec![2]]).into_vec_limited(3);
+    /// assert_eq!(v.unwrap(), vec![1, 2, 3]);
+    ///
+    /// let err = merge([repeat(1).take(10), repeat(2).take(usize::MAX)])
+    ///     .into_vec_limited(12)
+    ///     .unwrap_err();
+    /// assert_eq!(err.partial().len(), 12);
     /// # }
     /// ```
+    pub fn into_vec_limited(
+        self, max_items: usize,
+    ) -> Result<alloc::vec::Vec<Item<S>>, ItemLimitExceeded<alloc::vec::Vec<Item<S>>>> {
+        let mut res = alloc::vec::Vec::new();
+        res.reserve_exact(self.size_hint().0.min(max_items));
+        match self.collect_into_limited(&mut res, max_items) {
+            Ok(()) => Ok(res),
+            Err(_) => Err(ItemLimitExceeded(res)),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Returns the first `n` items in merged order, dropping the rest of the items without
+    /// consuming them.
     ///
-    /// [`Peekable::next_if`]: core::iter::Peekable::next_if
-    pub fn next_if(&mut self, func: impl FnOnce(&Item<S>) -> bool) -> Option<Item<S>> {
+    /// With a max-first comparator this returns the `n` largest items of all sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{VecStorage, comparators::ByOrd};
+    ///
+    /// let shards = [vec![9, 5, 1], vec![8, 7, 2], vec![6, 4, 3]];
+    /// let top = VecStorage::from_iter(shards)
+    ///     .into_builder()
+    ///     .max_by(ByOrd)
+    ///     .build()
+    ///     .top_k(4);
+    /// assert_eq!(top, vec![9, 8, 7, 6]);
+    /// # }
+    /// ```
+    pub fn top_k(self, n: usize) -> alloc::vec::Vec<Item<S>> {
+        self.into_vec_limited(n)
+            .unwrap_or_else(ItemLimitExceeded::into_partial)
+    }
+
+    /// Moves at most `max_items` items into `collection` in merged order
+    ///
+    /// # Errors
+    /// Returns [`ItemLimitExceeded`] if there are more than `max_items` items. In this case
+    /// the first `max_items` items are moved into the `collection` and the rest are dropped.
+    pub fn collect_into_limited<E: Extend<Item<S>>>(
+        mut self, collection: &mut E, max_items: usize,
+    ) -> Result<(), ItemLimitExceeded<()>> {
+        collection.extend(self.by_ref().take(max_items));
         match self.peek() {
-            Some(item) if func(item) => {
-                // SAFETY: self.peek would've returned None if len == 0.
-                // Since the len > 0 - we always would have an item to produce
-                Some(unsafe { self.0.pop_front_item().unwrap_unchecked() })
-            }
-            _ => None,
+            Some(_) => Err(ItemLimitExceeded(())),
+            None => Ok(()),
         }
     }
 
-    /// Returns the next item of the iterator if it is equal to a given value.
-    ///
-    /// This method behaves identically to [`Peekable::next_if_eq`] from the standard library:
-    /// it returns the next item if it is equal to the given value, otherwise returns `None`.
+    /// Moves all of the remaining items into `collection` in merged order
     ///
-    /// This is a convenience method that is equivalent to `next_if(|item| item == expected)`.
+    /// Like [`into_vec`](Self::into_vec), this is faster than
+    /// <code>collection.[extend](Extend::extend)(self)</code> by optimizing merges with 2 or
+    /// 1 iterators remaining. This allows to reuse an existing collection between merges.
     ///
     /// # Examples
     ///
     /// ```
     /// # #[cfg(feature = "alloc")]
     /// # {
+    /// use std::collections::VecDeque;
+    ///
     /// use iter_merge::merge;
     ///
-    /// let mut merged = merge([vec![1, 1, 2, 3], vec![1, 4, 5, 6]]);
+    /// let mut res = VecDeque::from([0]);
+    /// merge([vec![1, 3, 5], vec![2, 4, 6]]).collect_into(&mut res);
+    /// assert_eq!(res, [0, 1, 2, 3, 4, 5, 6]);
+    /// # }
+    /// ```
+    pub fn collect_into<E: Extend<Item<S>>>(self, collection: &mut E) {
+        self.0.drain_into(&mut ExtendSink(collection));
+    }
+
+    /// Tags every item with a route key computed by `route`, yielding `(route_key, item)`
+    /// pairs in merged order.
     ///
-    /// // Consume all 1s
-    /// while let Some(item) = merged.next_if_eq(&1) {
-    ///     assert_eq!(item, 1);
-    /// }
+    /// # Examples
     ///
-    /// assert_eq!(merged.next(), Some(2));
-    /// # }
     /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
     ///
-    /// [`Peekable::next_if_eq`]: core::iter::Peekable::next_if_eq
-    pub fn next_if_eq<T>(&mut self, expected: &T) -> Option<Item<S>>
+    /// let routed = merge([vec![1, 4], vec![2, 3]]).route_by(|item| item % 2 == 0);
+    /// assert!(routed.eq([(false, 1), (true, 2), (false, 3), (true, 4)]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn route_by<K, F>(self, route: F) -> RouteBy<S, CMP, F>
     where
-        T: ?Sized,
-        Item<S>: PartialEq<T>,
+        F: FnMut(&Item<S>) -> K,
     {
-        self.next_if(|item| item == expected)
+        RouteBy { iter: self, route }
     }
 
-    /// Returns an iterator, yielding unordered tuples of `(peeked_item, iter)`
-    /// from the [`MergeIter`]
+    /// Tags every item with the index of the iterator it came from, yielding
+    /// `(source_index, item)` pairs in merged order.
     ///
-    /// No exact order is guaranteed, but you can expect the later iterators from [`MergeIter`]
-    /// to be yielded first, and the frontmost iterator (that would've been polled by
-    /// [`MergeIter::next()`]) to be yielded last.
+    /// The index is the position of the iterator among all of the iterators inserted into
+    /// the storage, including the empty ones. Indexes are derived from the storage layout,
+    /// so they don't depend on the comparator or the tie breaker.
     ///
-    /// Original [`MergeIter`] remains valid after use of this iterator, items yielded by this
-    /// iterator are excluded.
+    /// # Panics
+    /// The returned iterator panics if the storage doesn't provide the pointer to its
+    /// allocation (see [`BaseStorage::storage`](crate::internal::BaseStorage::storage)).
+    /// Storages provided by this crate always do.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let tagged = merge([vec![1, 4], vec![], vec![2, 3]]).source_tagged();
+    /// assert!(tagged.eq([(0, 1), (2, 2), (2, 3), (0, 4)]));
+    /// # }
+    /// ```
     #[inline]
-    pub fn as_unordered_iters(&mut self) -> UnorderedItersIter<'_, S> {
-        UnorderedItersIter(&mut self.0.storage)
+    pub fn source_tagged(self) -> SourceTagged<S, CMP> {
+        SourceTagged(self)
     }
 
-    /// Returns an ordered iterator, yielding tuples of `(peeked_item, iter)` from the [`MergeIter`]
+    /// Returns an iterator that yields at most `n` items, then drops all of the component
+    /// iterators.
     ///
-    /// Items are ordered according to value of `peeked_item`, as compared by the [`MergeIter`]'s
-    /// comparator
+    /// Unlike [`take`](Iterator::take), the iterators (and the resources they hold, e.g. open
+    /// files or cursors) are released as soon as the last item is yielded, not when the
+    /// returned iterator is dropped.
     ///
-    /// Original [`MergeIter`] remains valid after use of this iterator, items yielded by this
-    /// iterator are excluded.
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut top = merge([vec![1, 4], vec![2, 3]]).limit(2);
+    /// assert_eq!(top.next(), Some(1));
+    /// assert_eq!(top.next(), Some(2));
+    /// assert_eq!(top.next(), None);
+    /// # }
+    /// ```
     #[inline]
-    pub fn as_iters(&mut self) -> ItersIter<'_, S, CMP> {
-        ItersIter(&mut self.0)
+    pub fn limit(self, n: usize) -> Limit<S, CMP> {
+        Limit::new(self, n)
     }
-}
-
-impl<CMP, S> Iterator for MergeIter<S, CMP>
-where
-    S: Storage,
-    CMP: Comparator<Item<S>>,
-{
-    type Item = Item<S>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.pop_front_item()
+    /// Returns an iterator that verifies that every source yields its items in order,
+    /// according to the comparator of this [`MergeIter`] (tie breakers are ignored).
+    ///
+    /// Items are yielded as `Ok`. Once a source yields an item that should've been yielded
+    /// before its previous one, [`UnsortedSource`] error is yielded after that previous item,
+    /// the offending source is dropped, and the rest of the sources are merged as usual.
+    /// Use `.map(Result::unwrap)` to panic instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let checked: Vec<_> = merge([vec![1, 4], vec![2, 5, 3, 6]]).checked().collect();
+    /// assert_eq!(checked[..4], [Ok(1), Ok(2), Ok(4), Ok(5)]);
+    /// assert_eq!(checked[4].unwrap_err().source_index(), 1);
+    /// assert_eq!(checked.len(), 5);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn checked(self) -> Checked<S, CMP> {
+        Checked {
+            iter: self,
+            error: None,
+        }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        // this accounts for peeked items
-        let mut min = self.0.storage.len();
-        let mut max = min;
-        let mut no_max = false;
-        self.0.storage.map_items(|it| {
-            let (it_min, it_max) = it.iter.size_hint();
-            min = min.saturating_add(it_min);
-            let overflow;
-            // if we're here - storage.len()>0, and so is the initial max value
-            // If it_max is None it will become usize::MAX, and adding non-zero value to
-            // usize::MAX will overflow, correctly setting the no_max
-            (max, overflow) = max.overflowing_add(it_max.unwrap_or(usize::MAX));
-            no_max |= overflow;
-        });
-        // If any inner iterator has an unbounded upper bound, or the sum of
-        // upper bounds overflows a usize - overall upper bound is None.
-        (min, (!no_max).then_some(max))
+    /// Returns an iterator that yields the items preceding `bound` according to the comparator,
+    /// then drops all of the component iterators.
+    ///
+    /// Range scan for merges of sorted iterators: unlike [`take_until`](Self::take_until), the
+    /// iterators (and the resources they hold) are released as soon as the end of the range is
+    /// reached. Items equivalent to `bound` (see [`Comparator::equivalent`]) end the range,
+    /// regardless of the tie breaker. The items after the range are never visited, so there's
+    /// nothing to skip with [`SeekableSource::seek`]; pair it with [`seek_to`](Self::seek_to)
+    /// or [`Builder::seek_start_at`] to skip the items before the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let range = merge([vec![1, 5, 9], vec![2, 6]]).end_before(6);
+    /// assert!(range.eq([1, 2, 5]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn end_before(self, bound: Item<S>) -> EndBefore<S, CMP, Item<S>> {
+        EndBefore { iter: self, bound }
     }
 
-    fn count(mut self) -> usize
-    where
-        Self: Sized,
-    {
-        let mut count = 0;
-        while let Some((_, iter)) = self.0.storage.pop_last_item() {
-            // panic in debug and wrapping in release is the expected behaiour
-            #[allow(clippy::arithmetic_side_effects)]
-            {
+    /// Returns an iterator that skips the items equivalent to the previous yielded item.
+    ///
+    /// Equivalence is defined by the comparator of this [`MergeIter`], see
+    /// [`Comparator::equivalent`], tie breakers are ignored. The first item of every run of
+    /// equivalent items is yielded; with [`InsertionOrder`](crate::comparators::tie_breaker::InsertionOrder)
+    /// tie breaker it's the item from the earliest inserted iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge_by_key;
+    ///
+    /// let replica_a = vec![(1, 'a'), (2, 'a'), (4, 'a')];
+    /// let replica_b = vec![(1, 'b'), (3, 'b'), (4, 'b')];
+    /// let merged = merge_by_key([replica_a, replica_b], |item| item.0).dedup();
+    /// assert!(merged.eq([(1, 'a'), (2, 'a'), (3, 'b'), (4, 'a')]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn dedup(self) -> Dedup<S, CMP> {
+        Dedup(self)
+    }
+
+    /// Returns an iterator that skips the items with the same key as the previous yielded
+    /// item.
+    ///
+    /// Unlike [`dedup`](Self::dedup), the key doesn't have to match the order of the merge,
+    /// e.g. records ordered by timestamp can be deduplicated by their id, as long as the
+    /// duplicates are adjacent in the merged order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let primary = vec![(1, "login"), (5, "logout")];
+    /// let replica = vec![(1, "login"), (3, "click"), (5, "logout")];
+    /// let merged = merge([primary, replica]).dedup_by_key(|event| event.1);
+    /// assert!(merged.eq([(1, "login"), (3, "click"), (5, "logout")]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn dedup_by_key<K, F>(self, key: F) -> DedupByKey<S, CMP, F>
+    where
+        F: FnMut(&Item<S>) -> K,
+        K: PartialEq,
+    {
+        DedupByKey { iter: self, key }
+    }
+
+    /// Like [`dedup`](Self::dedup), but also yields the number of items in every run of
+    /// equivalent items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let counts = merge([vec!["a", "b", "b"], vec!["b", "c"], vec!["a"]]).dedup_with_count();
+    /// assert!(counts.eq([(2, "a"), (3, "b"), (1, "c")]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn dedup_with_count(self) -> DedupWithCount<S, CMP> {
+        DedupWithCount(self)
+    }
+
+    /// Returns an iterator that combines every run of equivalent items into one item with
+    /// `func(accumulated, next)`.
+    ///
+    /// The next item is compared with the accumulated one, so `func` should keep the
+    /// accumulated item equivalent to the combined ones. See [`dedup`](Self::dedup) for the
+    /// definition of equivalence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge_by_key;
+    ///
+    /// // (timestamp, value) metrics from two shards
+    /// let shard_a = vec![(1, 10), (2, 20), (4, 40)];
+    /// let shard_b = vec![(1, 1), (3, 3), (4, 4)];
+    /// let sums = merge_by_key([shard_a, shard_b], |metric| metric.0)
+    ///     .coalesce_equal(|(ts, acc), (_, value)| (ts, acc + value));
+    /// assert!(sums.eq([(1, 11), (2, 20), (3, 3), (4, 44)]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn coalesce_equal<F>(self, func: F) -> CoalesceEqual<S, CMP, F>
+    where
+        F: FnMut(Item<S>, Item<S>) -> Item<S>,
+    {
+        CoalesceEqual { iter: self, func }
+    }
+
+    /// Returns an iterator yielding `(key, group)` pairs, where `group` is a run of the
+    /// consecutive merged items for which `key` returns the same value.
+    ///
+    /// If the iterators are merged by the same key, every group holds all of the items with
+    /// that key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge_by_key;
+    ///
+    /// let orders = vec![("alice", 3), ("bob", 1)];
+    /// let refunds = vec![("alice", -1), ("carol", -2)];
+    /// let mut by_customer = merge_by_key([orders, refunds], |entry| entry.0)
+    ///     .group_by(|entry| entry.0);
+    /// assert_eq!(by_customer.next(), Some(("alice", vec![("alice", 3), ("alice", -1)])));
+    /// assert_eq!(by_customer.next(), Some(("bob", vec![("bob", 1)])));
+    /// assert_eq!(by_customer.next(), Some(("carol", vec![("carol", -2)])));
+    /// assert_eq!(by_customer.next(), None);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn group_by<K, F>(self, key: F) -> GroupBy<S, CMP, F>
+    where
+        F: FnMut(&Item<S>) -> K,
+        K: PartialEq,
+    {
+        GroupBy { iter: self, key }
+    }
+
+    /// Concatenates `other` after this [`MergeIter`] without merging them: all items of
+    /// `self` are yielded first, followed by all items of `other`.
+    ///
+    /// Use this when it's known that every item of `self` precedes every item of `other`
+    /// (e.g. per-day merges), it avoids the cost of another level of merging. The ordering
+    /// is not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let monday = merge([vec![1, 3], vec![2]]);
+    /// let tuesday = merge([vec![4], vec![5, 6]]);
+    /// assert_eq!(monday.chain_after(tuesday).into_vec(), vec![1, 2, 3, 4, 5, 6]);
+    /// # }
+    /// ```
+    pub fn chain_after<S2, CMP2>(self, other: MergeIter<S2, CMP2>) -> ChainAfter<S, CMP, S2, CMP2>
+    where
+        S2: Storage,
+        Iter<S2>: Iterator<Item = Item<S>>,
+        CMP2: Comparator<Item<S>>,
+    {
+        ChainAfter {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Consumes the [`MergeIter`], dispatching every item to `sinks[route(&item)]`.
+    ///
+    /// Items are dispatched in merged order, so every sink receives its items in the same
+    /// relative order as they were yielded by the [`MergeIter`].
+    ///
+    /// # Panics
+    /// Panics if `route` returns an index out of bounds of `sinks`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut sinks = [Vec::new(), Vec::new(), Vec::new()];
+    /// merge([vec![1, 4, 7], vec![2, 3, 9]]).demux_into(|item| item % 3, &mut sinks);
+    /// assert_eq!(sinks, [vec![3, 9], vec![1, 4, 7], vec![2]]);
+    /// # }
+    /// ```
+    pub fn demux_into<E, F>(self, mut route: F, sinks: &mut [E])
+    where
+        E: Extend<Item<S>>,
+        F: FnMut(&Item<S>) -> usize,
+    {
+        for item in self {
+            sinks[route(&item)].extend(Some(item));
+        }
+    }
+
+    /// Returns a reference to the next item that will be returned by `next()` without
+    /// consuming it.
+    ///
+    /// This method behaves identically to [`Peekable::peek`] from the standard library:
+    /// it returns a reference to the next item, or `None` if the iterator is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4, 6]]);
+    ///
+    /// assert_eq!(merged.peek(), Some(&1));
+    /// assert_eq!(merged.next(), Some(1));
+    /// assert_eq!(merged.peek(), Some(&2));
+    /// # }
+    /// ```
+    ///
+    /// [`Peekable::peek`]: core::iter::Peekable::peek
+    #[inline]
+    pub fn peek(&self) -> Option<&Item<S>> {
+        self.0.storage.peek()
+    }
+
+    /// Returns the index of the iterator that will produce the next item.
+    ///
+    /// The index is the position of the iterator among all of the iterators inserted into
+    /// the storage (including the empty ones), see [`source_tagged`](Self::source_tagged).
+    ///
+    /// Returns `None` if the [`MergeIter`] is exhausted, or if the storage doesn't provide
+    /// the pointer to its allocation (storages provided by this crate always do).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![2, 3], vec![1]]);
+    /// assert_eq!(merged.peek_source(), Some(1));
+    /// assert_eq!(merged.next(), Some(1));
+    /// assert_eq!(merged.peek_source(), Some(0));
+    /// merged.nth(1);
+    /// assert_eq!(merged.peek_source(), None);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn peek_source(&self) -> Option<usize> {
+        self.0.storage.first_source_index()
+    }
+
+    /// Returns the number of component iterators that are not exhausted yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3], vec![2], vec![]]);
+    /// assert_eq!(merged.iter_count(), 2);
+    /// merged.nth(1);
+    /// assert_eq!(merged.iter_count(), 1);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn iter_count(&self) -> usize {
+        self.0.storage.len()
+    }
+
+    /// Returns `true` if all of the component iterators are exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1]]);
+    /// assert!(!merged.is_empty());
+    /// merged.next();
+    /// assert!(merged.is_empty());
+    /// # }
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.storage.is_empty()
+    }
+
+    #[cfg(feature = "stats")]
+    /// Returns the counters of the operations performed since the construction.
+    ///
+    /// Items are counted when they are yielded one by one (by [`next`](Iterator::next) and
+    /// the methods built on it) or skipped by [`nth`](Iterator::nth), the collecting methods
+    /// like [`into_vec`](Self::into_vec) consume the [`MergeIter`] together with its counters.
+    /// Trailing sources that haven't yielded anything yet may be missing from the
+    /// [`items_per_source`](MergeStats::items_per_source).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 2, 4], vec![3]]);
+    /// assert_eq!(merged.by_ref().take(3).collect::<Vec<_>>(), [1, 2, 3]);
+    /// let stats = merged.stats();
+    /// assert_eq!(stats.items_per_source, [2, 1]);
+    /// assert!(stats.comparisons > 0);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> MergeStats {
+        let counters = &self.0.stats;
+        MergeStats {
+            comparisons: counters.comparisons,
+            sifts: counters.sifts,
+            items_per_source: counters.items.clone(),
+        }
+    }
+
+    /// Returns an iterator over the current head (peeked item) of every live source,
+    /// without consuming anything.
+    ///
+    /// Heads are yielded in an unspecified order, the smallest one is [`peek`](Self::peek).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4], vec![2, 3], vec![]]);
+    /// merged.next();
+    /// // Watermark: the largest of the heads
+    /// assert_eq!(merged.heads().max(), Some(&4));
+    /// # }
+    /// ```
+    pub fn heads(&self) -> impl ExactSizeIterator<Item = &Item<S>> + '_ {
+        self.sources().map(|peek_iter| &peek_iter.item)
+    }
+
+    /// Returns an iterator over the state of every live source: its peeked item and the rest
+    /// of its iterator, without consuming or modifying anything.
+    ///
+    /// Unlike [`as_unordered_iters`](Self::as_unordered_iters), the sources remain in the
+    /// [`MergeIter`]. Sources are yielded in an unspecified order, the first one is the source
+    /// of [`peek`](Self::peek).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4, 5], vec![2, 3]]);
+    /// merged.next();
+    /// let mut remaining: Vec<_> = merged
+    ///     .sources()
+    ///     .map(|source| (source.item, source.iter.len()))
+    ///     .collect();
+    /// remaining.sort();
+    /// assert_eq!(remaining, vec![(2, 1), (4, 1)]);
+    /// # }
+    /// ```
+    pub fn sources(&self) -> impl ExactSizeIterator<Item = &PeekIter<Iter<S>>> + '_ {
+        let heap = self.0.storage.heap();
+        (0..self.0.storage.len()).map(move |i| {
+            // SAFETY: pointers up to storage.len() are valid and outlive the borrow of self
+            unsafe { &**heap.add(i) }
+        })
+    }
+
+    /// Like [`heads`](Self::heads), but also yields the index of the source of every head,
+    /// see [`source_tagged`](Self::source_tagged).
+    ///
+    /// # Panics
+    /// Panics if the storage doesn't provide the pointer to its allocation (storages
+    /// provided by this crate always do) and the [`MergeIter`] isn't exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let merged = merge([vec![3], vec![], vec![1, 2]]);
+    /// let mut heads: Vec<_> = merged.source_heads().collect();
+    /// heads.sort_unstable();
+    /// assert_eq!(heads, [(0, &3), (2, &1)]);
+    /// # }
+    /// ```
+    pub fn source_heads(&self) -> impl ExactSizeIterator<Item = (usize, &Item<S>)> + '_ {
+        let heap = self.0.storage.heap();
+        (0..self.0.storage.len()).map(move |i| {
+            // SAFETY: pointers up to storage.len() are valid and outlive the borrow of self
+            let peek_iter = unsafe { *heap.add(i) };
+            let source = self
+                .0
+                .storage
+                .source_index(peek_iter)
+                .expect("storage doesn't support source indexes");
+            // SAFETY: see above
+            (source, unsafe { &(*peek_iter).item })
+        })
+    }
+
+    /// Replaces the next item to be yielded with `item`, returning the replaced one.
+    ///
+    /// The new item takes the place of the old one in its source, and is yielded in order with
+    /// the rest of the items (not necessarily next). To keep the output sorted, the new item
+    /// shouldn't be greater than the following items of its source. Returns `Err(item)` if
+    /// the [`MergeIter`] is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4], vec![2, 5]]);
+    /// assert_eq!(merged.replace_head(3), Ok(1));
+    /// assert_eq!(merged.into_vec(), vec![2, 3, 4, 5]);
+    /// # }
+    /// ```
+    pub fn replace_head(&mut self, item: Item<S>) -> Result<Item<S>, Item<S>> {
+        self.0.replace_first_item(item)
+    }
+
+    /// Returns the next item of the iterator if it satisfies a predicate.
+    ///
+    /// This method behaves identically to [`Peekable::next_if`] from the standard library:
+    /// it returns the next item if it satisfies the predicate, otherwise returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 1, 2, 3], vec![1, 4, 5, 6]]);
+    ///
+    /// // Consume all 1s
+    /// while let Some(item) = merged.next_if(|&x| x == 1) {
+    ///     assert_eq!(item, 1);
+    /// }
+    ///
+    /// assert_eq!(merged.next(), Some(2));
+    /// # }
+    /// ```
+    ///
+    /// [`Peekable::next_if`]: core::iter::Peekable::next_if
+    pub fn next_if(&mut self, func: impl FnOnce(&Item<S>) -> bool) -> Option<Item<S>> {
+        match self.peek() {
+            Some(item) if func(item) => {
+                // SAFETY: self.peek would've returned None if len == 0.
+                // Since the len > 0 - we always would have an item to produce
+                Some(unsafe { self.0.pop_front_item().unwrap_unchecked() })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the next item of the iterator if it is equal to a given value.
+    ///
+    /// This method behaves identically to [`Peekable::next_if_eq`] from the standard library:
+    /// it returns the next item if it is equal to the given value, otherwise returns `None`.
+    ///
+    /// This is a convenience method that is equivalent to `next_if(|item| item == expected)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 1, 2, 3], vec![1, 4, 5, 6]]);
+    ///
+    /// // Consume all 1s
+    /// while let Some(item) = merged.next_if_eq(&1) {
+    ///     assert_eq!(item, 1);
+    /// }
+    ///
+    /// assert_eq!(merged.next(), Some(2));
+    /// # }
+    /// ```
+    ///
+    /// [`Peekable::next_if_eq`]: core::iter::Peekable::next_if_eq
+    pub fn next_if_eq<T>(&mut self, expected: &T) -> Option<Item<S>>
+    where
+        T: ?Sized,
+        Item<S>: PartialEq<T>,
+    {
+        self.next_if(|item| item == expected)
+    }
+
+    /// Returns an array of the next `N` items.
+    ///
+    /// If fewer than `N` items remain, returns them in [`Err`]: all of the remaining items are
+    /// consumed and can be retrieved from the [`ArrayRemainder`], like the unstable
+    /// `Iterator::next_chunk` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4]]);
+    /// assert_eq!(merged.next_array().ok(), Some([1, 2]));
+    /// assert_eq!(merged.next_array().ok(), Some([3, 4]));
+    /// let tail = merged.next_array::<2>().unwrap_err();
+    /// assert_eq!(tail.as_slice(), [5]);
+    /// assert_eq!(merged.next(), None);
+    /// # }
+    /// ```
+    pub fn next_array<const N: usize>(
+        &mut self,
+    ) -> Result<[Item<S>; N], ArrayRemainder<Item<S>, N>> {
+        // Drops initialized items if `next` panics
+        let mut guard = ArrayRemainder::<Item<S>, N>::new();
+        while guard.end < N {
+            match self.0.pop_front_item() {
+                Some(item) => guard.array[guard.end].write(item),
+                None => return Err(guard),
+            };
+            guard.end += 1;
+        }
+        let guard = ManuallyDrop::new(guard);
+        // SAFETY: all N items are initialized, guard is not dropped. [MaybeUninit<T>; N] has
+        // the same layout as [T; N]
+        Ok(unsafe { ptr::addr_of!(guard.array).cast::<[Item<S>; N]>().read() })
+    }
+
+    /// Fills `buf` with the next items, returning the number of items written.
+    ///
+    /// The returned value is less than `buf.len()` only if the [`MergeIter`] is exhausted.
+    /// Elements of `buf` past the returned count are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4]]);
+    /// let mut buf = [0; 4];
+    /// assert_eq!(merged.next_slice(&mut buf), 4);
+    /// assert_eq!(buf, [1, 2, 3, 4]);
+    /// assert_eq!(merged.next_slice(&mut buf), 1);
+    /// assert_eq!(buf, [5, 2, 3, 4]);
+    /// # }
+    /// ```
+    pub fn next_slice(&mut self, buf: &mut [Item<S>]) -> usize {
+        for (count, slot) in buf.iter_mut().enumerate() {
+            match self.0.pop_front_item() {
+                Some(item) => *slot = item,
+                None => return count,
+            }
+        }
+        buf.len()
+    }
+
+    /// Returns an iterator over the consecutive items from the source of the next item.
+    ///
+    /// Items are yielded while the head of that source stays first according to the
+    /// comparator, i.e. until the next item would come from a different source. The
+    /// first call to [`next`](Iterator::next) always yields an item (unless the
+    /// [`MergeIter`] is exhausted).
+    ///
+    /// Items not consumed from the [`Run`] remain in the [`MergeIter`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 2, 3, 7], vec![4, 5, 6]]);
+    /// let mut run = Vec::new();
+    /// run.extend(merged.next_run());
+    /// assert_eq!(run, vec![1, 2, 3]);
+    /// assert!(merged.next_run().eq([4, 5, 6]));
+    /// assert!(merged.next_run().eq([7]));
+    /// assert!(merged.next_run().next().is_none());
+    /// # }
+    /// ```
+    #[inline]
+    pub fn next_run(&mut self) -> Run<'_, S, CMP> {
+        Run {
+            heap: &mut self.0,
+            source: None,
+        }
+    }
+
+    /// Returns an iterator yielding the merged items while they precede `bound` according to
+    /// the comparator.
+    ///
+    /// Items equivalent to `bound` (see [`Comparator::equivalent`]) don't precede it,
+    /// regardless of the tie breaker. The first item that doesn't precede `bound` stays in the
+    /// [`MergeIter`], so it can be used for the next window once the returned iterator is
+    /// dropped. If the returned iterator is dropped before it's exhausted, the remaining items
+    /// preceding `bound` stay in the [`MergeIter`] too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut events = merge([vec![1, 4, 12, 25], vec![3, 10, 11, 30]]);
+    /// assert!(events.take_until(&10).eq([1, 3, 4]));
+    /// assert!(events.take_until(&20).eq([10, 11, 12]));
+    /// assert_eq!(events.take_until(&20).next(), None);
+    /// assert_eq!(events.into_vec(), vec![25, 30]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn take_until<'a>(&'a mut self, bound: &'a Item<S>) -> TakeUntil<'a, S, CMP> {
+        TakeUntil { iter: self, bound }
+    }
+
+    /// Returns an iterator yielding the merged items that are less than `key` according to the
+    /// comparator, leaving the [`MergeIter`] positioned at the first item that isn't, same as
+    /// [`take_until`](Self::take_until).
+    ///
+    /// Repeated calls with increasing keys process the merged items window by window, without
+    /// peeking in the user code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut events = merge([vec![1, 7, 12, 15], vec![3, 9, 18]]);
+    /// let mut window_sums = Vec::new();
+    /// for window_end in [5, 10, 15, 20] {
+    ///     window_sums.push(events.drain_less_than(&window_end).sum::<i32>());
+    /// }
+    /// assert_eq!(window_sums, vec![4, 16, 12, 33]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn drain_less_than<'a>(&'a mut self, key: &'a Item<S>) -> TakeUntil<'a, S, CMP> {
+        self.take_until(key)
+    }
+
+    /// Skips `k` items and returns the next one, i.e. the `k`-th (0-based) item in merged
+    /// order. Returns `None` if there are `k` or fewer items left.
+    ///
+    /// Same as [`nth`](Iterator::nth). The component iterator with the smallest item skips its
+    /// items without updating the heap while they stay before the head of the runner-up, and
+    /// once a single component iterator remains, its items are skipped via its own
+    /// [`nth`](Iterator::nth), which is O(1) for slices and ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([0..1000, 5..10]);
+    /// assert_eq!(merged.kth(3), Some(3));
+    /// assert_eq!(merged.kth(500), Some(499));
+    /// assert_eq!(merged.kth(1000), None);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn kth(&mut self, k: usize) -> Option<Item<S>> {
+        self.0.nth(k)
+    }
+
+    /// Calls `func` with mutable references to the peeked item and the iterator of
+    /// every source, then restores the heap order.
+    ///
+    /// Sources are visited in unspecified order. `func` may modify the peeked item or
+    /// advance the iterator (e.g. skip some items by replacing the peeked item with a later
+    /// one); the [`MergeIter`] is rebuilt afterwards, so the order of items is correct even
+    /// if the peeked items changed their relative order.
+    ///
+    /// If `func` panics, the [`MergeIter`] remains safe to use, but may yield items in an
+    /// incorrect order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4, 6]]);
+    /// // skip the items smaller than 4 in every source
+    /// merged.inspect_sources_mut(|item, iter| {
+    ///     while *item < 4 {
+    ///         match iter.next() {
+    ///             Some(next) => *item = next,
+    ///             None => break,
+    ///         }
+    ///     }
+    /// });
+    /// assert_eq!(merged.into_vec(), vec![4, 5, 6]);
+    /// # }
+    /// ```
+    pub fn inspect_sources_mut(&mut self, mut func: impl FnMut(&mut Item<S>, &mut Iter<S>)) {
+        let storage = &mut self.0.storage;
+        for i in 0..storage.len() {
+            // SAFETY: pointers up to storage.len() are valid and unique, no other
+            // references to the items exist
+            let PeekIter { item, iter } = unsafe { &mut **storage.heap().add(i) };
+            func(item, iter);
+        }
+        self.0.heapify_storage();
+    }
+
+    /// Drops the component iterators whose peeked item doesn't satisfy the predicate, then
+    /// restores the heap order.
+    ///
+    /// Sources are visited in unspecified order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 8], vec![2, 3], vec![6, 7]]);
+    /// // discard the sources that have nothing below the range end
+    /// merged.retain_sources(|&head| head < 5);
+    /// assert_eq!(merged.into_vec(), vec![1, 2, 3, 8]);
+    /// # }
+    /// ```
+    pub fn retain_sources(&mut self, mut func: impl FnMut(&Item<S>) -> bool) {
+        self.0.storage.retain(|peek_iter| func(&peek_iter.item));
+        self.0.heapify_storage();
+    }
+
+    /// Restores the heap order in O(n), where n is the number of component iterators.
+    ///
+    /// The heap order is maintained by the [`MergeIter`] itself, this is only needed to resume
+    /// the merge after catching a panic of the comparator or of a component iterator, see
+    /// [`MergeIter#panics`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use std::{
+    ///     cell::Cell,
+    ///     panic::{AssertUnwindSafe, catch_unwind},
+    /// };
+    ///
+    /// use iter_merge::VecStorage;
+    ///
+    /// let calls = Cell::new(0);
+    /// let mut merged = VecStorage::from_iter([vec![1, 3, 5], vec![2, 4, 6]])
+    ///     .into_builder()
+    ///     .min_by_func(|a: &i32, b: &i32| {
+    ///         calls.set(calls.get() + 1);
+    ///         assert!(calls.get() != 2, "flaky comparator");
+    ///         a.cmp(b)
+    ///     })
+    ///     .build();
+    /// assert!(catch_unwind(AssertUnwindSafe(|| merged.next())).is_err());
+    /// merged.rebuild();
+    /// // item `1` was dropped by the panicking call
+    /// assert_eq!(merged.into_vec(), vec![2, 3, 4, 5, 6]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn rebuild(&mut self) {
+        self.0.heapify_storage();
+    }
+
+    /// Discards the items satisfying the predicate from the front of every component
+    /// iterator, then restores the heap order. Returns the number of discarded items.
+    ///
+    /// Every iterator is advanced until its next item doesn't satisfy the predicate (or until
+    /// it's exhausted), the heap is rebuilt only once. If the iterators are sorted and `func`
+    /// holds for a prefix of the merged order, this is equivalent to calling
+    /// [`next`](Iterator::next) while the peeked item satisfies `func`, but much faster.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 3, 5], vec![2, 4, 6]]);
+    /// assert_eq!(merged.advance_while(|&item| item < 4), 3);
+    /// assert_eq!(merged.into_vec(), vec![4, 5, 6]);
+    /// # }
+    /// ```
+    pub fn advance_while(&mut self, mut func: impl FnMut(&Item<S>) -> bool) -> usize {
+        self.0.advance_while(|_, item| func(item))
+    }
+
+    /// Discards all of the items that precede `bound` according to the comparator, returning
+    /// the number of discarded items.
+    ///
+    /// Fast-forwarding for merges of sorted iterators, see
+    /// [`advance_while`](Self::advance_while). Items equivalent to `bound` (see
+    /// [`Comparator::equivalent`]) are kept, regardless of the tie breaker. If the iterators
+    /// implement [`SeekableSource`], [`seek_to`](Self::seek_to) skips the items without
+    /// visiting each of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{merge, merge_by_key};
+    ///
+    /// let mut merged = merge([vec![10, 20, 30], vec![15, 25]]);
+    /// assert_eq!(merged.skip_to(&20), 2);
+    /// assert_eq!(merged.next(), Some(20));
+    /// assert_eq!(merged.skip_to(&100), 2);
+    /// assert_eq!(merged.next(), None);
+    ///
+    /// // The bound is compared by the key of the merge
+    /// let mut events = merge_by_key([vec![(1, "a"), (5, "b")], vec![(3, "c")]], |e| e.0);
+    /// assert_eq!(events.skip_to(&(3, "")), 1);
+    /// assert_eq!(events.next(), Some((3, "c")));
+    /// # }
+    /// ```
+    pub fn skip_to(&mut self, bound: &Item<S>) -> usize {
+        self.0
+            .advance_while(|comparator, item| precedes(comparator, item, bound))
+    }
+
+    /// Discards all of the items that precede `bound` according to the comparator, using
+    /// [`SeekableSource::seek`] to skip them.
+    ///
+    /// Like [`skip_to`](Self::skip_to), but a seekable source (e.g. a slice iterator) skips the
+    /// items at once instead of one by one, so the number of discarded items is not known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    ///
+    /// use iter_merge::ArrayStorage;
+    ///
+    /// let (a, b) = ([1, 3, 5, 7, 9], [2, 4, 6, 8]);
+    /// let storage = pin!(ArrayStorage::from_arr([a.iter(), b.iter()]));
+    /// let mut merged = storage.build();
+    /// merged.seek_to(&&6);
+    /// assert!(merged.eq([&6, &7, &8, &9]));
+    /// ```
+    pub fn seek_to(&mut self, bound: &Item<S>)
+    where
+        Iter<S>: SeekableSource,
+    {
+        let comparator = &self.0.comparator;
+        self.0.storage.retain(|peek_iter| {
+            if !precedes(comparator, &peek_iter.item, bound) {
+                return true;
+            }
+            match peek_iter
+                .iter
+                .seek(|item| precedes(comparator, item, bound))
+            {
+                Some(item) => {
+                    peek_iter.item = item;
+                    true
+                }
+                None => false,
+            }
+        });
+        self.0.heapify_storage();
+    }
+
+    /// Removes the component iterator with the given source index from the [`MergeIter`],
+    /// returning its `(peeked_item, iter)`.
+    ///
+    /// The index is the position of the iterator among all of the iterators inserted into
+    /// the storage (including the empty ones), see [`source_tagged`](Self::source_tagged).
+    ///
+    /// Returns `None` if that iterator is already exhausted (or was never inserted), or if the
+    /// storage doesn't provide the pointer to its allocation (storages provided by this crate
+    /// always do).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4], vec![2, 5], vec![3, 6]]);
+    /// assert_eq!(merged.next(), Some(1));
+    /// let (head, rest) = merged.remove_source(1).unwrap();
+    /// assert_eq!((head, rest.collect::<Vec<_>>()), (2, vec![5]));
+    /// assert!(merged.remove_source(1).is_none());
+    /// assert_eq!(merged.into_vec(), vec![3, 4, 6]);
+    /// # }
+    /// ```
+    pub fn remove_source(&mut self, index: usize) -> Option<(Item<S>, Iter<S>)> {
+        let storage = &mut self.0.storage;
+        let heap = storage.heap();
+        let position = (0..storage.len()).find(|&i| {
+            // SAFETY: pointers up to storage.len() are valid
+            storage.source_index(unsafe { *heap.add(i) }) == Some(index)
+        })?;
+        // SAFETY: position and len - 1 are valid heap positions, swapping pointers within the
+        // heap preserves the storage invariants
+        unsafe {
+            ptr::swap(heap.add(position), heap.add(storage.len() - 1));
+        }
+        let removed = storage.pop_last_item();
+        self.0.heapify_storage();
+        removed
+    }
+
+    /// Splits the [`MergeIter`] into its storage and its comparator.
+    ///
+    /// The storage holds the remaining `(peeked_item, iter)` pairs, which can be inspected
+    /// or taken out via [`StorageOps`](crate::internal::StorageOps), and the comparator can
+    /// be reused, e.g. with [`from_parts`](Self::from_parts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{MergeIter, internal::StorageOps, merge};
+    ///
+    /// let mut merged = merge([vec![1, 3], vec![2, 4]]);
+    /// merged.next();
+    /// let (mut storage, comparator) = merged.into_parts();
+    /// let mut heads = Vec::new();
+    /// storage.map_items(|peek_iter| heads.push(peek_iter.item));
+    /// heads.sort();
+    /// assert_eq!(heads, vec![2, 3]);
+    /// let merged = MergeIter::from_parts(storage, comparator);
+    /// assert_eq!(merged.into_vec(), vec![2, 3, 4]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn into_parts(self) -> (S, CMP) {
+        let Heap {
+            comparator,
+            mut storage,
+            ..
+        } = self.0;
+        // The comparator may be replaced before the storage is merged again
+        storage.forget_keys();
+        (storage, comparator)
+    }
+
+    /// Constructs the [`MergeIter`] from a storage and a comparator, restoring the heap order.
+    ///
+    /// Inverse of [`into_parts`](Self::into_parts); the storage may have been modified in
+    /// between.
+    #[inline]
+    pub fn from_parts(storage: S, comparator: CMP) -> Self {
+        Self(Heap::new(comparator, storage))
+    }
+
+    /// Replaces the comparator, rebuilding the heap in O(n), where n is the number of
+    /// component iterators.
+    ///
+    /// All of the peeked items and iterators are kept. `comparator` replaces the whole
+    /// comparator of the [`MergeIter`], including the tie breaker set by the
+    /// [`Builder`]; use [`Chain`](crate::comparators::Chain) to
+    /// add one. The component iterators should yield items in the order of the new comparator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use core::cmp::Ordering;
+    ///
+    /// use iter_merge::{comparators::Comparator, merge};
+    ///
+    /// // sort key chosen at runtime
+    /// struct ByField(usize);
+    /// impl Comparator<[i32; 2]> for ByField {
+    ///     fn compare(&self, a: &[i32; 2], b: &[i32; 2]) -> Ordering {
+    ///         a[self.0].cmp(&b[self.0])
+    ///     }
+    /// }
+    ///
+    /// // items are sorted by both fields
+    /// let merged = merge([vec![[1, 4], [3, 6]], vec![[2, 2], [5, 5]]]);
+    /// let merged = merged.with_comparator(ByField(1));
+    /// assert_eq!(merged.into_vec(), vec![[2, 2], [1, 4], [5, 5], [3, 6]]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn with_comparator<C>(self, comparator: C) -> MergeIter<S, C>
+    where
+        C: Comparator<Item<S>>,
+    {
+        let (storage, _) = self.into_parts();
+        MergeIter::from_parts(storage, comparator)
+    }
+
+    /// Reverses the order of the merge, rebuilding the heap in O(n), where n is the number of
+    /// component iterators.
+    ///
+    /// The comparator (including the tie breaker) is wrapped into [`MaxFirst`], so the largest
+    /// item is yielded first. The component iterators aren't changed, so they should already
+    /// yield items in the new order (e.g. cursors that switched their direction), and the
+    /// currently peeked items are yielded first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![5, 3, 1], vec![6, 4, 2]]);
+    /// // peeked items 5 and 6 are in the wrong order for the min-first merge
+    /// assert_eq!(merged.next(), Some(5));
+    /// let reversed = merged.into_reversed();
+    /// assert_eq!(reversed.into_vec(), vec![6, 4, 3, 2, 1]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn into_reversed(self) -> MergeIter<S, MaxFirst<CMP>> {
+        let (storage, comparator) = self.into_parts();
+        MergeIter::from_parts(storage, MaxFirst(comparator))
+    }
+
+    /// Wraps the comparator (including the tie breaker) into [`Directed`] with the
+    /// `direction`, rebuilding the heap in O(n), where n is the number of component iterators.
+    ///
+    /// Unlike [`into_reversed`](Self::into_reversed), the direction of the returned merge can
+    /// be toggled by [`set_direction`](Self::set_direction) without changing its type, e.g. to
+    /// store it in a structure.
+    #[inline]
+    pub fn into_directed(self, direction: Direction) -> MergeIter<S, Directed<CMP>> {
+        let (storage, comparator) = self.into_parts();
+        MergeIter::from_parts(storage, Directed::new(comparator, direction))
+    }
+
+    /// Returns an iterator, yielding unordered tuples of `(peeked_item, iter)`
+    /// from the [`MergeIter`]
+    ///
+    /// No exact order is guaranteed, but you can expect the later iterators from [`MergeIter`]
+    /// to be yielded first, and the frontmost iterator (that would've been polled by
+    /// [`MergeIter::next()`]) to be yielded last.
+    ///
+    /// Original [`MergeIter`] remains valid after use of this iterator, items yielded by this
+    /// iterator are excluded.
+    #[inline]
+    pub fn as_unordered_iters(&mut self) -> UnorderedItersIter<'_, S> {
+        UnorderedItersIter(&mut self.0.storage)
+    }
+
+    /// Returns an ordered iterator, yielding tuples of `(peeked_item, iter)` from the [`MergeIter`]
+    ///
+    /// Items are ordered according to value of `peeked_item`, as compared by the [`MergeIter`]'s
+    /// comparator
+    ///
+    /// Original [`MergeIter`] remains valid after use of this iterator, items yielded by this
+    /// iterator are excluded.
+    #[inline]
+    pub fn as_iters(&mut self) -> ItersIter<'_, S, CMP> {
+        ItersIter(&mut self.0)
+    }
+
+    /// Consumes the [`MergeIter`], returning an iterator over the remaining sources.
+    ///
+    /// Every source is yielded as `once(peeked_item).chain(iter)`, so no items are lost.
+    /// Sources are ordered like in [`as_iters`](Self::as_iters).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4, 5], vec![2, 3]]);
+    /// merged.next();
+    /// let sources: Vec<Vec<_>> = merged.into_sources().map(Iterator::collect).collect();
+    /// assert_eq!(sources, vec![vec![2, 3], vec![4, 5]]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn into_sources(self) -> IntoSources<S, CMP> {
+        IntoSources(self)
+    }
+}
+
+impl<S, CMP, IT, M> MergeIter<S, CMP>
+where
+    S: Storage<IT = WithMeta<IT, M>>,
+    CMP: Comparator<IT::Item>,
+    IT: Iterator,
+{
+    /// Returns a reference to the metadata of the source of the next item, see
+    /// [`WithMeta`].
+    #[inline]
+    pub fn peek_meta(&self) -> Option<&M> {
+        if self.0.storage.is_empty() {
+            return None;
+        }
+        // SAFETY: len >= 1
+        Some(unsafe { &(**self.0.storage.first()).iter.meta })
+    }
+
+    /// Tags every item with the metadata of its source, yielding `(meta, item)` pairs in
+    /// merged order. The metadata is cloned for every item, so it should be cheap to clone
+    /// (e.g. an id or an `Rc`).
+    ///
+    /// See [`WithMeta`] for an example.
+    #[inline]
+    pub fn meta_tagged(self) -> MetaTagged<S, CMP> {
+        MetaTagged(self)
+    }
+}
+
+impl<S, CMP> MergeIter<S, CMP>
+where
+    Self: Clone,
+{
+    /// Saves the current progress of the [`MergeIter`], so it could be restored by
+    /// [`rewind`](Self::rewind).
+    ///
+    /// Only the remaining (not exhausted) component iterators are cloned, so the checkpoint is
+    /// cheap if they are cheap to clone (e.g. slice iterators or ranges).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::merge;
+    ///
+    /// let (a, b) = ([1, 3, 5], [2, 4, 6]);
+    /// let mut tokens = merge([a.iter(), b.iter()]);
+    /// tokens.next();
+    /// let checkpoint = tokens.mark();
+    /// assert!(tokens.by_ref().take(3).eq([&2, &3, &4]));
+    /// tokens.rewind(&checkpoint);
+    /// assert_eq!(tokens.next(), Some(&2));
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn mark(&self) -> Checkpoint<S, CMP> {
+        Checkpoint(self.clone())
+    }
+
+    /// Restores the progress saved by [`mark`](Self::mark). The checkpoint remains valid, so
+    /// it's possible to rewind to it multiple times.
+    #[inline]
+    pub fn rewind(&mut self, checkpoint: &Checkpoint<S, CMP>) {
+        self.clone_from(&checkpoint.0);
+    }
+}
+
+impl<S, CMP> MergeIter<S, Directed<CMP>>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    /// Returns the current direction of the merge, see [`into_directed`](Self::into_directed)
+    #[inline]
+    pub const fn direction(&self) -> Direction {
+        self.0.comparator.direction()
+    }
+
+    /// Changes the direction of the merge, rebuilding the heap in O(n), where n is the number
+    /// of component iterators. Does nothing if the direction is the same.
+    ///
+    /// Like with [`into_reversed`](Self::into_reversed), the component iterators aren't
+    /// changed, so they should already yield items in the new order (e.g. cursors that switched
+    /// their direction), and the currently peeked items are yielded first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{comparators::Direction, merge};
+    ///
+    /// let mut merged = merge([vec![1, 4], vec![2, 3]]).into_directed(Direction::Ascending);
+    /// assert_eq!(merged.next(), Some(1));
+    /// merged.set_direction(Direction::Descending);
+    /// assert_eq!(merged.direction(), Direction::Descending);
+    /// assert_eq!(merged.next(), Some(4));
+    /// merged.set_direction(Direction::Ascending);
+    /// assert_eq!(merged.into_vec(), vec![2, 3]);
+    /// # }
+    /// ```
+    pub fn set_direction(&mut self, direction: Direction) {
+        if self.0.comparator.direction() == direction {
+            return;
+        }
+        self.0.comparator.set_direction(direction);
+        self.0.heapify_storage();
+    }
+}
+
+impl<CMP, S> Iterator for MergeIter<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front_item()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.kth(n)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // this accounts for peeked items
+        let mut min = self.0.storage.len();
+        let mut max = min;
+        let mut no_max = false;
+        self.0.storage.map_items(|it| {
+            let (it_min, it_max) = it.iter.size_hint();
+            min = min.saturating_add(it_min);
+            let overflow;
+            // if we're here - storage.len()>0, and so is the initial max value
+            // If it_max is None it will become usize::MAX, and adding non-zero value to
+            // usize::MAX will overflow, correctly setting the no_max
+            (max, overflow) = max.overflowing_add(it_max.unwrap_or(usize::MAX));
+            no_max |= overflow;
+        });
+        if self.0.comparator.deduplicates() {
+            // every remaining item may be a duplicate of the first one
+            min = min.min(1);
+        }
+        // If any inner iterator has an unbounded upper bound, or the sum of
+        // upper bounds overflows a usize - overall upper bound is None.
+        (min, (!no_max).then_some(max))
+    }
+
+    fn count(mut self) -> usize
+    where
+        Self: Sized,
+    {
+        let mut count = 0;
+        if self.0.comparator.deduplicates() {
+            while self.0.pop_front_item().is_some() {
+                // panic in debug and wrapping in release is the expected behaiour
+                #[allow(clippy::arithmetic_side_effects)]
+                {
+                    count += 1;
+                }
+            }
+            return count;
+        }
+        while let Some((_, iter)) = self.0.storage.pop_last_item() {
+            // panic in debug and wrapping in release is the expected behaiour
+            #[allow(clippy::arithmetic_side_effects)]
+            {
                 count += 1 + iter.count();
             }
         }
-        count
+        count
+    }
+
+    /// Returns the last item of the merge without yielding every other item.
+    ///
+    /// Takes the [`last`](Iterator::last) item of every remaining iterator and returns
+    /// the greatest of them according to the comparator (respecting the tie breaker).
+    ///
+    /// This is equal to the last item yielded by [`next`](Iterator::next) as long as the
+    /// merged iterators are sorted according to the comparator. Otherwise the result may
+    /// differ from the last item of the full merge.
+    fn last(self) -> Option<Self::Item>
+    where
+        Self: Sized,
+    {
+        self.0.last()
+    }
+}
+
+// The iterator is definitely fused, since we're popping inner iterators after
+// the first `None` is returned
+impl<CMP, S> FusedIterator for MergeIter<S, CMP>
+where
+    CMP: Comparator<Item<S>>,
+    S: Storage,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{iter::repeat, pin::pin};
+
+    use crate::ArrayStorage;
+
+    #[test]
+    fn peek() {
+        let s = ArrayStorage::from_arr([[3, 2], [2, 6], [3, 4]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.peek(), Some(&2));
+        assert_eq!(m.next(), Some(2));
+        assert_eq!(m.peek(), Some(&3));
+    }
+
+    #[test]
+    fn peek_source() {
+        let s = ArrayStorage::from_arr([[3, 5], [2, 6], [3, 4]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.peek_source(), Some(1));
+        m.next();
+        assert_eq!(m.peek_source(), Some(0));
+        m.next();
+        assert_eq!(m.peek_source(), Some(2));
+        assert_eq!(m.next(), Some(3));
+        assert_eq!(m.peek_source(), Some(2));
+        m.nth(3);
+        assert_eq!(m.peek_source(), None);
+    }
+
+    #[test]
+    fn next_if() {
+        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next_if(|&el| el <= 2), Some(1));
+        assert_eq!(m.next_if(|&el| el <= 2), Some(2));
+        m.nth(10);
+        assert_eq!(m.next_if(|_el| true), None);
+    }
+
+    #[test]
+    fn next_if_eq() {
+        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next_if_eq(&1), Some(1));
+        assert_eq!(m.next_if_eq(&200), None);
+        m.nth(3);
+        assert_eq!(m.next_if_eq(&6), Some(6));
+        assert_eq!(m.next_if_eq(&7), None);
+    }
+
+    #[test]
+    fn kth() {
+        let data = [[1, 4, 7, 8, 9], [2, 3, 5, 6, 10]];
+        for k in 0..12 {
+            let s1 = pin!(ArrayStorage::from_arr(data));
+            let mut expected = s1.build();
+            for _ in 0..k {
+                expected.next();
+            }
+            let s2 = pin!(ArrayStorage::from_arr(data));
+            let mut m = s2.build();
+            assert_eq!(m.kth(k), expected.next(), "{k}");
+            assert!(m.eq(expected));
+        }
+        // Runs of items from the same source
+        let data = [[1, 2, 3, 10, 11], [4, 5, 12, 13, 14], [6, 7, 8, 9, 15]];
+        for k in 0..16 {
+            let s = pin!(ArrayStorage::from_arr(data));
+            let mut m = s.build();
+            assert_eq!(m.kth(k), (k < 15).then_some(k as i32 + 1), "{k}");
+            assert!(m.eq(k as i32 + 2..=15), "{k}");
+        }
+        let s = ArrayStorage::from_arr([0..3, 10..100]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.kth(2), Some(2));
+        assert_eq!(m.kth(10), Some(20));
+        assert_eq!(m.kth(0), Some(21));
+        assert_eq!(m.iter_count(), 1);
+        assert_eq!(m.kth(77), Some(99));
+        assert!(m.is_empty());
+        let s = ArrayStorage::from_arr([0..3, 10..100]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.kth(100), None);
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn count() {
+        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+        let s = pin!(s);
+        let m = s.build();
+        assert_eq!(m.count(), 6);
+    }
+
+    #[test]
+    fn last() {
+        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+        let s = pin!(s);
+        assert_eq!(s.build().last(), Some(6));
+
+        let s = ArrayStorage::<3, core::array::IntoIter<i32, 2>>::new();
+        let s = pin!(s);
+        assert_eq!(s.build().last(), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn last_tie_breaker() {
+        use crate::{VecStorage, comparators::tie_breaker};
+        let sources = [[(1, 'a'), (3, 'a')], [(2, 'b'), (3, 'b')], [(0, 'c'), (3, 'c')]];
+        let merge = || VecStorage::from_iter(sources).into_builder().min_by_key(|it| it.0);
+        assert_eq!(merge().build().last(), Some((3, 'c')));
+        assert_eq!(
+            merge()
+                .tie_breaker(tie_breaker::ReverseInsertionOrder)
+                .build()
+                .last(),
+            Some((3, 'a'))
+        );
+        for consumed in 0..6 {
+            let mut it = merge().build();
+            it.nth(consumed);
+            assert_eq!(it.clone().last(), it.into_vec().pop());
+        }
+    }
+
+    #[test]
+    fn next_array() {
+        let s = ArrayStorage::from_arr([[1, 4, 5], [2, 3, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next_array().ok(), Some([1, 2, 3, 4]));
+        assert_eq!(m.next_array::<0>().ok(), Some([]));
+        let mut tail = m.next_array::<3>().unwrap_err();
+        assert_eq!(tail.as_slice(), [5, 6]);
+        assert_eq!(tail.len(), 2);
+        assert_eq!(m.next(), None);
+        assert_eq!(tail.next(), Some(5));
+        assert!(tail.eq([6]));
+        assert_eq!(m.next_array::<2>().unwrap_err().len(), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn next_array_drops_tail() {
+        use alloc::{rc::Rc, vec};
+
+        use crate::merge;
+        let item = Rc::new(());
+        let mut m = merge([vec![item.clone(), item.clone()], vec![item.clone()]]);
+        let mut tail = m.next_array::<4>().unwrap_err();
+        assert_eq!(Rc::strong_count(&item), 4);
+        tail.next();
+        assert_eq!(Rc::strong_count(&item), 3);
+        drop(tail);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn next_slice() {
+        let s = ArrayStorage::from_arr([[1, 4, 5], [2, 3, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        let mut buf = [0; 4];
+        assert_eq!(m.next_slice(&mut buf), 4);
+        assert_eq!(buf, [1, 2, 3, 4]);
+        assert_eq!(m.next_slice(&mut buf), 2);
+        assert_eq!(buf, [5, 6, 3, 4]);
+        assert_eq!(m.next_slice(&mut buf), 0);
+    }
+
+    #[test]
+    fn inspect_sources_mut() {
+        let s = ArrayStorage::from_arr([[1, 15], [2, 16], [3, 17]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(1));
+        // reverse the order of the heads
+        m.inspect_sources_mut(|item, _iter| *item = 10 - *item);
+        assert!(m.eq([-5, 7, 8, 16, 17]));
+    }
+
+    #[test]
+    fn collect_into() {
+        struct Collector([i32; 8], usize);
+        impl Extend<i32> for Collector {
+            fn extend<T: IntoIterator<Item = i32>>(&mut self, iter: T) {
+                for item in iter {
+                    self.0[self.1] = item;
+                    self.1 += 1;
+                }
+            }
+        }
+        let mut res = Collector([0; 8], 1);
+        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        m.next();
+        m.collect_into(&mut res);
+        let s = ArrayStorage::from_arr([[8], [7]]);
+        let s = pin!(s);
+        s.build().collect_into(&mut res);
+        assert_eq!(res.0, [0, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn chain_after() {
+        let first = ArrayStorage::from_arr([[3, 4], [1, 2]]);
+        let first = pin!(first);
+        let second = ArrayStorage::from_arr([[5, 8], [6, 7], [9, 10]]);
+        let second = pin!(second);
+        let mut chain = first.build().chain_after(second.build());
+        assert_eq!(chain.size_hint(), (10, Some(10)));
+        assert_eq!(chain.next_if_eq(&1), Some(1));
+        assert_eq!(chain.next_if(|&item| item > 2), None);
+        assert!(chain.by_ref().take(3).eq([2, 3, 4]));
+        // the first iterator is exhausted, peeking falls through to the second one
+        assert_eq!(chain.peek(), Some(&5));
+        assert_eq!(chain.next_if_eq(&5), Some(5));
+        assert!(chain.eq([6, 7, 8, 9, 10]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn chain_after_into_vec() {
+        use alloc::{vec, vec::Vec};
+
+        use crate::merge;
+        let chain = merge([vec![4], vec![3]]).chain_after(merge([Vec::new(), vec![1, 2]]));
+        assert_eq!(chain.clone().count(), 4);
+        assert_eq!(chain.clone().last(), Some(2));
+        let mut res = vec![0];
+        chain.clone().collect_into(&mut res);
+        assert_eq!(res, [0, 3, 4, 1, 2]);
+        assert_eq!(chain.into_vec(), [3, 4, 1, 2]);
+
+        let chain = merge([vec![1]]).chain_after(merge([Vec::<i32>::new()]));
+        assert_eq!(chain.clone().last(), Some(1));
+        assert_eq!(chain.into_vec(), [1]);
+    }
+
+    #[cfg(feature = "alloc-audit")]
+    #[test]
+    fn into_vec_audited() {
+        use alloc::vec;
+
+        use super::AllocStats;
+        use crate::merge;
+        let (v, stats) = merge([vec![1, 3], vec![2]]).into_vec_audited();
+        assert_eq!(v, [1, 2, 3]);
+        assert_eq!(stats, AllocStats { allocations: 1, reallocations: 0 });
+
+        // Sources that report no size hint force the vector to grow
+        let unhinted = |range: core::ops::RangeInclusive<i32>| range.filter(|_| true);
+        let (v, stats) = merge([unhinted(1..=4), unhinted(5..=8)]).into_vec_audited();
+        assert_eq!(v, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(stats.allocations, 1);
+        assert!(stats.reallocations > 0);
+
+        let (v, stats) = merge([vec![0; 0]]).into_vec_audited();
+        assert!(v.is_empty());
+        assert_eq!(stats, AllocStats::default());
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        use alloc::vec;
+        use core::{cell::Cell, cmp::Ordering};
+
+        use crate::{VecStorage, comparators::Comparator};
+
+        struct Counting<'a>(&'a Cell<u64>);
+        impl Comparator<i32> for Counting<'_> {
+            fn compare(&self, a: &i32, b: &i32) -> Ordering {
+                self.0.set(self.0.get() + 1);
+                a.cmp(b)
+            }
+        }
+
+        let calls = Cell::new(0);
+        let mut m = VecStorage::from_iter([vec![1, 5, 6], vec![], vec![2, 3], vec![4, 7]])
+            .build_with(Counting(&calls));
+        assert_eq!(m.stats().items_per_source, [0; 0]);
+        assert!(m.by_ref().take(5).eq([1, 2, 3, 4, 5]));
+        let stats = m.stats();
+        assert_eq!(stats.comparisons, calls.get());
+        assert!(stats.sifts > 0);
+        assert_eq!(stats.items_per_source, [2, 0, 2, 1]);
+        while m.next().is_some() {}
+        assert_eq!(m.stats().items_per_source, [3, 0, 2, 2]);
+
+        // Skipping a run of items from the same source doesn't touch the heap
+        let run = (0..50).chain([90]).collect();
+        let mut m = VecStorage::from_iter([run, vec![60, 61], vec![70]]).build();
+        let sifts = m.stats().sifts;
+        assert_eq!(m.kth(48), Some(48));
+        assert_eq!(m.stats().sifts, sifts);
+        assert_eq!(m.stats().items_per_source, [49]);
+
+        // Items skipped by nth are counted in bulk, including the ones skipped by the last
+        // source
+        let mut m = VecStorage::from_iter([vec![1, 2, 3, 9], vec![4, 5], vec![6, 7, 8]]).build();
+        assert_eq!(m.nth(3), Some(4));
+        assert_eq!(m.stats().items_per_source, [3, 1]);
+        assert!(m.by_ref().skip(2).take(1).eq([7]));
+        assert_eq!(m.stats().items_per_source, [3, 2, 2]);
+        assert_eq!(m.nth(1), Some(9));
+        assert_eq!(m.stats().items_per_source, [4, 2, 3]);
+        let unsized_hint = (0..10).filter(|_| true);
+        let mut m = VecStorage::from_iter([unsized_hint.clone(), unsized_hint]).build();
+        assert_eq!(m.nth(25), None);
+        assert_eq!(m.stats().items_per_source, [10, 10]);
+        let mut m = VecStorage::from_iter([vec![1, 2], vec![3, 4, 5]]).build();
+        assert_eq!(m.nth(9), None);
+        assert_eq!(m.stats().items_per_source, [2, 3]);
+
+        // Adapters comparing the items count their comparisons too
+        let sources = || VecStorage::from_iter([vec![1, 1, 2], vec![1, 3]]);
+        calls.set(0);
+        let mut dedup = sources().build_with(Counting(&calls)).dedup();
+        assert!(dedup.by_ref().eq([1, 2, 3]));
+        assert_eq!(dedup.0.stats().comparisons, calls.get());
+        calls.set(0);
+        let mut checked = sources().build_with(Counting(&calls)).checked();
+        assert_eq!(checked.by_ref().count(), 5);
+        assert_eq!(checked.iter.stats().comparisons, calls.get());
+        calls.set(0);
+        let mut coalesced = sources().build_with(Counting(&calls)).coalesce_equal(|a, _| a);
+        assert!(coalesced.by_ref().eq([1, 2, 3]));
+        assert_eq!(coalesced.iter.stats().comparisons, calls.get());
     }
-}
 
-// The iterator is definitely fused, since we're popping inner iterators after
-// the first `None` is returned
-impl<CMP, S> FusedIterator for MergeIter<S, CMP>
-where
-    CMP: Comparator<Item<S>>,
-    S: Storage,
-{
-}
+    #[test]
+    fn retain_sources() {
+        let s = ArrayStorage::from_arr([[5, 6], [1, 9], [3, 4], [7, 8]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(1));
+        m.retain_sources(|&head| head < 7);
+        assert_eq!(m.iter_count(), 2);
+        assert!(m.by_ref().eq([3, 4, 5, 6]));
+        m.retain_sources(|_| unreachable!());
+    }
 
-#[cfg(test)]
-mod tests {
-    use core::{iter::repeat, pin::pin};
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_dedup_vec() {
+        use alloc::vec;
 
-    use crate::ArrayStorage;
+        use crate::{merge, merge_by_key};
+        let m = || merge([vec![1, 1, 3, 5, 5], vec![1, 2, 5, 6], vec![0, 5, 6, 6]]);
+        let mut expected = m().into_vec();
+        expected.dedup();
+        assert_eq!(m().into_dedup_vec(), expected);
+        assert_eq!(merge([vec![7, 7, 7]]).into_dedup_vec(), [7]);
+        assert!(merge([vec![0; 0]]).into_dedup_vec().is_empty());
+        // Equivalence is decided by the comparator, the first item of every run is kept
+        let by_key = merge_by_key([vec![(1, 'a'), (2, 'b')], vec![(1, 'c'), (3, 'd')]], |i| i.0);
+        let by_key = by_key.into_dedup_vec();
+        assert_eq!(by_key, [(1, 'a'), (2, 'b'), (3, 'd')]);
+        assert!(by_key.capacity() >= 4);
+    }
 
     #[test]
-    fn peek() {
-        let s = ArrayStorage::from_arr([[3, 2], [2, 6], [3, 4]]);
+    fn remove_source() {
+        let s = ArrayStorage::from_arr([[3, 6], [1, 5], [2, 4]]);
         let s = pin!(s);
         let mut m = s.build();
-        assert_eq!(m.peek(), Some(&2));
+        let (item, iter) = m.remove_source(1).unwrap();
+        assert_eq!(item, 1);
+        assert!(iter.eq([5]));
+        assert!(m.remove_source(1).is_none());
+        assert!(m.remove_source(3).is_none());
         assert_eq!(m.next(), Some(2));
+        let (item, iter) = m.remove_source(0).unwrap();
+        assert_eq!(item, 3);
+        assert!(iter.eq([6]));
+        assert!(m.eq([4]));
+    }
+
+    #[test]
+    fn replace_head() {
+        let s = ArrayStorage::from_arr([[1, 4, 7], [2, 5, 8], [3, 6, 9]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.replace_head(0), Ok(1));
+        assert_eq!(m.replace_head(4), Ok(0));
+        assert_eq!(m.peek(), Some(&2));
+        assert_eq!(m.replace_head(2), Ok(2));
+        assert_eq!(m.peek_source(), Some(1));
+        assert!(m.by_ref().eq([2, 3, 4, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(m.replace_head(1), Err(1));
+    }
+
+    #[test]
+    fn into_parts() {
+        use crate::{MergeIter, internal::StorageOps};
+
+        let s = ArrayStorage::from_arr([[1, 4], [2, 5], [3, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(1));
+        let (mut storage, cmp) = m.into_parts();
+        let mut heads = [0; 3];
+        let mut i = 0;
+        storage.map_items(|peek_iter| {
+            heads[i] = peek_iter.item;
+            i += 1;
+        });
+        heads.sort_unstable();
+        assert_eq!(heads, [2, 3, 4]);
+        storage.retain(|peek_iter| peek_iter.item != 3);
+        let m = MergeIter::from_parts(storage, cmp);
+        assert!(m.eq([2, 4, 5]));
+    }
+
+    #[test]
+    fn with_comparator() {
+        use crate::comparators::{ByOrd, Chain, tie_breaker};
+        let s = ArrayStorage::from_arr([[(1, 'a'), (2, 'd')], [(1, 'b'), (3, 'c')]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some((1, 'a')));
+        // the peeked items are kept
+        let m = m.with_comparator(Chain::new::<(i32, char)>(ByOrd, tie_breaker::InsertionOrder));
+        assert!(m.eq([(1, 'b'), (2, 'd'), (3, 'c')]));
+    }
+
+    #[test]
+    fn into_reversed() {
+        // sources are ordered by the key, the second element tags the source
+        let s = ArrayStorage::from_arr([[(3, 0), (1, 0)], [(3, 1), (2, 1)], [(4, 2), (0, 2)]]);
+        let s = pin!(s);
+        let m = s.into_builder().min_by_key(|item: &(i32, i32)| item.0).build();
+        let mut m = m.into_reversed();
+        // equal items are reversed together with the tie breaker
+        assert!(m.by_ref().take(3).eq([(4, 2), (3, 1), (3, 0)]));
+        let m = m.into_reversed().into_reversed();
+        assert!(m.eq([(2, 1), (1, 0), (0, 2)]));
+    }
+
+    #[test]
+    fn set_direction() {
+        use crate::comparators::Direction;
+
+        // sources are ordered by the key, the second element tags the source
+        let s = ArrayStorage::from_arr([[(3, 0), (1, 0)], [(3, 1), (2, 1)], [(4, 2), (0, 2)]]);
+        let s = pin!(s);
+        let m = s.into_builder().min_by_key(|item: &(i32, i32)| item.0).build();
+        let mut m = m.into_directed(Direction::Descending);
+        assert!(m.by_ref().take(3).eq([(4, 2), (3, 1), (3, 0)]));
+        m.set_direction(Direction::Ascending);
+        assert_eq!(m.next(), Some((0, 2)));
+        m.set_direction(Direction::Descending);
+        m.set_direction(Direction::Descending);
+        assert_eq!(m.direction(), Direction::Descending);
+        assert!(m.eq([(2, 1), (1, 0)]));
+    }
+
+    #[test]
+    fn sources() {
+        let s = ArrayStorage::from_arr([[1, 4], [2, 5], [3, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.sources().len(), 3);
+        m.nth(2);
+        let first = m.sources().next().unwrap();
+        assert_eq!((first.item, first.iter.len()), (4, 0));
+        let mut total = 0;
+        for source in m.sources() {
+            total += source.item + source.iter.clone().sum::<i32>();
+        }
+        assert_eq!(total, 15);
+        assert!(m.eq([4, 5, 6]));
+    }
+
+    #[test]
+    fn skip_to() {
+        let s = ArrayStorage::from_arr([[1, 4, 7], [2, 5, 8], [3, 6, 9]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(1));
+        assert_eq!(m.skip_to(&5), 3);
+        assert_eq!(m.peek(), Some(&5));
+        assert_eq!(m.iter_count(), 3);
+        assert_eq!(m.skip_to(&5), 0);
+        assert_eq!(m.advance_while(|&item| item != 9), 4);
+        assert_eq!(m.iter_count(), 1);
+        assert!(m.eq([9]));
+
+        // Bound is compared with the comparator of the merge
+        let s = ArrayStorage::from_arr([[(9, 'a'), (5, 'a')], [(7, 'b'), (5, 'b')]]);
+        let s = pin!(s);
+        let mut m = s.into_builder().max_by_key(|item| item.0).build();
+        assert_eq!(m.skip_to(&(5, 'z')), 2);
+        assert!(m.eq([(5, 'a'), (5, 'b')]));
+    }
+
+    #[test]
+    fn seek_to() {
+        let (a, b, c) = ([1, 4, 7], [2, 5, 8], [3, 6, 9]);
+        let s = ArrayStorage::from_arr([a.iter(), b.iter(), c.iter()]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(&1));
+        m.seek_to(&&5);
+        assert_eq!(m.iter_count(), 3);
+        assert_eq!(m.next(), Some(&5));
+        m.seek_to(&&8);
+        assert_eq!(m.iter_count(), 2);
+        m.seek_to(&&9);
+        assert!(m.eq([&9]));
+
+        // Bound is compared with the comparator of the merge
+        let (a, b) = ([(9, 'a'), (5, 'a'), (1, 'a')], [(7, 'b'), (5, 'b')]);
+        let s = pin!(ArrayStorage::from_arr([a.iter(), b.iter()]));
+        let mut m = s.into_builder().max_by_key(|item| item.0).build();
+        m.seek_to(&&(5, 'z'));
+        assert!(m.eq([&(5, 'a'), &(5, 'b'), &(1, 'a')]));
+    }
+
+    #[test]
+    fn iter_count() {
+        let s = ArrayStorage::from_arr([[1, 4], [2, 3], [5, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.iter_count(), 3);
+        assert_eq!(m.nth(3), Some(4));
+        assert_eq!(m.iter_count(), 1);
+        assert!(!m.is_empty());
+        m.by_ref().for_each(drop);
+        assert_eq!(m.iter_count(), 0);
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn heads() {
+        let s = ArrayStorage::from_arr([[3, 5], [1, 2], [4, 6]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.heads().len(), 3);
+        assert_eq!(m.heads().copied().sum::<i32>(), 8);
+        assert_eq!(m.nth(1), Some(2));
+        assert_eq!(m.heads().len(), 2);
+        assert_eq!(m.heads().max(), Some(&4));
+        let mut source_heads = [(0, 0); 2];
+        for (slot, (source, &item)) in source_heads.iter_mut().zip(m.source_heads()) {
+            *slot = (source, item);
+        }
+        source_heads.sort_unstable();
+        assert_eq!(source_heads, [(0, 3), (2, 4)]);
+        // heads() doesn't consume anything
+        assert!(m.by_ref().eq([3, 4, 5, 6]));
+        assert_eq!(m.heads().len(), 0);
+        assert_eq!(m.source_heads().len(), 0);
+    }
+
+    #[test]
+    fn route_by() {
+        let s = ArrayStorage::from_arr([[1, 4], [2, 3]]);
+        let s = pin!(s);
+        let routed = s.build().route_by(|&item| item > 2);
+        assert_eq!(routed.size_hint(), (4, Some(4)));
+        assert!(routed.eq([(false, 1), (false, 2), (true, 3), (true, 4)]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn demux_into() {
+        use alloc::{vec, vec::Vec};
+        let mut sinks = [Vec::new(), Vec::new()];
+        crate::merge([[(1, 'a'), (3, 'b')], [(1, 'b'), (2, 'a')]])
+            .demux_into(|&(_, route)| usize::from(route == 'b'), &mut sinks);
+        assert_eq!(sinks, [vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (3, 'b')]]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_boxed_slice() {
+        let m = crate::merge([[3, 6], [1, 4], [2, 5]]);
+        assert_eq!(&*m.into_boxed_slice(), &[1, 2, 3, 4, 5, 6]);
+        let m = crate::merge([[0; 0]]);
+        assert!(m.into_boxed_slice().is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_vec_while() {
+        use alloc::{vec, vec::Vec};
+        let m = || crate::merge([vec![1, 4, 7, 8, 9], vec![2, 3], vec![5, 6]]);
+        for limit in 0..11 {
+            let mut merged = m();
+            let head = merged.into_vec_while(|&item| item < limit);
+            let expected: Vec<_> = (1..limit.max(1)).collect();
+            assert_eq!(head, expected);
+            assert!(merged.eq(limit.max(1)..10));
+        }
+        // the rejected item stays in place of the single remaining source
+        let mut merged = crate::merge([vec![1, 2, 3, 4]]);
+        assert_eq!(merged.into_vec_while(|&item| item != 3), vec![1, 2]);
+        assert_eq!(merged.into_vec_while(|_| true), vec![3, 4]);
+        assert!(merged.is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_vec_limited() {
+        use alloc::vec;
+        let m = || crate::merge([[3, 6], [1, 4], [2, 5]]);
+        assert_eq!(m().into_vec_limited(6).unwrap(), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(m().into_vec_limited(7).unwrap(), vec![1, 2, 3, 4, 5, 6]);
+        let err = m().into_vec_limited(5).unwrap_err();
+        assert_eq!(err.into_partial(), vec![1, 2, 3, 4, 5]);
+        let err = crate::merge([repeat(1)]).into_vec_limited(0).unwrap_err();
+        assert!(err.partial().is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn top_k() {
+        use alloc::vec;
+        let m = || crate::merge([[3, 6], [1, 4], [2, 5]]);
+        assert_eq!(m().top_k(2), vec![1, 2]);
+        assert_eq!(m().top_k(10), vec![1, 2, 3, 4, 5, 6]);
+        assert!(m().top_k(0).is_empty());
+        // the rest of the items isn't consumed
+        assert_eq!(crate::merge([repeat(1), repeat(0)]).top_k(3), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn source_tagged() {
+        use crate::comparators::tie_breaker;
+        let s = ArrayStorage::from_arr([[1, 1], [0, 1], [1, 2]]);
+        let s = pin!(s);
+        let tagged = s.build().source_tagged();
+        assert!(tagged.eq([(1, 0), (0, 1), (0, 1), (1, 1), (2, 1), (2, 2)]));
+
+        let s = ArrayStorage::from_arr([[1, 1], [0, 1], [1, 2]]);
+        let s = pin!(s);
+        let tagged = s
+            .into_builder()
+            .tie_breaker(tie_breaker::ReverseInsertionOrder)
+            .build()
+            .source_tagged();
+        assert!(tagged.eq([(1, 0), (2, 1), (1, 1), (0, 1), (0, 1), (2, 2)]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn source_tagged_vec() {
+        use alloc::vec;
+        let mut m = crate::merge([vec![3], vec![], vec![1, 2]]);
+        // Clones have their own storage, but the same indexes
+        m.next();
+        assert!(m.clone().source_tagged().eq([(2, 2), (0, 3)]));
+        assert!(m.source_tagged().eq([(2, 2), (0, 3)]));
+    }
+
+    #[test]
+    fn next_run() {
+        let s = ArrayStorage::from_arr([[1, 2, 5, 6], [3, 3, 4, 7]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert!(m.next_run().eq([1, 2]));
+        assert_eq!(m.next_run().next(), Some(3));
         assert_eq!(m.peek(), Some(&3));
+        assert!(m.next_run().eq([3, 4]));
+        assert!(m.next_run().eq([5, 6]));
+        assert!(m.next_run().eq([7]));
+        assert!(m.next_run().next().is_none());
     }
 
     #[test]
-    fn next_if() {
-        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+    fn take_until() {
+        let s = ArrayStorage::from_arr([[1, 4, 7], [2, 5, 8], [3, 6, 9]]);
         let s = pin!(s);
         let mut m = s.build();
-        assert_eq!(m.next_if(|&el| el <= 2), Some(1));
-        assert_eq!(m.next_if(|&el| el <= 2), Some(2));
-        m.nth(10);
-        assert_eq!(m.next_if(|_el| true), None);
+        assert_eq!(m.take_until(&4).size_hint(), (0, Some(9)));
+        assert_eq!(m.take_until(&4).next(), Some(1));
+        assert!(m.take_until(&4).eq([2, 3]));
+        assert!(m.take_until(&0).eq([]));
+        assert!(m.take_until(&7).eq([4, 5, 6]));
+        assert!(m.take_until(&100).eq([7, 8, 9]));
+        assert!(m.is_empty());
+
+        // Bound is compared by the key of the merge, equivalent items don't precede it
+        let s = ArrayStorage::from_arr([[(3, 'a'), (1, 'a')], [(3, 'b'), (2, 'b')]]);
+        let s = pin!(s);
+        let mut m = s.into_builder().max_by_key(|item| item.0).build();
+        assert!(m.take_until(&(3, 'z')).eq([]));
+        assert!(m.take_until(&(1, 'z')).eq([(3, 'a'), (3, 'b'), (2, 'b')]));
+        assert_eq!(m.next(), Some((1, 'a')));
     }
 
     #[test]
-    fn next_if_eq() {
-        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+    fn drain_less_than() {
+        let s = ArrayStorage::from_arr([[1, 4, 7], [2, 4, 8]]);
         let s = pin!(s);
         let mut m = s.build();
-        assert_eq!(m.next_if_eq(&1), Some(1));
-        assert_eq!(m.next_if_eq(&200), None);
-        m.nth(3);
-        assert_eq!(m.next_if_eq(&6), Some(6));
-        assert_eq!(m.next_if_eq(&7), None);
+        assert!(m.drain_less_than(&4).eq([1, 2]));
+        assert_eq!(m.peek(), Some(&4));
+        assert_eq!(m.drain_less_than(&8).next(), Some(4));
+        assert!(m.drain_less_than(&8).eq([4, 7]));
+        assert!(m.drain_less_than(&8).eq([]));
+        assert_eq!(m.next(), Some(8));
     }
 
     #[test]
-    fn count() {
-        let s = ArrayStorage::from_arr([[3, 6], [1, 4], [2, 5]]);
+    fn dedup() {
+        use crate::comparators::{ByOrd, MaxFirst};
+
+        let s = ArrayStorage::from_arr([[1, 1, 2, 5], [1, 3, 5, 5], [2, 3, 4, 6]]);
         let s = pin!(s);
-        let m = s.build();
-        assert_eq!(m.count(), 6);
+        let m = s.build().dedup();
+        assert_eq!(m.size_hint(), (1, Some(12)));
+        assert!(m.eq([1, 2, 3, 4, 5, 6]));
+
+        let s = ArrayStorage::from_arr([[3, 2, 2], [3, 1, 1]]);
+        let s = pin!(s);
+        let m = s.build_with(MaxFirst::new::<i32>(ByOrd)).dedup();
+        assert!(m.eq([3, 2, 1]));
+    }
+
+    #[test]
+    fn builder_dedup() {
+        let data = [[1, 1, 2, 5], [1, 3, 5, 5], [2, 3, 4, 6]];
+        let s = pin!(ArrayStorage::from_arr(data));
+        let mut m = s.into_builder().dedup();
+        assert_eq!(m.size_hint(), (1, Some(12)));
+        assert_eq!(m.next(), Some(1));
+        assert_eq!(m.peek(), Some(&2));
+        assert_eq!(m.nth(1), Some(3));
+        assert_eq!(m.peek(), Some(&4));
+        assert!(m.eq([4, 5, 6]));
+
+        let s = pin!(ArrayStorage::from_arr(data));
+        assert_eq!(s.into_builder().dedup().count(), 6);
+        let s = pin!(ArrayStorage::from_arr(data));
+        assert_eq!(s.into_builder().dedup().last(), Some(6));
+        let s = pin!(ArrayStorage::from_arr([[(3, 'a'), (1, 'a')], [(2, 'b'), (1, 'b')]]));
+        let m = s.into_builder().max_by_key(|item| item.0).dedup();
+        assert_eq!(m.last(), Some((1, 'a')));
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::vec;
+
+            let s = pin!(ArrayStorage::from_arr(data));
+            assert_eq!(s.into_builder().dedup().into_vec(), vec![1, 2, 3, 4, 5, 6]);
+            let s = pin!(ArrayStorage::from_arr(data));
+            let mut m = s.into_builder().dedup();
+            assert_eq!(m.into_vec_while(|&item| item < 5), vec![1, 2, 3, 4]);
+            assert_eq!(m.next(), Some(5));
+            assert_eq!(m.next(), Some(6));
+        }
+
+        let s = pin!(ArrayStorage::from_arr([
+            [(1, 'a'), (4, 'b')],
+            [(2, 'a'), (3, 'c')],
+            [(5, 'b'), (6, 'a')],
+        ]));
+        let m = s.into_builder().dedup_by_key(|item| item.1);
+        assert!(m.eq([(1, 'a'), (3, 'c'), (4, 'b'), (6, 'a')]));
+    }
+
+    #[test]
+    fn interleave() {
+        let s = pin!(ArrayStorage::from_arr([
+            &[1, 2, 3][..],
+            &[],
+            &[4],
+            &[5, 6],
+            &[7, 8, 9, 10],
+        ]));
+        let mut m = s.into_builder().interleave();
+        assert_eq!(m.size_hint(), (10, Some(10)));
+        assert!(m.by_ref().take(5).eq([&1, &4, &5, &7, &2]));
+        assert_eq!(m.size_hint(), (5, Some(5)));
+        assert!(m.eq([&6, &8, &3, &9, &10]));
+    }
+
+    #[test]
+    fn interleave_weighted() {
+        let s = pin!(ArrayStorage::from_arr([
+            &[1, 2, 3, 4, 5][..],
+            &[],
+            &[6, 7, 8],
+            &[9, 10, 11, 12],
+        ]));
+        let weights = [3, 100, 0, 2];
+        let mut m = s.into_builder().interleave_weighted(|source| weights[source]);
+        assert_eq!(m.size_hint(), (12, Some(12)));
+        assert!(m.by_ref().take(7).eq([&1, &2, &3, &6, &9, &10, &4]));
+        assert!(m.eq([&5, &7, &11, &12, &8]));
+    }
+
+    #[test]
+    fn key_range() {
+        let (a, b, c) = ([1, 3, 5, 7, 9], [2, 4, 6, 8], [0, 10]);
+        let s = pin!(ArrayStorage::from_arr([a.iter(), b.iter(), c.iter()]));
+        let mut range = s.into_builder().seek_start_at(&&3).end_before(&7);
+        assert_eq!(range.size_hint(), (0, Some(8)));
+        assert!(range.by_ref().eq([&3, &4, &5, &6]));
+        assert_eq!(range.size_hint(), (0, Some(0)));
+        assert_eq!(range.next(), None);
+
+        let s = pin!(ArrayStorage::from_arr([[1, 3, 5], [2, 4, 6], [0, 1, 2]]));
+        let range = s.into_builder().start_at(&3).end_before(6);
+        assert!(range.eq([3, 4, 5]));
+
+        let s = pin!(ArrayStorage::from_arr([[1, 2], [3, 4]]));
+        let mut range = s.into_builder().start_at(&5).end_before(6);
+        assert_eq!(range.size_hint(), (0, Some(0)));
+        assert_eq!(range.next(), None);
+
+        // Bounds are compared with the comparator of the merge
+        let s = pin!(ArrayStorage::from_arr([
+            [(9, 'a'), (6, 'a'), (3, 'a')],
+            [(8, 'b'), (3, 'b'), (1, 'b')],
+        ]));
+        let range = s
+            .into_builder()
+            .max_by_key(|item| item.0)
+            .start_at(&(8, 'z'))
+            .end_before((3, 'z'));
+        assert!(range.eq([(8, 'b'), (6, 'a')]));
+    }
+
+    #[test]
+    fn build_presorted() {
+        let s = pin!(ArrayStorage::from_arr([[1, 9], [2, 3], [2, 4], [5, 6], [7, 8]]));
+        let m = s.into_builder().build_presorted();
+        assert!(m.eq([1, 2, 2, 3, 4, 5, 6, 7, 8, 9]));
+
+        let s = pin!(ArrayStorage::from_arr([[(1, 'a')], [(1, 'b')], [(0, 'c')]]));
+        let m = s.into_builder().max_by_key(|item| item.0).build_presorted();
+        assert!(m.eq([(1, 'a'), (1, 'b'), (0, 'c')]));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "iterators aren't sorted by their first items")]
+    fn build_presorted_unsorted() {
+        let s = pin!(ArrayStorage::from_arr([[2], [1]]));
+        let _ = s.into_builder().build_presorted();
+    }
+
+    #[test]
+    fn build_lazy() {
+        let s = pin!(ArrayStorage::from_arr([[5, 6], [3, 4], [1, 2]]));
+        let mut lazy = s.into_builder().build_lazy();
+        assert!(!lazy.is_forced());
+        assert_eq!(lazy.size_hint(), (6, Some(6)));
+        assert_eq!(lazy.next(), Some(1));
+        assert!(lazy.is_forced());
+        assert!(lazy.into_inner().eq([2, 3, 4, 5, 6]));
+
+        let s = pin!(ArrayStorage::from_arr([[2], [1]]));
+        assert!(s.into_builder().build_lazy().into_inner().eq([1, 2]));
+    }
+
+    #[test]
+    fn merge_config() {
+        use crate::comparators::tie_breaker::ReverseInsertionOrder;
+
+        let s = pin!(ArrayStorage::from_arr([[(1, 'a'), (3, 'a')], [(1, 'b'), (2, 'b')]]));
+        let builder = s
+            .into_builder()
+            .min_by_key(|item: &(i32, char)| item.0)
+            .tie_breaker(ReverseInsertionOrder);
+        let config = builder.config();
+        assert!(builder.build().eq([(1, 'b'), (1, 'a'), (2, 'b'), (3, 'a')]));
+        for _ in 0..2 {
+            let s = pin!(ArrayStorage::from_arr([[(2, 'c')], [(2, 'd')]]));
+            let merged = config.apply(s.into_builder()).build();
+            assert!(merged.eq([(2, 'd'), (2, 'c')]));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn checked() {
+        use alloc::vec;
+
+        use crate::{VecStorage, merge};
+
+        let sorted = VecStorage::from_iter([vec![(1, 'a'), (1, 'b'), (2, 'a')], vec![(1, 'c')]])
+            .into_builder()
+            .min_by_key(|item| item.0)
+            .checked();
+        assert!(sorted.map(Result::unwrap).eq([(1, 'a'), (1, 'b'), (1, 'c'), (2, 'a')]));
+
+        let mut checked = merge([vec![2, 1, 7], vec![3, 4], vec![], vec![5, 0]]).checked();
+        // The debug checks panic on the unsorted source before it's reported
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        {
+            use crate::tests::std::{panic::catch_unwind, string::String};
+
+            let panic = catch_unwind(move || checked.by_ref().for_each(drop)).unwrap_err();
+            assert_eq!(
+                panic.downcast_ref::<String>().map(String::as_str),
+                Some("source 0 yielded items out of order")
+            );
+        }
+        #[cfg(not(all(debug_assertions, iter_merge_debug_checks)))]
+        {
+            use super::UnsortedSource;
+
+            assert_eq!(checked.next(), Some(Ok(2)));
+            assert_eq!(checked.size_hint(), (2, Some(5)));
+            assert_eq!(checked.next(), Some(Err(UnsortedSource(0))));
+            assert_eq!(checked.size_hint(), (1, Some(4)));
+            assert_eq!(checked.next(), Some(Ok(3)));
+            assert_eq!(checked.next(), Some(Ok(4)));
+            assert_eq!(checked.next(), Some(Ok(5)));
+            assert_eq!(checked.next().unwrap().unwrap_err().source_index(), 3);
+            assert_eq!(checked.next(), None);
+        }
+    }
+
+    #[test]
+    fn limit() {
+        use core::{cell::Cell, ops::Range};
+
+        struct Tracked<'a>(Range<i32>, &'a Cell<usize>);
+        impl Iterator for Tracked<'_> {
+            type Item = i32;
+            fn next(&mut self) -> Option<i32> {
+                self.0.next()
+            }
+        }
+        impl Drop for Tracked<'_> {
+            fn drop(&mut self) {
+                self.1.set(self.1.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let s = ArrayStorage::from_arr([Tracked(0..10, &drops), Tracked(5..15, &drops)]);
+        let s = pin!(s);
+        let mut m = s.build().limit(3);
+        assert_eq!(m.size_hint(), (2, Some(3)));
+        assert!(m.by_ref().take(2).eq([0, 1]));
+        assert_eq!(drops.get(), 0);
+        assert_eq!(m.next(), Some(2));
+        assert_eq!(drops.get(), 2);
+        assert_eq!(m.next(), None);
+
+        let s = ArrayStorage::from_arr([[1, 2], [3, 4]]);
+        let s = pin!(s);
+        let mut m = s.build().limit(0);
+        assert_eq!(m.iter.iter_count(), 0);
+        assert_eq!(m.next(), None);
+    }
+
+    #[test]
+    fn dedup_by_key() {
+        use crate::comparators::tie_breaker;
+
+        let s = ArrayStorage::from_arr([
+            [(1, 'a'), (4, 'b')],
+            [(2, 'a'), (3, 'c')],
+            [(5, 'b'), (6, 'a')],
+        ]);
+        let s = pin!(s);
+        let m = s.build().dedup_by_key(|item| item.1);
+        assert!(m.eq([(1, 'a'), (3, 'c'), (4, 'b'), (6, 'a')]));
+
+        let s = ArrayStorage::from_arr([[1, 2], [1, 3]]);
+        let s = pin!(s);
+        let m = s.into_builder().tie_breaker(tie_breaker::Unspecified).dedup();
+        assert!(m.eq([1, 2, 3]));
+    }
+
+    #[test]
+    fn coalesce_equal() {
+        use core::cmp::Ordering;
+
+        use crate::comparators::Comparator;
+
+        struct ByFirst;
+        impl Comparator<(u8, u32)> for ByFirst {
+            fn compare(&self, a: &(u8, u32), b: &(u8, u32)) -> Ordering {
+                a.0.cmp(&b.0)
+            }
+        }
+        let s = ArrayStorage::from_arr([
+            [(1, 1), (1, 2), (3, 4)],
+            [(1, 8), (2, 16), (3, 32)],
+            [(0, 64), (3, 128), (4, 256)],
+        ]);
+        let s = pin!(s);
+        let m = s
+            .build_with(ByFirst)
+            .coalesce_equal(|(key, acc), (_, value)| (key, acc | value));
+        assert_eq!(m.size_hint(), (1, Some(9)));
+        assert!(m.eq([(0, 64), (1, 11), (2, 16), (3, 164), (4, 256)]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn mark_rewind() {
+        use crate::merge;
+
+        let (a, b, c) = ([1, 4], [2, 5], [3, 6]);
+        let mut m = merge([a.iter(), b.iter(), c.iter()]);
+        let start = m.mark();
+        assert!(m.by_ref().take(4).eq([&1, &2, &3, &4]));
+        let middle = m.mark();
+        assert!(m.by_ref().eq([&5, &6]));
+        m.rewind(&middle);
+        assert_eq!(m.iter_count(), 2);
+        assert_eq!(m.next(), Some(&5));
+        m.rewind(&start);
+        assert!(m.clone().eq([&1, &2, &3, &4, &5, &6]));
+        assert!(m.source_tagged().eq([(0, &1), (1, &2), (2, &3), (0, &4), (1, &5), (2, &6)]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn partition_at() {
+        let s = ArrayStorage::from_arr([[1, 4, 7], [2, 5, 8], [3, 6, 9]]);
+        let s = pin!(s);
+        let (head, tail) = s.build().partition_at(&4);
+        assert_eq!(head, [1, 2, 3]);
+        assert_eq!(tail, [4, 5, 6, 7, 8, 9]);
+
+        let s = ArrayStorage::from_arr([[1, 2], [3, 4]]);
+        let s = pin!(s);
+        let (head, tail) = s.build().partition_at(&10);
+        assert_eq!(head, [1, 2, 3, 4]);
+        assert!(tail.is_empty());
+
+        // Partitioned by the comparator of the merge
+        let s = ArrayStorage::from_arr([[(5, 'a'), (1, 'a')], [(4, 'b'), (1, 'b')]]);
+        let s = pin!(s);
+        let m = s.into_builder().max_by_key(|item| item.0).build();
+        let (head, tail) = m.partition_at(&(4, 'z'));
+        assert_eq!(head, [(5, 'a')]);
+        assert_eq!(tail, [(4, 'b'), (1, 'a'), (1, 'b')]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn group_by() {
+        use alloc::vec;
+
+        let s = ArrayStorage::from_arr([[1, 4, 7, 10], [2, 3, 8, 9], [5, 6, 11, 12]]);
+        let s = pin!(s);
+        let m = s.build().group_by(|item| item / 4);
+        assert_eq!(m.size_hint(), (1, Some(12)));
+        assert!(m.eq([
+            (0, vec![1, 2, 3]),
+            (1, vec![4, 5, 6, 7]),
+            (2, vec![8, 9, 10, 11]),
+            (3, vec![12]),
+        ]));
+    }
+
+    #[test]
+    fn dedup_with_count() {
+        let s = ArrayStorage::from_arr([[1, 1, 2, 5], [1, 3, 5, 5], [2, 3, 4, 6]]);
+        let s = pin!(s);
+        let m = s.build().dedup_with_count();
+        assert!(m.eq([(3, 1), (2, 2), (2, 3), (1, 4), (3, 5), (1, 6)]));
     }
 
     #[inline]
@@ -0,0 +1,964 @@
+//! Iterator adapters for the [`MergeIter`]
+use core::{
+    cmp::Ordering,
+    fmt::Debug,
+    iter::FusedIterator,
+    mem::{self, MaybeUninit},
+    slice,
+};
+
+use super::{Heap, UnsortedSource};
+use crate::{
+    MergeIter,
+    comparators::{Comparator, tie_breaker::Unspecified},
+    internal::{Item, Iter, pointers::ptr_to_usize, precedes},
+    meta::WithMeta,
+    storage::Storage,
+};
+
+/// Iterator over a run of items from a single source of the [`MergeIter`]
+///
+/// Constructed by [`MergeIter::next_run`]
+#[derive(Debug)]
+pub struct Run<'a, S, CMP> {
+    pub(crate) heap: &'a mut Heap<S, CMP>,
+    // Address of the source of the run, None before the first item is yielded
+    pub(crate) source: Option<usize>,
+}
+
+impl<S, CMP> Iterator for Run<'_, S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.heap.storage.is_empty() {
+            return None;
+        }
+        // SAFETY: len >= 1
+        let first = ptr_to_usize(unsafe { *self.heap.storage.first() });
+        match self.source {
+            None => self.source = Some(first),
+            // Once the source lost the first place it can't regain it, since the heap
+            // could be advanced only through this iterator
+            Some(source) if source != first => return None,
+            Some(_) => {}
+        }
+        self.heap.pop_front_item()
+    }
+}
+
+impl<S, CMP> FusedIterator for Run<'_, S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator over the items of the [`MergeIter`] that precede a bound
+///
+/// Constructed by [`MergeIter::take_until`] and [`MergeIter::drain_less_than`]
+pub struct TakeUntil<'a, S: Storage, CMP> {
+    pub(crate) iter: &'a mut MergeIter<S, CMP>,
+    pub(crate) bound: &'a Item<S>,
+}
+
+impl<S, CMP> Debug for TakeUntil<'_, S, CMP>
+where
+    S: Storage + Debug,
+    CMP: Debug,
+    Item<S>: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TakeUntil")
+            .field("iter", &self.iter)
+            .field("bound", &self.bound)
+            .finish()
+    }
+}
+
+impl<S, CMP> Iterator for TakeUntil<'_, S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut self.iter.0;
+        if precedes(&heap.comparator, heap.storage.peek()?, self.bound) {
+            heap.pop_front_item()
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// Iterator yielding `(route_key, item)` pairs in merged order
+///
+/// Constructed by [`MergeIter::route_by`]
+#[derive(Debug, Clone)]
+pub struct RouteBy<S, CMP, F> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) route: F,
+}
+
+impl<S, CMP, F, K> Iterator for RouteBy<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+{
+    type Item = (K, Item<S>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        Some(((self.route)(&item), item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<S, CMP, F, K> FusedIterator for RouteBy<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+{
+}
+
+/// Iterator yielding `(source_index, item)` pairs in merged order
+///
+/// Constructed by [`MergeIter::source_tagged`]
+#[derive(Debug, Clone)]
+pub struct SourceTagged<S, CMP>(pub(crate) MergeIter<S, CMP>);
+
+impl<S, CMP> Iterator for SourceTagged<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = (usize, Item<S>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut (self.0).0;
+        if heap.storage.is_empty() {
+            return None;
+        }
+        let source = heap
+            .storage
+            .first_source_index()
+            .expect("storage doesn't support source indexes");
+        Some((source, heap.pop_front_item()?))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<S, CMP> FusedIterator for SourceTagged<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator that yields only the first item of every run of equivalent items of the
+/// [`MergeIter`]
+///
+/// Constructed by [`MergeIter::dedup`]
+#[derive(Debug, Clone)]
+pub struct Dedup<S, CMP>(pub(crate) MergeIter<S, CMP>);
+
+impl<S, CMP> Iterator for Dedup<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut (self.0).0;
+        let item = heap.pop_front_item()?;
+        while heap.peeked_equivalent(&item) {
+            heap.pop_front_item();
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.0.size_hint();
+        (min.min(1), max)
+    }
+}
+
+impl<S, CMP> FusedIterator for Dedup<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator that yields only the first item of every run of items with equal keys of the
+/// [`MergeIter`]
+///
+/// Constructed by [`MergeIter::dedup_by_key`]
+#[derive(Debug, Clone)]
+pub struct DedupByKey<S, CMP, F> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) key: F,
+}
+
+impl<S, CMP, F, K> Iterator for DedupByKey<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+    K: PartialEq,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut self.iter.0;
+        let item = heap.pop_front_item()?;
+        let key = (self.key)(&item);
+        while heap
+            .storage
+            .peek()
+            .map_or(false, |next| (self.key)(next) == key)
+        {
+            heap.pop_front_item();
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iter.size_hint();
+        (min.min(1), max)
+    }
+}
+
+impl<S, CMP, F, K> FusedIterator for DedupByKey<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+    K: PartialEq,
+{
+}
+
+/// Iterator that yields at most `n` items of the [`MergeIter`], dropping the component
+/// iterators as soon as the limit is reached
+///
+/// Constructed by [`MergeIter::limit`]
+#[derive(Debug, Clone)]
+pub struct Limit<S, CMP> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) remaining: usize,
+}
+
+impl<S, CMP> Limit<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    pub(crate) fn new(mut iter: MergeIter<S, CMP>, limit: usize) -> Self {
+        if limit == 0 {
+            iter.retain_sources(|_| false);
+        }
+        Self {
+            iter,
+            remaining: limit,
+        }
+    }
+}
+
+impl<S, CMP> Iterator for Limit<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.iter.next()?;
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            // release the resources held by the iterators
+            self.iter.retain_sources(|_| false);
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iter.size_hint();
+        (
+            min.min(self.remaining),
+            Some(max.map_or(self.remaining, |max| max.min(self.remaining))),
+        )
+    }
+}
+
+impl<S, CMP> FusedIterator for Limit<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// [`MergeIter`] that defers building the heap until the first item is requested
+///
+/// Constructed by [`Builder::build_lazy`](super::Builder::build_lazy)
+#[derive(Debug, Clone)]
+pub struct LazyMergeIter<S, CMP> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) heapified: bool,
+}
+
+impl<S, CMP> LazyMergeIter<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    /// Returns the [`MergeIter`], building the heap if it wasn't built yet
+    #[inline]
+    pub fn force(&mut self) -> &mut MergeIter<S, CMP> {
+        if !self.heapified {
+            self.iter.0.heapify_storage();
+            self.heapified = true;
+        }
+        &mut self.iter
+    }
+
+    /// Returns `true` if the heap was built
+    #[inline]
+    pub const fn is_forced(&self) -> bool {
+        self.heapified
+    }
+
+    /// Peeks the next item, building the heap if it wasn't built yet, see
+    /// [`MergeIter::peek`]
+    #[inline]
+    pub fn peek(&mut self) -> Option<&Item<S>> {
+        self.force().peek()
+    }
+
+    /// Returns the [`MergeIter`], building the heap if it wasn't built yet
+    #[inline]
+    pub fn into_inner(mut self) -> MergeIter<S, CMP> {
+        self.force();
+        self.iter
+    }
+}
+
+impl<S, CMP> Iterator for LazyMergeIter<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.force().next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Doesn't depend on the heap order
+        self.iter.size_hint()
+    }
+}
+
+impl<S, CMP> FusedIterator for LazyMergeIter<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator that ignores the order of the items and yields one item from every component
+/// iterator in turn, in the order of insertion
+///
+/// Constructed by [`Builder::interleave`](super::Builder::interleave)
+#[derive(Debug, Clone)]
+pub struct Interleave<S> {
+    // Sources are kept in the order of insertion, comparator is never used
+    pub(crate) iter: MergeIter<S, Unspecified>,
+    // Position of the source that yields the next item
+    pub(crate) next: usize,
+}
+
+impl<S: Storage> Interleave<S> {
+    /// Yields the next item of the current source, moving to the next source if `advance` is
+    /// `true` or if the current one is exhausted. Returns `true` alongside the item if the
+    /// current source has changed.
+    fn pop_current(&mut self, advance: bool) -> Option<(Item<S>, bool)> {
+        let storage = &mut self.iter.0.storage;
+        let len = storage.len();
+        if len == 0 {
+            return None;
+        }
+        let pos = self.next;
+        debug_assert!(pos < len);
+        let heap = storage.heap();
+        // SAFETY: pos < len, no other references into the storage exist
+        let peek_iter = unsafe { &mut **heap.add(pos) };
+        if let Some(next) = peek_iter.iter.next() {
+            if advance {
+                self.next = if pos + 1 == len { 0 } else { pos + 1 };
+            }
+            return Some((mem::replace(&mut peek_iter.item, next), advance));
+        }
+        // Moving the exhausted source to the end, keeping the order of the rest
+        // SAFETY: positions pos..len are valid, no other references into the storage exist
+        unsafe { slice::from_raw_parts_mut(heap.add(pos), len - pos) }.rotate_left(1);
+        if pos + 1 == len {
+            self.next = 0;
+        }
+        storage.pop_last_item().map(|(item, _)| (item, true))
+    }
+
+    /// Returns the source index of the current source
+    fn current_source(&self) -> Option<usize> {
+        let storage = &self.iter.0.storage;
+        if storage.is_empty() {
+            return None;
+        }
+        // SAFETY: next < len
+        let peek_iter = unsafe { *storage.heap().add(self.next) };
+        Some(
+            storage
+                .source_index(peek_iter)
+                .expect("storage doesn't support source indexes"),
+        )
+    }
+}
+
+impl<S: Storage> Iterator for Interleave<S> {
+    type Item = Item<S>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pop_current(true).map(|(item, _)| item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<S: Storage> FusedIterator for Interleave<S> {}
+
+/// Iterator that ignores the order of the items and yields up to `weight` items from every
+/// component iterator in turn, in the order of insertion
+///
+/// Constructed by [`Builder::interleave_weighted`](super::Builder::interleave_weighted)
+#[derive(Debug, Clone)]
+pub struct WeightedInterleave<S, F> {
+    pub(crate) iter: Interleave<S>,
+    pub(crate) weight: F,
+    // Number of items the current source may yield in this turn
+    pub(crate) quota: usize,
+}
+
+impl<S, F> Iterator for WeightedInterleave<S, F>
+where
+    S: Storage,
+    F: FnMut(usize) -> usize,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.quota == 0 {
+            self.quota = (self.weight)(self.iter.current_source()?).max(1);
+        }
+        self.quota -= 1;
+        let (item, moved) = self.iter.pop_current(self.quota == 0)?;
+        if moved {
+            self.quota = 0;
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<S, F> FusedIterator for WeightedInterleave<S, F>
+where
+    S: Storage,
+    F: FnMut(usize) -> usize,
+{
+}
+
+/// Iterator that verifies that every source of the [`MergeIter`] yields its items in order
+///
+/// Constructed by [`MergeIter::checked`]
+#[derive(Debug, Clone)]
+pub struct Checked<S, CMP> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) error: Option<UnsortedSource>,
+}
+
+impl<S, CMP> Iterator for Checked<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Result<Item<S>, UnsortedSource>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        let heap = &mut self.iter.0;
+        if heap.storage.is_empty() {
+            return None;
+        }
+        let source = heap
+            .storage
+            .first_source_index()
+            .expect("storage doesn't support source indexes");
+        let item = heap.pop_front_item()?;
+        // The next item of the source is the smallest one in the heap if it's smaller than
+        // the item that was just popped
+        let unsorted = heap.storage.first_source_index() == Some(source)
+            && heap.cmp_peeked(&item).map_or(false, Ordering::is_lt)
+            && !heap.peeked_equivalent(&item);
+        if unsorted {
+            self.iter.remove_source(source);
+            self.error = Some(UnsortedSource(source));
+        }
+        Some(Ok(item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iter.size_hint();
+        // Every error replaces at least one dropped item
+        let pending = usize::from(self.error.is_some());
+        (min.min(1) + pending, max.and_then(|max| max.checked_add(pending)))
+    }
+}
+
+impl<S, CMP> FusedIterator for Checked<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator that yields the items of the [`MergeIter`] preceding the bound, dropping the
+/// component iterators as soon as the next item doesn't
+///
+/// `T` is the type of the bound, the item type of the [`MergeIter`].
+///
+/// Constructed by [`MergeIter::end_before`]
+#[derive(Debug, Clone)]
+pub struct EndBefore<S, CMP, T> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) bound: T,
+}
+
+impl<S, CMP> Iterator for EndBefore<S, CMP, Item<S>>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut self.iter.0;
+        if precedes(&heap.comparator, heap.storage.peek()?, &self.bound) {
+            return heap.pop_front_item();
+        }
+        // release the resources held by the iterators
+        self.iter.retain_sources(|_| false);
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<S, CMP> FusedIterator for EndBefore<S, CMP, Item<S>>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator yielding the first item of every run of equivalent items of the [`MergeIter`]
+/// together with the length of the run
+///
+/// Constructed by [`MergeIter::dedup_with_count`]
+#[derive(Debug, Clone)]
+pub struct DedupWithCount<S, CMP>(pub(crate) MergeIter<S, CMP>);
+
+impl<S, CMP> Iterator for DedupWithCount<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = (usize, Item<S>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut (self.0).0;
+        let item = heap.pop_front_item()?;
+        let mut count = 1_usize;
+        while heap.peeked_equivalent(&item) {
+            heap.pop_front_item();
+            count += 1;
+        }
+        Some((count, item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.0.size_hint();
+        (min.min(1), max)
+    }
+}
+
+impl<S, CMP> FusedIterator for DedupWithCount<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+/// Iterator that combines the runs of equivalent items of the [`MergeIter`] into one item
+///
+/// Constructed by [`MergeIter::coalesce_equal`]
+#[derive(Debug, Clone)]
+pub struct CoalesceEqual<S, CMP, F> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) func: F,
+}
+
+impl<S, CMP, F> Iterator for CoalesceEqual<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(Item<S>, Item<S>) -> Item<S>,
+{
+    type Item = Item<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap = &mut (self.iter).0;
+        let mut acc = heap.pop_front_item()?;
+        while heap.peeked_equivalent(&acc) {
+            // peeked item exists
+            let Some(next) = heap.pop_front_item() else {
+                break;
+            };
+            acc = (self.func)(acc, next);
+        }
+        Some(acc)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iter.size_hint();
+        (min.min(1), max)
+    }
+}
+
+impl<S, CMP, F> FusedIterator for CoalesceEqual<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(Item<S>, Item<S>) -> Item<S>,
+{
+}
+
+/// Iterator yielding `(key, group)` pairs for every run of items of the [`MergeIter`]
+/// sharing a key
+///
+/// Constructed by [`MergeIter::group_by`]
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct GroupBy<S, CMP, F> {
+    pub(crate) iter: MergeIter<S, CMP>,
+    pub(crate) key: F,
+}
+
+#[cfg(feature = "alloc")]
+impl<S, CMP, F, K> Iterator for GroupBy<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+    K: PartialEq,
+{
+    type Item = (K, alloc::vec::Vec<Item<S>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.iter.next()?;
+        let key = (self.key)(&first);
+        let mut group = alloc::vec![first];
+        let func = &mut self.key;
+        while let Some(item) = self.iter.next_if(|item| func(item) == key) {
+            group.push(item);
+        }
+        Some((key, group))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.iter.size_hint();
+        (min.min(1), max)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S, CMP, F, K> FusedIterator for GroupBy<S, CMP, F>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    F: FnMut(&Item<S>) -> K,
+    K: PartialEq,
+{
+}
+
+/// Iterator yielding `(meta, item)` pairs, where `meta` is the metadata of the source
+/// of the item
+///
+/// Constructed by [`MergeIter::meta_tagged`]
+#[derive(Debug, Clone)]
+pub struct MetaTagged<S, CMP>(pub(crate) MergeIter<S, CMP>);
+
+impl<S, CMP, IT, M> Iterator for MetaTagged<S, CMP>
+where
+    S: Storage<IT = WithMeta<IT, M>>,
+    CMP: Comparator<IT::Item>,
+    IT: Iterator,
+    M: Clone,
+{
+    type Item = (M, IT::Item);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let meta = self.0.peek_meta()?.clone();
+        Some((meta, (self.0).0.pop_front_item()?))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<S, CMP, IT, M> FusedIterator for MetaTagged<S, CMP>
+where
+    S: Storage<IT = WithMeta<IT, M>>,
+    CMP: Comparator<IT::Item>,
+    IT: Iterator,
+    M: Clone,
+{
+}
+
+/// Concatenation of two [`MergeIter`]s, where all items of the first one precede all items
+/// of the second one.
+///
+/// Provides the same methods for peeking and efficient collection as [`MergeIter`].
+///
+/// Constructed by [`MergeIter::chain_after`]
+#[derive(Debug, Clone)]
+pub struct ChainAfter<S1, CMP1, S2, CMP2> {
+    pub(crate) first: MergeIter<S1, CMP1>,
+    pub(crate) second: MergeIter<S2, CMP2>,
+}
+
+impl<S1, CMP1, S2, CMP2> ChainAfter<S1, CMP1, S2, CMP2>
+where
+    S1: Storage,
+    CMP1: Comparator<Item<S1>>,
+    S2: Storage,
+    Iter<S2>: Iterator<Item = Item<S1>>,
+    CMP2: Comparator<Item<S1>>,
+{
+    /// See [`MergeIter::peek`]
+    #[inline]
+    pub fn peek(&self) -> Option<&Item<S1>> {
+        self.first.peek().or_else(|| self.second.peek())
+    }
+
+    /// See [`MergeIter::next_if`]
+    pub fn next_if(&mut self, func: impl FnOnce(&Item<S1>) -> bool) -> Option<Item<S1>> {
+        if self.first.peek().is_some() {
+            self.first.next_if(func)
+        } else {
+            self.second.next_if(func)
+        }
+    }
+
+    /// See [`MergeIter::next_if_eq`]
+    pub fn next_if_eq<T>(&mut self, expected: &T) -> Option<Item<S1>>
+    where
+        T: ?Sized,
+        Item<S1>: PartialEq<T>,
+    {
+        self.next_if(|item| item == expected)
+    }
+
+    #[cfg(feature = "alloc")]
+    /// See [`MergeIter::into_vec`]
+    pub fn into_vec(self) -> alloc::vec::Vec<Item<S1>> {
+        let mut res = self.first.into_vec();
+        self.second.collect_into(&mut res);
+        res
+    }
+
+    /// See [`MergeIter::collect_into`]
+    pub fn collect_into<E: Extend<Item<S1>>>(self, collection: &mut E) {
+        self.first.collect_into(collection);
+        self.second.collect_into(collection);
+    }
+}
+
+impl<S1, CMP1, S2, CMP2> Iterator for ChainAfter<S1, CMP1, S2, CMP2>
+where
+    S1: Storage,
+    CMP1: Comparator<Item<S1>>,
+    S2: Storage,
+    Iter<S2>: Iterator<Item = Item<S1>>,
+    CMP2: Comparator<Item<S1>>,
+{
+    type Item = Item<S1>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.first.next().or_else(|| self.second.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min1, max1) = self.first.size_hint();
+        let (min2, max2) = self.second.size_hint();
+        let max = match (max1, max2) {
+            (Some(max1), Some(max2)) => max1.checked_add(max2),
+            _ => None,
+        };
+        (min1.saturating_add(min2), max)
+    }
+
+    fn count(self) -> usize {
+        // panic in debug and wrapping in release is the expected behaviour
+        #[allow(clippy::arithmetic_side_effects)]
+        {
+            self.first.count() + self.second.count()
+        }
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        let first = self.first.last();
+        self.second.last().or(first)
+    }
+}
+
+impl<S1, CMP1, S2, CMP2> FusedIterator for ChainAfter<S1, CMP1, S2, CMP2>
+where
+    S1: Storage,
+    CMP1: Comparator<Item<S1>>,
+    S2: Storage,
+    Iter<S2>: Iterator<Item = Item<S1>>,
+    CMP2: Comparator<Item<S1>>,
+{
+}
+
+/// Items that remained in the [`MergeIter`] when fewer than `N` were left for
+/// [`MergeIter::next_array`]
+///
+/// Iterates over the remaining items in merged order, like the [`IntoIter`](core::array::IntoIter)
+/// returned by the unstable `Iterator::next_chunk`.
+pub struct ArrayRemainder<T, const N: usize> {
+    pub(crate) array: [MaybeUninit<T>; N],
+    // Items in start..end are initialized
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl<T, const N: usize> ArrayRemainder<T, N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            // SAFETY: array of MaybeUninit does not need initialization
+            array: unsafe { MaybeUninit::uninit().assume_init() },
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Returns the remaining items as a slice
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        let init = &self.array[self.start..self.end];
+        // SAFETY: items in start..end are initialized, MaybeUninit<T> has the same layout as T
+        unsafe { slice::from_raw_parts(init.as_ptr().cast(), init.len()) }
+    }
+}
+
+impl<T, const N: usize> Iterator for ArrayRemainder<T, N> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        // SAFETY: the item was initialized, it's excluded from start..end, so it's read once
+        Some(unsafe { self.array[self.start - 1].assume_init_read() })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for ArrayRemainder<T, N> {}
+
+impl<T, const N: usize> FusedIterator for ArrayRemainder<T, N> {}
+
+impl<T, const N: usize> Drop for ArrayRemainder<T, N> {
+    fn drop(&mut self) {
+        for item in &mut self.array[self.start..self.end] {
+            // SAFETY: items in start..end are initialized
+            unsafe {
+                item.assume_init_drop();
+            }
+        }
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for ArrayRemainder<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArrayRemainder").field(&self.as_slice()).finish()
+    }
+}
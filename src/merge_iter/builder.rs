@@ -1,16 +1,30 @@
 use core::cmp::Ordering;
 
-use super::Heap;
+use super::{
+    Checked, EndBefore, Heap, Interleave, LazyMergeIter, Limit, MetaTagged,
+    WeightedInterleave,
+};
 use crate::{
     MergeIter,
-    comparators::{ByFunc, ByKey, ByOrd, Chain, Comparator, MaxFirst, tie_breaker},
-    internal::Item,
+    comparators::{
+        BranchlessCmp, ByFunc, ByKey, ByKeyRef, ByKeyWith, ByLessThan, ByMut, ByOrd,
+        ByOrdBranchless, ByPartialOrd, Chain, Comparator, ComparatorMut, Deduplicated,
+        DeduplicatedByKey, Incomparable, MaxFirst, Reversible, tie_breaker,
+    },
+    internal::{Item, Iter, precedes},
+    meta::WithMeta,
+    seek::SeekableSource,
     storage::Storage,
 };
+#[cfg(feature = "alloc")]
+use crate::{comparators::DynComparator, storage::HeapKeyStorage};
 
 /// [`MergeIter`] with default comparator
 pub type DefaultMergeIter<S> = MergeIter<S, Chain<ByOrd, tie_breaker::InsertionOrder>>;
 
+/// [`MergeIter`] with a custom comparator and the default tie breaker
+pub type MergeIterWith<S, CMP> = MergeIter<S, Chain<CMP, tie_breaker::InsertionOrder>>;
+
 /// [`MergeIter`] with default comparator
 pub type DefaultBuilder<S> = Builder<S, ByOrd, tie_breaker::InsertionOrder>;
 
@@ -20,13 +34,88 @@ pub type DefaultBuilder<S> = Builder<S, ByOrd, tie_breaker::InsertionOrder>;
 ///
 /// By default items are compared using [`Ord`], smallest item yielded first, and if the
 /// items are equal - they are yielded in insertion order, earliest -- first.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Builder<S, CMP, TieBreaker> {
     comparator: CMP,
     tie_breaker: TieBreaker,
     storage: S,
 }
 
+/// Configuration of the [`Builder`] (comparator and tie breaker) without the storage
+///
+/// Constructed by [`Builder::config`], reusable for any number of storages.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::VecStorage;
+///
+/// let config = VecStorage::<std::vec::IntoIter<(u32, &str)>>::new()
+///     .into_builder()
+///     .max_by_key(|item| item.0)
+///     .config();
+/// let batches = [[vec![(2, "a")], vec![(3, "b")]], [vec![(1, "c")], vec![(5, "d")]]];
+/// for (batch, expected) in batches.into_iter().zip([["b", "a"], ["d", "c"]]) {
+///     let merged = config.apply(VecStorage::from_iter(batch).into_builder()).build();
+///     assert!(merged.map(|item| item.1).eq(expected));
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MergeConfig<CMP, TieBreaker> {
+    comparator: CMP,
+    tie_breaker: TieBreaker,
+}
+
+impl<CMP, TieBreaker> MergeConfig<CMP, TieBreaker> {
+    /// Replaces the configuration of the `builder` with this one, keeping its storage
+    #[inline]
+    pub fn apply<S, C, TB>(&self, builder: Builder<S, C, TB>) -> Builder<S, CMP, TieBreaker>
+    where
+        S: Storage,
+        CMP: Clone,
+        TieBreaker: Clone,
+    {
+        let mut storage = builder.storage;
+        storage.forget_keys();
+        Builder {
+            comparator: self.comparator.clone(),
+            tie_breaker: self.tie_breaker.clone(),
+            storage,
+        }
+    }
+}
+
+impl<S, CMP, TieBreaker> Builder<S, CMP, TieBreaker> {
+    /// Replaces the storage with the result of `func`, keeping the rest of the configuration
+    #[inline]
+    pub(crate) fn map_storage<S2>(
+        self, func: impl FnOnce(S) -> S2,
+    ) -> Builder<S2, CMP, TieBreaker> {
+        Builder {
+            comparator: self.comparator,
+            tie_breaker: self.tie_breaker,
+            storage: func(self.storage),
+        }
+    }
+
+    /// Returns the configuration of this builder, to be applied to other storages, see
+    /// [`MergeConfig`]
+    #[inline]
+    pub fn config(&self) -> MergeConfig<CMP, TieBreaker>
+    where
+        CMP: Clone,
+        TieBreaker: Clone,
+    {
+        MergeConfig {
+            comparator: self.comparator.clone(),
+            tie_breaker: self.tie_breaker.clone(),
+        }
+    }
+}
+
 impl<S, CMP, TieBreaker> Builder<S, CMP, TieBreaker>
 where
     S: Storage,
@@ -40,9 +129,40 @@ where
         }
     }
 
+    /// Compare heap items using their [`Ord`] implementation and yield smallest item first
+    #[inline]
+    pub fn min(self) -> Builder<S, ByOrd, TieBreaker>
+    where
+        Item<S>: Ord,
+    {
+        self.min_by(ByOrd)
+    }
+
+    /// Compare heap items using their [`Ord`] implementation and yield largest item first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([[5, 2], [4, 3]]).into_builder().max().build();
+    /// assert_eq!(merged.into_vec(), vec![5, 4, 3, 2]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn max(self) -> Builder<S, MaxFirst<ByOrd>, TieBreaker>
+    where
+        Item<S>: Ord,
+    {
+        self.max_by(ByOrd)
+    }
+
     /// Compare heap items using comparator `cmp` and yield smallest item first
     #[inline]
-    pub fn min_by<C: Comparator<Item<S>>>(self, cmp: C) -> Builder<S, C, TieBreaker> {
+    pub fn min_by<C: Comparator<Item<S>>>(mut self, cmp: C) -> Builder<S, C, TieBreaker> {
+        self.storage.forget_keys();
         Builder::new(self.storage, cmp, self.tie_breaker)
     }
 
@@ -54,6 +174,96 @@ where
         self.min_by(MaxFirst(cmp))
     }
 
+    /// Compare heap items using their [`PartialOrd`] implementation and yield smallest item
+    /// first. Incomparable items are handled according to `policy`, see [`Incomparable`].
+    #[inline]
+    pub fn min_by_partial(self, policy: Incomparable) -> Builder<S, ByPartialOrd, TieBreaker>
+    where
+        Item<S>: PartialOrd,
+    {
+        self.min_by(ByPartialOrd(policy))
+    }
+
+    /// Compare heap items using their [`PartialOrd`] implementation and yield largest item
+    /// first. Incomparable items are handled according to `policy`, see [`Incomparable`].
+    #[inline]
+    pub fn max_by_partial(
+        self, policy: Incomparable,
+    ) -> Builder<S, MaxFirst<ByPartialOrd>, TieBreaker>
+    where
+        Item<S>: PartialOrd,
+    {
+        self.max_by(ByPartialOrd(policy))
+    }
+
+    /// Compare heap items using the boxed comparator `cmp` and yield smallest item first
+    ///
+    /// The type of the resulting [`MergeIter`] doesn't depend on the comparator, so it may be
+    /// supplied at runtime without monomorphizing the merge for every one of them. Borrowed
+    /// `&dyn Comparator` can be passed to [`min_by`](Self::min_by) directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{
+    ///     VecStorage,
+    ///     comparators::{ByOrd, DynComparator, Reversible},
+    /// };
+    ///
+    /// let descending = true;
+    /// let cmp: DynComparator<'_, i32> = if descending {
+    ///     Box::new(ByOrd.reverse())
+    /// } else {
+    ///     Box::new(ByOrd)
+    /// };
+    /// let res = VecStorage::from_iter([vec![3, 1], vec![2]])
+    ///     .into_builder()
+    ///     .min_by_dyn(cmp)
+    ///     .build()
+    ///     .into_vec();
+    /// assert_eq!(res, vec![3, 2, 1]);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn min_by_dyn<'a>(
+        self, cmp: DynComparator<'a, Item<S>>,
+    ) -> Builder<S, DynComparator<'a, Item<S>>, TieBreaker> {
+        self.min_by(cmp)
+    }
+
+    /// Compare heap items using the boxed comparator `cmp` and yield largest item first,
+    /// see [`min_by_dyn`](Self::min_by_dyn)
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn max_by_dyn<'a>(
+        self, cmp: DynComparator<'a, Item<S>>,
+    ) -> Builder<S, MaxFirst<DynComparator<'a, Item<S>>>, TieBreaker> {
+        self.max_by(cmp)
+    }
+
+    /// Compare heap items using the stateful comparator `cmp` and yield smallest item first,
+    /// see [`ByMut`]
+    #[inline]
+    pub fn min_by_mut<C>(self, cmp: C) -> Builder<S, ByMut<C>, TieBreaker>
+    where
+        C: ComparatorMut<Item<S>>,
+    {
+        self.min_by(ByMut::new(cmp))
+    }
+
+    /// Compare heap items using the stateful comparator `cmp` and yield largest item first,
+    /// see [`ByMut`]
+    #[inline]
+    pub fn max_by_mut<C>(self, cmp: C) -> Builder<S, MaxFirst<ByMut<C>>, TieBreaker>
+    where
+        C: ComparatorMut<Item<S>>,
+    {
+        self.max_by(ByMut::new(cmp))
+    }
+
     /// Compare heap items using `func` and yield smallest item first
     #[inline]
     pub fn min_by_func<F>(self, func: F) -> Builder<S, ByFunc<F>, TieBreaker>
@@ -72,6 +282,44 @@ where
         self.max_by(ByFunc(func))
     }
 
+    /// Compare heap items using the "less than" predicate `func` and yield smallest item
+    /// first. `func(a, b)` returns `true` if `a` should be yielded before `b`, same as in
+    /// [`itertools::kmerge_by`](https://docs.rs/itertools/latest/itertools/fn.kmerge_by.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let res = VecStorage::from_iter([vec![(1, 'a'), (3, 'b')], vec![(2, 'c')]])
+    ///     .into_builder()
+    ///     .min_by_less_than(|a, b| a.0 < b.0)
+    ///     .build()
+    ///     .into_vec();
+    /// assert_eq!(res, vec![(1, 'a'), (2, 'c'), (3, 'b')]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn min_by_less_than<F>(self, func: F) -> Builder<S, ByLessThan<F>, TieBreaker>
+    where
+        F: Fn(&Item<S>, &Item<S>) -> bool,
+    {
+        self.min_by(ByLessThan(func))
+    }
+
+    /// Compare heap items using the "less than" predicate `func` and yield largest item first
+    #[inline]
+    pub fn max_by_less_than<F>(
+        self, func: F,
+    ) -> Builder<S, MaxFirst<ByLessThan<F>>, TieBreaker>
+    where
+        F: Fn(&Item<S>, &Item<S>) -> bool,
+    {
+        self.max_by(ByLessThan(func))
+    }
+
     /// Compare heap items by comparing their keys produced by `func` and yield smallest item first
     #[inline]
     pub fn min_by_key<F, K>(self, func: F) -> Builder<S, ByKey<F>, TieBreaker>
@@ -92,34 +340,658 @@ where
         self.max_by(ByKey(func))
     }
 
+    /// Compare heap items by comparing their keys produced by `func` and yield smallest item
+    /// first, like [`min_by_key`](Self::min_by_key)
+    ///
+    /// The keys of the peeked items are cached next to the heap, see [`HeapKeyStorage`]. The
+    /// storage is wrapped, so the methods of the [`MergeIter`] specific to the storage (e.g.
+    /// [`MergeIter::add_iter`]) aren't available. Replacing the comparator afterwards (e.g. by
+    /// [`min_by`](Self::min_by) or [`reverse`](Self::reverse)) stops the caching.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let records = |ts: [u64; 2]| ts.map(|ts| Box::new((ts, [0_u8; 64])));
+    /// let merged = VecStorage::from_iter([records([1, 4]), records([2, 3])])
+    ///     .into_builder()
+    ///     .min_by_heap_key(|record| record.0)
+    ///     .build();
+    /// assert!(merged.map(|record| record.0).eq([1, 2, 3, 4]));
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn min_by_heap_key<F, K>(
+        self, func: F,
+    ) -> Builder<HeapKeyStorage<S, F, K>, ByKey<F>, TieBreaker>
+    where
+        F: Fn(&Item<S>) -> K + Clone,
+        K: Ord,
+    {
+        self.min_by_key(func.clone())
+            .map_storage(|storage| HeapKeyStorage::new(storage, func, false))
+    }
+
+    /// Compare heap items by comparing their keys produced by `func` and yield largest item
+    /// first, caching the keys like [`min_by_heap_key`](Self::min_by_heap_key)
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn max_by_heap_key<F, K>(
+        self, func: F,
+    ) -> Builder<HeapKeyStorage<S, F, K>, MaxFirst<ByKey<F>>, TieBreaker>
+    where
+        F: Fn(&Item<S>) -> K + Clone,
+        K: Ord,
+    {
+        self.max_by_key(func.clone())
+            .map_storage(|storage| HeapKeyStorage::new(storage, func, true))
+    }
+
+    /// Compare heap items by comparing their keys borrowed by `func` and yield smallest item
+    /// first
+    ///
+    /// Unlike [`min_by_key`](Self::min_by_key) the key doesn't have to be cloned out of the
+    /// item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// struct Record {
+    ///     name: String,
+    ///     size: u64,
+    /// }
+    /// let rec = |name: &str, size| Record { name: name.to_owned(), size };
+    ///
+    /// let merged = VecStorage::from_iter([vec![rec("a", 3), rec("c", 1)], vec![rec("b", 2)]])
+    ///     .into_builder()
+    ///     .min_by_key_ref(|r| &r.name)
+    ///     .build();
+    /// assert!(merged.map(|r| r.size).eq([3, 2, 1]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn min_by_key_ref<F, K>(self, func: F) -> Builder<S, ByKeyRef<F>, TieBreaker>
+    where
+        F: for<'a> Fn(&'a Item<S>) -> &'a K,
+        K: Ord + ?Sized,
+    {
+        self.min_by(ByKeyRef(func))
+    }
+
+    /// Compare heap items by comparing their keys borrowed by `func` and yield largest item
+    /// first
+    #[inline]
+    pub fn max_by_key_ref<F, K>(self, func: F) -> Builder<S, MaxFirst<ByKeyRef<F>>, TieBreaker>
+    where
+        F: for<'a> Fn(&'a Item<S>) -> &'a K,
+        K: Ord + ?Sized,
+    {
+        self.max_by(ByKeyRef(func))
+    }
+
+    /// Compare heap items by comparing their keys borrowed by `key` with `comparator` and
+    /// yield smallest item first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::{VecStorage, comparators::ByLessThan};
+    ///
+    /// let ignore_case = ByLessThan::new(|a: &str, b: &str| a.to_lowercase() < b.to_lowercase());
+    /// let merged = VecStorage::from_iter([vec![("Apple", 1), ("cherry", 2)], vec![("banana", 3)]])
+    ///     .into_builder()
+    ///     .min_by_key_with(|(name, _)| *name, ignore_case)
+    ///     .build();
+    /// assert!(merged.map(|(_, id)| id).eq([1, 3, 2]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn min_by_key_with<F, K, C>(
+        self, key: F, comparator: C,
+    ) -> Builder<S, ByKeyWith<F, C>, TieBreaker>
+    where
+        F: for<'a> Fn(&'a Item<S>) -> &'a K,
+        K: ?Sized,
+        C: Comparator<K>,
+    {
+        self.min_by(ByKeyWith::new(key, comparator))
+    }
+
+    /// Compare heap items by comparing their keys borrowed by `key` with `comparator` and
+    /// yield largest item first
+    #[inline]
+    pub fn max_by_key_with<F, K, C>(
+        self, key: F, comparator: C,
+    ) -> Builder<S, MaxFirst<ByKeyWith<F, C>>, TieBreaker>
+    where
+        F: for<'a> Fn(&'a Item<S>) -> &'a K,
+        K: ?Sized,
+        C: Comparator<K>,
+    {
+        self.max_by(ByKeyWith::new(key, comparator))
+    }
+
+    /// Reverses the order of the configured comparator: min-first becomes max-first and vice
+    /// versa. The tie breaker isn't affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let descending = true;
+    /// let sources = [vec![1, 4], vec![2, 3]].map(|mut source| {
+    ///     if descending {
+    ///         source.reverse();
+    ///     }
+    ///     source
+    /// });
+    /// let builder = VecStorage::from_iter(sources)
+    ///     .into_builder()
+    ///     .min_by_key(|&item| item);
+    /// let merged = if descending {
+    ///     builder.reverse().build().into_vec()
+    /// } else {
+    ///     builder.build().into_vec()
+    /// };
+    /// assert_eq!(merged, vec![4, 3, 2, 1]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn reverse(mut self) -> Builder<S, CMP::Reversed, TieBreaker>
+    where
+        CMP: Reversible,
+    {
+        self.storage.forget_keys();
+        Builder::new(self.storage, self.comparator.reverse(), self.tie_breaker)
+    }
+
+    /// Discards the items preceding `bound` according to the comparator from every iterator,
+    /// without building the heap.
+    ///
+    /// For merges of sorted iterators, see [`MergeIter::skip_to`]; use
+    /// [`end_before`](Self::end_before) to set the end of the range. If the iterators implement
+    /// [`SeekableSource`], [`seek_start_at`](Self::seek_start_at) skips the items without
+    /// visiting each of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let segments = [vec![1, 4, 7, 10], vec![2, 5, 8, 11], vec![3, 6, 9]];
+    /// let range = VecStorage::from_iter(segments)
+    ///     .into_builder()
+    ///     .start_at(&4)
+    ///     .end_before(8);
+    /// assert!(range.eq([4, 5, 6, 7]));
+    /// # }
+    /// ```
+    pub fn start_at(mut self, bound: &Item<S>) -> Self
+    where
+        CMP: Comparator<Item<S>>,
+    {
+        let comparator = &self.comparator;
+        self.storage.retain(|peek_iter| {
+            while precedes(comparator, &peek_iter.item, bound) {
+                match peek_iter.iter.next() {
+                    Some(item) => peek_iter.item = item,
+                    None => return false,
+                }
+            }
+            true
+        });
+        self
+    }
+
+    /// Discards the items that precede `bound` from every iterator using
+    /// [`SeekableSource::seek`], without building the heap, see [`start_at`](Self::start_at)
+    /// and [`MergeIter::seek_to`].
+    pub fn seek_start_at(mut self, bound: &Item<S>) -> Self
+    where
+        CMP: Comparator<Item<S>>,
+        Iter<S>: SeekableSource,
+    {
+        let comparator = &self.comparator;
+        self.storage.retain(|peek_iter| {
+            if !precedes(comparator, &peek_iter.item, bound) {
+                return true;
+            }
+            match peek_iter
+                .iter
+                .seek(|item| precedes(comparator, item, bound))
+            {
+                Some(item) => {
+                    peek_iter.item = item;
+                    true
+                }
+                None => false,
+            }
+        });
+        self
+    }
+
     /// If items are equal - compare them again using `tie_breaker`, yielding smallest item first
     #[inline]
     pub fn tie_breaker<TB: Comparator<Item<S>>>(self, tie_breaker: TB) -> Builder<S, CMP, TB> {
         Builder::new(self.storage, self.comparator, tie_breaker)
     }
+
+    /// If items are equal - compare them again using `func`, yielding smallest item first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![(1, 'b'), (2, 'a')], vec![(1, 'a'), (2, 'b')]])
+    ///     .into_builder()
+    ///     .min_by_key(|item| item.0)
+    ///     .tie_breaker_func(|a, b| b.1.cmp(&a.1))
+    ///     .build();
+    /// assert!(merged.eq([(1, 'b'), (1, 'a'), (2, 'b'), (2, 'a')]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn tie_breaker_func<F>(self, func: F) -> Builder<S, CMP, ByFunc<F>>
+    where
+        F: Fn(&Item<S>, &Item<S>) -> Ordering,
+    {
+        self.tie_breaker(ByFunc(func))
+    }
 }
 
-impl<S, CMP, TieBreaker> Builder<S, CMP, TieBreaker>
-where
-    S: Storage,
-    CMP: Comparator<Item<S>>,
-    TieBreaker: Comparator<Item<S>>,
-{
+impl<S: Storage, CMP, TieBreaker> Builder<S, CMP, TieBreaker> {
     /// Builds the [`MergeIter`] using specified comparator and tie breaker.
     ///
     /// Getting a compiler error
     /// ```custom
-    /// the method `build` exists for struct `Builder<...>`,
-    /// but its trait bounds were not satisfied
+    /// `ByOrd` can't compare items of type `...`
     /// ```
+    /// (or `the trait bound ...: Ord is not satisfied` on older compilers)
     /// means that the item type does not implement [`Ord`].
     /// Either implement it for your type or specify another way to compare items by using builder
     /// methods documented above.
     #[inline]
-    pub fn build(self) -> MergeIter<S, Chain<CMP, TieBreaker>> {
+    pub fn build(self) -> MergeIter<S, Chain<CMP, TieBreaker>>
+    where
+        CMP: Comparator<Item<S>>,
+        TieBreaker: Comparator<Item<S>>,
+    {
         MergeIter(Heap::new(
             Chain::new(self.comparator, self.tie_breaker),
             self.storage,
         ))
     }
+
+    /// Preset for the consumers that process the items one by one as they arrive, e.g. the
+    /// ones that stop after a few items.
+    ///
+    /// Keeps the configured comparator, yields equal items in the order of insertion of their
+    /// sources and builds the heap lazily: building is O(1), the heap is built when the first
+    /// item is requested. Same as `.tie_breaker(InsertionOrder).build_lazy()`, see
+    /// [`build_lazy`](Self::build_lazy).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let mut merged = VecStorage::from_iter([vec![(1, 'a'), (3, 'a')], vec![(1, 'b')]])
+    ///     .into_builder()
+    ///     .min_by_key(|item| item.0)
+    ///     .latency_optimized();
+    /// assert!(!merged.is_forced());
+    /// assert_eq!(merged.next(), Some((1, 'a')));
+    /// assert!(merged.eq([(1, 'b'), (3, 'a')]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn latency_optimized(self) -> LazyMergeIter<S, Chain<CMP, tie_breaker::InsertionOrder>>
+    where
+        CMP: Comparator<Item<S>>,
+    {
+        self.tie_breaker(tie_breaker::InsertionOrder).build_lazy()
+    }
+}
+
+impl<S, TieBreaker> Builder<S, ByOrd, TieBreaker>
+where
+    S: Storage,
+    Item<S>: BranchlessCmp,
+{
+    /// Preset for the consumers that drain the whole merge (e.g. via
+    /// [`MergeIter::into_vec`]) of the integers or byte arrays, and don't care about the order
+    /// of equal items.
+    ///
+    /// Compares the items with [`ByOrdBranchless`] instead of [`ByOrd`], skips tie breaking
+    /// (saving a comparison on every tie) and builds the heap right away. Same as
+    /// `.min_by(ByOrdBranchless).tie_breaker(Unspecified).build()`. For the other comparators
+    /// set these options individually, e.g. the [`Unspecified`](tie_breaker::Unspecified) tie
+    /// breaker and the keys cached by [`min_by_heap_key`](Self::min_by_heap_key).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![1_u64, 3], vec![2, 3]])
+    ///     .into_builder()
+    ///     .throughput_optimized();
+    /// assert_eq!(merged.into_vec(), vec![1, 2, 3, 3]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn throughput_optimized(
+        self,
+    ) -> MergeIter<S, Chain<ByOrdBranchless, tie_breaker::Unspecified>> {
+        self.min_by(ByOrdBranchless)
+            .tie_breaker(tie_breaker::Unspecified)
+            .build()
+    }
+}
+
+impl<S, CMP, TieBreaker> Builder<S, CMP, TieBreaker>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+    TieBreaker: Comparator<Item<S>>,
+{
+    /// Builds the iterator that ignores the comparator and yields one item from every
+    /// component iterator in turn (in the order of insertion), until all of them are
+    /// exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let queues = [vec!["a1", "a2", "a3"], vec!["b1"], vec!["c1", "c2"]];
+    /// let jobs = VecStorage::from_iter(queues).into_builder().interleave();
+    /// assert!(jobs.eq(["a1", "b1", "c1", "a2", "c2", "a3"]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn interleave(mut self) -> Interleave<S> {
+        self.storage.forget_keys();
+        Interleave {
+            iter: MergeIter(Heap::new_unordered(tie_breaker::Unspecified, self.storage)),
+            next: 0,
+        }
+    }
+
+    /// Builds the iterator that ignores the comparator and yields up to `weight(source_index)`
+    /// items from every component iterator in turn (in the order of insertion), until all of
+    /// them are exhausted. Weights of 0 are treated as 1.
+    ///
+    /// Weighted round-robin, the share of every source among the yielded items is
+    /// proportional to its weight (while it has the items). `weight` is called at the start
+    /// of every turn of the source, so the weights may change during the iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let queues = [vec!["a1", "a2", "a3", "a4"], vec!["b1", "b2", "b3"]];
+    /// let weights = [2, 1];
+    /// let jobs = VecStorage::from_iter(queues)
+    ///     .into_builder()
+    ///     .interleave_weighted(|source| weights[source]);
+    /// assert!(jobs.eq(["a1", "a2", "b1", "a3", "a4", "b2", "b3"]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn interleave_weighted<F>(self, weight: F) -> WeightedInterleave<S, F>
+    where
+        F: FnMut(usize) -> usize,
+    {
+        WeightedInterleave {
+            iter: self.interleave(),
+            weight,
+            quota: 0,
+        }
+    }
+
+    /// Builds the [`MergeIter`] in O(1), skipping the construction of the heap, for the
+    /// iterators that were inserted in the order of their first items (e.g. the runs produced
+    /// by a previous merge pass).
+    ///
+    /// The order is checked only in debug builds. If the iterators are out of order the items
+    /// are yielded in the wrong order, see [`Comparator`].
+    ///
+    /// # Panics
+    /// Panics in debug builds if the iterators weren't inserted in the order of their first
+    /// items (according to the comparator and the tie breaker).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let runs = [vec![1, 5], vec![2, 3], vec![2, 4], vec![6]];
+    /// let merged = VecStorage::from_iter(runs).into_builder().build_presorted();
+    /// assert_eq!(merged.into_vec(), vec![1, 2, 2, 3, 4, 5, 6]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn build_presorted(self) -> MergeIter<S, Chain<CMP, TieBreaker>> {
+        let heap = Heap::new_unordered(
+            Chain::new(self.comparator, self.tie_breaker),
+            self.storage,
+        );
+        debug_assert!(heap.is_sorted(), "iterators aren't sorted by their first items");
+        MergeIter(heap)
+    }
+
+    /// Builds the [`MergeIter`] in O(1), deferring the construction of the heap (O(n), where
+    /// n is the number of component iterators) until the first item is requested.
+    ///
+    /// Useful if the merge may be discarded without yielding a single item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let mut merged = VecStorage::from_iter([vec![3, 4], vec![1, 2]])
+    ///     .into_builder()
+    ///     .build_lazy();
+    /// assert_eq!(merged.size_hint(), (4, Some(4)));
+    /// assert!(!merged.is_forced());
+    /// assert_eq!(merged.peek(), Some(&1));
+    /// assert!(merged.eq([1, 2, 3, 4]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn build_lazy(self) -> LazyMergeIter<S, Chain<CMP, TieBreaker>> {
+        LazyMergeIter {
+            iter: MergeIter(Heap::new_unordered(
+                Chain::new(self.comparator, self.tie_breaker),
+                self.storage,
+            )),
+            heapified: false,
+        }
+    }
+
+    /// Builds the [`MergeIter`] that yields at most `n` items and then drops the component
+    /// iterators, see [`MergeIter::limit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let top = VecStorage::from_iter([vec![5, 3, 1], vec![6, 4, 2]])
+    ///     .into_builder()
+    ///     .max_by_key(|&item| item)
+    ///     .limit(3);
+    /// assert!(top.eq([6, 5, 4]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn limit(self, n: usize) -> Limit<S, Chain<CMP, TieBreaker>> {
+        self.build().limit(n)
+    }
+
+    /// Builds the [`MergeIter`] that verifies that every source yields its items in order,
+    /// see [`MergeIter::checked`].
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![3, 2, 1], vec![6, 5, 4]])
+    ///     .into_builder()
+    ///     .max()
+    ///     .checked();
+    /// assert!(merged.map(Result::unwrap).eq([6, 5, 4, 3, 2, 1]));
+    ///
+    /// let merged = VecStorage::from_iter([vec![1, 2], vec![3]]).into_builder().max().checked();
+    /// // panics: source 0 isn't sorted in descending order
+    /// merged.map(Result::unwrap).for_each(drop);
+    /// # }
+    /// # #[cfg(not(feature = "alloc"))]
+    /// # panic!();
+    /// ```
+    #[inline]
+    pub fn checked(self) -> Checked<S, Chain<CMP, TieBreaker>> {
+        self.build().checked()
+    }
+
+    /// Builds the [`MergeIter`] that yields the items preceding `bound` and then drops the
+    /// component iterators, see [`MergeIter::end_before`].
+    #[inline]
+    pub fn end_before(self, bound: Item<S>) -> EndBefore<S, Chain<CMP, TieBreaker>, Item<S>> {
+        self.build().end_before(bound)
+    }
+
+    /// Builds the [`MergeIter`] that yields only the first item of every run of equivalent
+    /// items, like [`MergeIter::dedup`].
+    ///
+    /// Equivalence is defined by the comparator, the tie breaker is ignored (but it decides
+    /// which of the equivalent items is yielded).
+    ///
+    /// Deduplication is a part of the comparator type: the duplicates are dropped right after
+    /// their item is popped, so the result is still a [`MergeIter`] with all of its methods
+    /// and the peeked item is never a duplicate of the yielded one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let mut merged =
+    ///     VecStorage::from_iter([vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (3, 'b')]])
+    ///         .into_builder()
+    ///         .min_by_key(|item| item.0)
+    ///         .dedup();
+    /// assert_eq!(merged.next(), Some((1, 'a')));
+    /// assert_eq!(merged.peek(), Some(&(2, 'a')));
+    /// assert_eq!(merged.into_vec(), vec![(2, 'a'), (3, 'b')]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn dedup(self) -> MergeIter<S, Deduplicated<Chain<CMP, TieBreaker>>> {
+        MergeIter(Heap::new(
+            Deduplicated(Chain::new(self.comparator, self.tie_breaker)),
+            self.storage,
+        ))
+    }
+
+    /// Builds the [`MergeIter`] that yields only the first item of every run of items with
+    /// equal keys, like [`MergeIter::dedup_by_key`].
+    ///
+    /// The key doesn't have to match the order of the merge, see [`dedup`](Self::dedup) for
+    /// how the duplicates are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![1, 12, 25], vec![3, 14, 21]])
+    ///     .into_builder()
+    ///     .dedup_by_key(|item| item / 10);
+    /// assert!(merged.eq([1, 12, 21]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn dedup_by_key<K, F>(
+        self, key: F,
+    ) -> MergeIter<S, DeduplicatedByKey<Chain<CMP, TieBreaker>, F>>
+    where
+        F: Fn(&Item<S>) -> K,
+        K: PartialEq,
+    {
+        MergeIter(Heap::new(
+            DeduplicatedByKey::new(Chain::new(self.comparator, self.tie_breaker), key),
+            self.storage,
+        ))
+    }
+}
+
+impl<S, CMP, TieBreaker, IT, M> Builder<S, CMP, TieBreaker>
+where
+    S: Storage<IT = WithMeta<IT, M>>,
+    CMP: Comparator<IT::Item>,
+    TieBreaker: Comparator<IT::Item>,
+    IT: Iterator,
+{
+    /// Builds the [`MergeIter`] that yields `(label, item)` pairs, see
+    /// [`MergeIter::meta_tagged`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")]
+    /// # {
+    /// use iter_merge::VecStorage;
+    ///
+    /// let tenants = [("acme", vec![1, 4]), ("globex", vec![2, 3])];
+    /// let merged = VecStorage::from_labeled(tenants).into_builder().labeled();
+    /// assert!(merged.eq([("acme", 1), ("globex", 2), ("globex", 3), ("acme", 4)]));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn labeled(self) -> MetaTagged<S, Chain<CMP, TieBreaker>> {
+        self.build().meta_tagged()
+    }
 }
@@ -1,8 +1,9 @@
 //! Iterators over the iterators within the [`MergeIter`](crate::MergeIter)
-use core::iter::FusedIterator;
+use core::iter::{Chain, FusedIterator, Once, once};
 
 use super::Heap;
 use crate::{
+    MergeIter,
     comparators::Comparator,
     internal::{Item, Iter, PeekIter},
     storage::Storage,
@@ -76,6 +77,48 @@ where
 {
 }
 
+/// Iterator, yielding the remaining sources of the [`MergeIter`] in order of their peeked
+/// items, with the peeked item re-attached to the front of every source
+///
+/// Constructed by [`MergeIter::into_sources`]
+#[derive(Debug)]
+pub struct IntoSources<S, CMP>(pub(crate) MergeIter<S, CMP>);
+
+impl<S, CMP> Iterator for IntoSources<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+    type Item = Chain<Once<Item<S>>, Iter<S>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.0).0.pop_front_iter().map(|it| {
+            let PeekIter { item, iter } = it;
+            once(item).chain(iter)
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.0).0.storage.len();
+        (len, Some(len))
+    }
+}
+
+impl<S, CMP> ExactSizeIterator for IntoSources<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
+impl<S, CMP> FusedIterator for IntoSources<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>>,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use core::{array, pin::pin};
@@ -117,4 +160,19 @@ mod tests {
         assert!(iter.eq([2]));
         assert!(iters_iter.next().is_none());
     }
+
+    #[test]
+    fn into_sources() {
+        let s = ArrayStorage::from_arr([[5, 7], [2, 6], [3, 4], [0, 2]]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.nth(1), Some(2));
+        let mut sources = m.into_sources();
+        assert_eq!(sources.len(), 4);
+        assert!(sources.next().unwrap().eq([2]));
+        assert!(sources.next().unwrap().eq([3, 4]));
+        assert!(sources.next().unwrap().eq([5, 7]));
+        assert!(sources.next().unwrap().eq([6]));
+        assert!(sources.next().is_none());
+    }
 }
@@ -0,0 +1,129 @@
+//! User metadata attached to the sources of the [`MergeIter`](crate::MergeIter).
+//!
+//! Wrap every iterator in [`WithMeta`] to keep a value (e.g. a file path or a shard id)
+//! alongside the source, without wrapping every item. The metadata is retrievable during the
+//! merge via [`MergeIter::peek_meta`](crate::MergeIter::peek_meta),
+//! [`MergeIter::meta_tagged`](crate::MergeIter::meta_tagged) and
+//! [`MergeIter::sources`](crate::MergeIter::sources).
+//!
+//! Labeled sources can be created from `(label, iter)` pairs via [`From`], and
+//! [`Builder::labeled`](crate::merge_iter::Builder::labeled) yields the items together with
+//! their labels.
+
+use core::iter::FusedIterator;
+
+use crate::seek::SeekableSource;
+
+/// Iterator with attached metadata, yielding the items of the inner iterator.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iter_merge::{merge, meta::WithMeta};
+///
+/// let mut merged = merge([
+///     WithMeta::new([1, 4], "a.log"),
+///     WithMeta::new([2, 3], "b.log"),
+/// ]);
+/// assert_eq!(merged.peek_meta(), Some(&"a.log"));
+/// assert!(merged.meta_tagged().eq([("a.log", 1), ("b.log", 2), ("b.log", 3), ("a.log", 4)]));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WithMeta<IT, M> {
+    /// Inner iterator
+    pub iter: IT,
+    /// Metadata of the iterator
+    pub meta: M,
+}
+
+impl<IT: Iterator, M> WithMeta<IT, M> {
+    /// Attaches `meta` to the `iter`
+    #[inline]
+    pub fn new<I: IntoIterator<IntoIter = IT>>(iter: I, meta: M) -> Self {
+        Self {
+            iter: iter.into_iter(),
+            meta,
+        }
+    }
+}
+
+impl<I: IntoIterator, M> From<(M, I)> for WithMeta<I::IntoIter, M> {
+    /// Converts the `(label, iter)` pair into the source labeled with `label`
+    #[inline]
+    fn from((meta, iter): (M, I)) -> Self {
+        Self::new(iter, meta)
+    }
+}
+
+impl<IT: Iterator, M> Iterator for WithMeta<IT, M> {
+    type Item = IT::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<IT: ExactSizeIterator, M> ExactSizeIterator for WithMeta<IT, M> {}
+
+impl<IT: FusedIterator, M> FusedIterator for WithMeta<IT, M> {}
+
+impl<IT: SeekableSource, M> SeekableSource for WithMeta<IT, M> {
+    #[inline]
+    fn seek<F>(&mut self, before: F) -> Option<Self::Item>
+    where
+        F: FnMut(&Self::Item) -> bool,
+    {
+        self.iter.seek(before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::pin::pin;
+
+    use super::WithMeta;
+    use crate::ArrayStorage;
+
+    #[test]
+    fn meta() {
+        let s = ArrayStorage::from_arr([
+            WithMeta::new([1, 4, 5], 'a'),
+            WithMeta::new([2, 3, 6], 'b'),
+        ]);
+        let s = pin!(s);
+        let mut m = s.build();
+        assert_eq!(m.next(), Some(1));
+        assert_eq!(m.peek_meta(), Some(&'b'));
+        let metas: [char; 2] = core::array::from_fn(|i| m.sources().nth(i).unwrap().iter.meta);
+        assert_eq!(metas, ['b', 'a']);
+        assert_eq!(m.skip_to(&4), 2);
+        assert_eq!(m.peek_meta(), Some(&'a'));
+        assert!(m.meta_tagged().eq([('a', 4), ('a', 5), ('b', 6)]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn labeled() {
+        use alloc::vec;
+
+        use crate::VecStorage;
+
+        let mut storage = VecStorage::from_labeled([(1_u8, vec![(1, 'a'), (3, 'a')])]);
+        storage.push(WithMeta::from((0, vec![])));
+        storage.push(WithMeta::from((2, vec![(2, 'b'), (3, 'b')])));
+        let m = storage.into_builder().min_by_key(|item| item.0).labeled();
+        assert!(m.eq([(1, (1, 'a')), (2, (2, 'b')), (1, (3, 'a')), (2, (3, 'b'))]));
+
+        let s = ArrayStorage::from_arr([('x', [2]), ('y', [1])].map(WithMeta::from));
+        let s = pin!(s);
+        assert!(s.into_builder().labeled().eq([('y', 1), ('x', 2)]));
+    }
+}
@@ -0,0 +1,98 @@
+//! Nesting of the merges without recursive teardown.
+//!
+//! Hierarchical merging builds merges of merges, and dropping the top merge drops the whole
+//! tree recursively, one stack frame per level. Wrap the nested merges (and the rest of the
+//! sources of the same merge) in [`Nested`]: when a [`VecStorage`](crate::VecStorage) drops
+//! its sources, the [`Nested`] ones are dropped one after another, so the tree is torn down
+//! with constant stack usage regardless of its depth.
+
+use core::{
+    fmt::{self, Debug},
+    mem::{self, ManuallyDrop},
+};
+use std::boxed::Box;
+
+use crate::storage::teardown::{self, Deferred};
+
+/// Boxed source, dropped without recursion when it's owned by a merge that's being dropped.
+///
+/// Only the [`Nested`] sources stored directly in the merge (or inside of a source of the
+/// merge) are deferred. A [`Nested`] stored in a separate allocation, e.g. in a [`Vec`] of the
+/// source, is dropped in place.
+///
+/// [`Vec`]: std::vec::Vec
+///
+/// # Example
+/// ```
+/// use iter_merge::{VecStorage, nested::Nested};
+///
+/// let mut tree = Nested::new(vec![100_000]);
+/// for level in (0..100_000).rev() {
+///     let merge = VecStorage::from_iter([tree, Nested::new(vec![level])]).build();
+///     tree = Nested::new(merge);
+/// }
+/// assert_eq!(tree.next(), Some(0));
+/// // Dropped level by level, without overflowing the stack
+/// drop(tree);
+/// ```
+pub struct Nested<'a, T> {
+    iter: ManuallyDrop<Box<dyn Iterator<Item = T> + 'a>>,
+    drop: unsafe fn(*mut ()),
+}
+
+/// Drops the box of `I`
+///
+/// # Safety
+///
+/// `ptr` has to come from `Box::<I>::into_raw`.
+unsafe fn drop_boxed<I>(ptr: *mut ()) {
+    // SAFETY: the caller guarantees that it's a boxed I
+    drop(unsafe { Box::from_raw(ptr.cast::<I>()) });
+}
+
+impl<'a, T> Nested<'a, T> {
+    /// Boxes the `iter`
+    #[inline]
+    pub fn new<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: 'a,
+    {
+        Self {
+            iter: ManuallyDrop::new(Box::new(iter.into_iter())),
+            drop: drop_boxed::<I::IntoIter>,
+        }
+    }
+}
+
+impl<T> Drop for Nested<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the box isn't used after the drop
+        let iter = unsafe { ManuallyDrop::take(&mut self.iter) };
+        let size = mem::size_of_val(&*iter);
+        let ptr = Box::into_raw(iter).cast::<()>();
+        // SAFETY: `self.drop` drops the box of the iterator at `ptr`
+        let deferred = unsafe { Deferred::new(ptr, size, self.drop) };
+        teardown::defer(self, deferred);
+    }
+}
+
+impl<T> Iterator for Nested<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> Debug for Nested<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Nested").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,294 @@
+//! Radix-partitioned merge strategy for integer keys.
+//!
+//! [`RadixMerge`] doesn't keep a binary heap of all sources. Instead, sources are partitioned
+//! into buckets by the highest bit in which the key of their peeked item differs from the
+//! last yielded key. Only the lowest non-empty bucket is ever scanned, and the sources in it
+//! are redistributed into even lower buckets, so every source moves through at most 65
+//! buckets between the yielded keys that differ in their top bits.
+//!
+//! The per-item cost depends on the distribution of the keys rather than on the number of
+//! sources: it's low when the keys of the sources are clustered, and high when every new
+//! key differs from the last one in its top bits. On uniformly random [`u64`] keys the
+//! default binary heap of [`MergeIter`](crate::MergeIter) is faster (see the
+//! `Radix partitioned` group of the benchmarks), so measure it on your data before
+//! switching.
+//!
+//! The strategy relies on keys being monotonic: every source must be sorted in ascending
+//! order of keys. If that's not the case the [`RadixMerge`] remains safe to use, but items
+//! are yielded in unspecified order.
+//!
+//! Constructed by [`VecStorage::radix_partitioned`](crate::VecStorage::radix_partitioned) and
+//! [`VecStorage::radix_partitioned_by_key`](crate::VecStorage::radix_partitioned_by_key).
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, iter::FusedIterator, mem};
+
+use crate::internal::PeekIter;
+
+/// Number of buckets: one for the keys equal to the last yielded one, and one for every
+/// possible highest differing bit of a [`u64`]
+const BUCKETS: usize = 65;
+
+/// Keys that can be used by the [`RadixMerge`]
+///
+/// The mapping to [`u64`] must be monotonic: `a < b` implies
+/// `a.radix_key() <= b.radix_key()`.
+pub trait RadixKey {
+    /// Maps the key to [`u64`], preserving its order
+    fn radix_key(&self) -> u64;
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            #[inline]
+            fn radix_key(&self) -> u64 {
+                *self as u64
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_radix_key_signed {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            #[inline]
+            fn radix_key(&self) -> u64 {
+                // Sign-extend, then flip the sign bit, so negative numbers come first
+                (*self as i64 as u64) ^ (1 << 63)
+            }
+        }
+    )*};
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, usize);
+impl_radix_key_signed!(i8, i16, i32, i64, isize);
+
+impl RadixKey for char {
+    #[inline]
+    fn radix_key(&self) -> u64 {
+        u64::from(*self)
+    }
+}
+
+impl RadixKey for bool {
+    #[inline]
+    fn radix_key(&self) -> u64 {
+        u64::from(*self)
+    }
+}
+
+impl<T: RadixKey + ?Sized> RadixKey for &T {
+    #[inline]
+    fn radix_key(&self) -> u64 {
+        (**self).radix_key()
+    }
+}
+
+/// Merging iterator that partitions its sources by the key of their peeked item,
+/// see the [module-level documentation](self)
+///
+/// Equal keys are yielded in unspecified order.
+pub struct RadixMerge<IT: Iterator, F> {
+    // Sources never move, buckets hold `(key, index)` pairs pointing into this vec.
+    // Exhausted sources are replaced with None
+    sources: Vec<Option<PeekIter<IT>>>,
+    buckets: [Vec<(u64, usize)>; BUCKETS],
+    // Bit N is set if the bucket N is not empty
+    occupied: u128,
+    // Key of the last item yielded from the lowest bucket
+    last: u64,
+    key: F,
+}
+
+impl<IT, F, K> RadixMerge<IT, F>
+where
+    IT: Iterator,
+    F: Fn(&IT::Item) -> K,
+    K: RadixKey,
+{
+    pub(crate) fn new(peek_iters: Vec<PeekIter<IT>>, key: F) -> Self {
+        let mut res = Self {
+            sources: Vec::with_capacity(peek_iters.len()),
+            buckets: core::array::from_fn(|_| Vec::new()),
+            occupied: 0,
+            last: 0,
+            key,
+        };
+        for (idx, peek_iter) in peek_iters.into_iter().enumerate() {
+            let key = (res.key)(&peek_iter.item).radix_key();
+            res.sources.push(Some(peek_iter));
+            res.push(key, idx);
+        }
+        res
+    }
+
+    #[inline]
+    fn push(&mut self, key: u64, idx: usize) {
+        // Keys smaller than the last one (from unsorted sources) are yielded right away
+        let bucket = if key < self.last {
+            0
+        } else {
+            (u64::BITS - (key ^ self.last).leading_zeros()) as usize
+        };
+        self.buckets[bucket].push((key, idx));
+        self.occupied |= 1 << bucket;
+    }
+
+    /// Makes sure that the lowest bucket contains the sources with the smallest key.
+    /// Returns `false` if all of the sources are exhausted.
+    #[inline]
+    fn refill_lowest(&mut self) -> bool {
+        if self.occupied & 1 != 0 {
+            return true;
+        }
+        if self.occupied == 0 {
+            return false;
+        }
+        self.redistribute(self.occupied.trailing_zeros() as usize);
+        true
+    }
+
+    /// Moves the sources from the `bucket` into the lower buckets, updating the last key
+    fn redistribute(&mut self, bucket: usize) {
+        let mut entries = mem::take(&mut self.buckets[bucket]);
+        self.occupied &= !(1 << bucket);
+        self.last = entries
+            .iter()
+            .map(|&(key, _)| key)
+            .min()
+            .unwrap_or(self.last);
+        // All of the entries share the bits above the `bucket` with the new `last`, and
+        // differ from it in lower bits, so they are moved into the lower buckets
+        for (key, idx) in entries.drain(..) {
+            self.push(key, idx);
+        }
+        // Keep the allocation around
+        self.buckets[bucket] = entries;
+    }
+
+    /// Returns a reference to the next item, without advancing the iterator.
+    pub fn peek(&mut self) -> Option<&IT::Item> {
+        if !self.refill_lowest() {
+            return None;
+        }
+        let &(_, idx) = self.buckets[0].last()?;
+        self.sources[idx]
+            .as_ref()
+            .map(|peek_iter| &peek_iter.item)
+    }
+}
+
+impl<IT, F, K> Iterator for RadixMerge<IT, F>
+where
+    IT: Iterator,
+    F: Fn(&IT::Item) -> K,
+    K: RadixKey,
+{
+    type Item = IT::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.refill_lowest() {
+            return None;
+        }
+        let lowest = &mut self.buckets[0];
+        let (_, idx) = lowest.pop()?;
+        if lowest.is_empty() {
+            self.occupied &= !1;
+        }
+        let source = &mut self.sources[idx];
+        let peek_iter = source.as_mut()?;
+        if let Some(item) = peek_iter.advance() {
+            let key = (self.key)(&peek_iter.item).radix_key();
+            self.push(key, idx);
+            Some(item)
+        } else {
+            source.take().map(|peek_iter| peek_iter.item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let mut min = 0_usize;
+        let mut max = Some(0_usize);
+        for peek_iter in self.sources.iter().flatten() {
+            let (it_min, it_max) = peek_iter.iter.size_hint();
+            min = min.saturating_add(it_min).saturating_add(1);
+            max = max
+                .zip(it_max)
+                .and_then(|(max, it_max)| max.checked_add(it_max)?.checked_add(1));
+        }
+        (min, max)
+    }
+}
+
+impl<IT, F, K> FusedIterator for RadixMerge<IT, F>
+where
+    IT: Iterator,
+    F: Fn(&IT::Item) -> K,
+    K: RadixKey,
+{
+}
+
+impl<IT, F> Debug for RadixMerge<IT, F>
+where
+    IT: Iterator,
+    PeekIter<IT>: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RadixMerge")
+            .field("last", &self.last)
+            .field("buckets", &self.buckets)
+            .field("sources", &self.sources)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::VecStorage;
+
+    #[test]
+    fn matches_heap_merge() {
+        let sources: Vec<Vec<u64>> = (0..50_u64)
+            .map(|n| {
+                (0..n % 7)
+                    .map(|i| (n * 0x9E37_79B9 + i * 0x1_0000_0001) % 0x1_0000_0000_0000)
+                    .scan(0, |acc, step| {
+                        *acc += step;
+                        Some(*acc)
+                    })
+                    .collect()
+            })
+            .collect();
+        let expected = VecStorage::from_iter(sources.clone()).build().into_vec();
+        let radix = VecStorage::from_iter(sources).radix_partitioned();
+        assert_eq!(radix.size_hint(), (expected.len(), Some(expected.len())));
+        assert_eq!(radix.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn signed_keys() {
+        let radix = VecStorage::from_iter([vec![-5_i32, 0, 7], vec![i32::MIN, -1, i32::MAX]])
+            .radix_partitioned();
+        let expected = [i32::MIN, -5, -1, 0, 7, i32::MAX];
+        assert_eq!(radix.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn by_key() {
+        let sources = [vec![(1_u8, 'a'), (3, 'b')], vec![], vec![(2, 'c')]];
+        let mut radix = VecStorage::from_iter(sources).radix_partitioned_by_key(|item| item.0);
+        assert_eq!(radix.peek(), Some(&(1, 'a')));
+        assert_eq!(radix.collect::<Vec<_>>(), [(1, 'a'), (2, 'c'), (3, 'b')]);
+    }
+
+    #[test]
+    fn unsorted_sources() {
+        let radix = VecStorage::from_iter([vec![5_u8, 1, 9], vec![3, 2]]).radix_partitioned();
+        let mut items = radix.collect::<Vec<_>>();
+        items.sort_unstable();
+        assert_eq!(items, [1, 2, 3, 5, 9]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Sources that can skip items faster than one by one.
+//!
+//! [`MergeIter::seek_to`](crate::MergeIter::seek_to) and
+//! [`Builder::seek_start_at`](crate::merge_iter::Builder::seek_start_at) fast-forward every
+//! component iterator via [`SeekableSource::seek`], turning skipping over many items into, for
+//! example, a binary search over a slice.
+
+use core::{ops::Range, slice};
+
+/// Iterator over sorted items, that can skip a prefix of its items without yielding them.
+///
+/// The prefix is described by a predicate (`before`), which is called with the items in the
+/// order of the iterator: it returns `true` for the items to skip, and `false` for the first
+/// item to keep and every item after it. The merge derives it from its comparator, so the
+/// items may be sorted in any order the comparator describes.
+///
+/// # Example
+/// ```
+/// use iter_merge::seek::SeekableSource;
+///
+/// let mut iter = [1, 3, 5, 7].iter();
+/// assert_eq!(iter.seek(|&&item| item < 4), Some(&5));
+/// assert_eq!(iter.next(), Some(&7));
+/// ```
+pub trait SeekableSource: Iterator {
+    /// Skips all of the items that satisfy `before`, returning the first item that doesn't.
+    ///
+    /// If `before` doesn't hold for a prefix of the items (i.e. the iterator is not sorted),
+    /// an unspecified number of items is skipped.
+    fn seek<F>(&mut self, before: F) -> Option<Self::Item>
+    where
+        F: FnMut(&Self::Item) -> bool;
+}
+
+impl<'a, T> SeekableSource for slice::Iter<'a, T> {
+    fn seek<F>(&mut self, mut before: F) -> Option<&'a T>
+    where
+        F: FnMut(&&'a T) -> bool,
+    {
+        // `partition_point` would only lend the items for a shorter lifetime than `'a`
+        let slice = self.as_slice();
+        let (mut lo, mut hi) = (0, slice.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if before(&&slice[mid]) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        self.nth(lo)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> SeekableSource for alloc::vec::IntoIter<T> {
+    fn seek<F>(&mut self, before: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let skip = self.as_slice().partition_point(before);
+        self.nth(skip)
+    }
+}
+
+macro_rules! impl_seekable_range {
+    ($($t:ty),*) => {$(
+        impl SeekableSource for Range<$t> {
+            fn seek<F>(&mut self, mut before: F) -> Option<$t>
+            where
+                F: FnMut(&$t) -> bool,
+            {
+                let (mut lo, mut hi) = (self.start, self.end);
+                while lo < hi {
+                    // Floor of the average, can't overflow
+                    let mid = (lo & hi) + ((lo ^ hi) >> 1);
+                    if before(&mid) {
+                        lo = mid + 1;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                self.start = lo;
+                self.next()
+            }
+        }
+    )*};
+}
+
+impl_seekable_range!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::SeekableSource;
+
+    #[test]
+    fn slice() {
+        let mut iter = [1, 2, 2, 3, 5].iter();
+        assert_eq!(iter.seek(|&&item| item < 2), Some(&2));
+        assert_eq!(iter.seek(|&&item| item < 2), Some(&2));
+        assert_eq!(iter.seek(|&&item| item < 4), Some(&5));
+        assert_eq!(iter.seek(|&&item| item < 0), None);
+
+        // Descending order
+        let mut iter = [9, 7, 5, 3].iter();
+        assert_eq!(iter.seek(|&&item| item > 6), Some(&5));
+    }
+
+    #[test]
+    fn range() {
+        let mut iter = 0_u32..10;
+        assert_eq!(iter.seek(|&item| item < 4), Some(4));
+        assert_eq!(iter.seek(|&item| item < 2), Some(5));
+        assert_eq!(iter.seek(|&item| item < 20), None);
+        assert!(iter.is_empty());
+
+        let mut iter = i8::MIN..i8::MAX;
+        assert_eq!(iter.seek(|&item| item < 100), Some(100));
+        assert_eq!(iter.seek(|&item| item < 120), Some(120));
+        assert_eq!(iter.seek(|_| true), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn vec() {
+        use alloc::{boxed::Box, vec};
+        let mut iter = vec![Box::new(1), Box::new(4), Box::new(9)].into_iter();
+        assert_eq!(iter.seek(|item| **item < 2), Some(Box::new(4)));
+        assert_eq!(iter.as_slice(), [Box::new(9)]);
+    }
+}
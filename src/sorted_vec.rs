@@ -0,0 +1,231 @@
+//! Accumulating sorted [`Vec`], see [`SortedVecMerge`].
+
+use alloc::vec::Vec;
+use core::ptr;
+
+use crate::comparators::{ByOrd, Comparator};
+
+/// Sorted [`Vec`] that absorbs sorted batches of items, merging them in place.
+///
+/// Every [`absorb`](Self::absorb) reserves the space for the batch at the end of the vector
+/// and merges from the back, so each item is moved at most once per absorb, and no temporary
+/// buffer is allocated (apart from the [`Vec`]'s own growth, which is amortized O(1) per
+/// item). If the batch entirely follows the accumulated items it's just appended.
+///
+/// Absorbing a batch of `m` items into `n` accumulated items takes O(n + m) time in the
+/// worst case (when the batch's items belong in front of the vector), and O(m) if they
+/// belong at the end. Absorbing many small batches with random keys is therefore quadratic,
+/// collect such batches into larger ones first (e.g. via [`merge`](crate::merge)).
+///
+/// Equal items are kept in order of absorption: items from the later batches follow the
+/// equal items from the earlier ones.
+///
+/// # Example
+/// ```
+/// use iter_merge::sorted_vec::SortedVecMerge;
+///
+/// let mut acc = SortedVecMerge::new();
+/// acc.absorb(vec![1, 4, 7]);
+/// acc.absorb([2, 8]);
+/// acc.absorb(9..=10);
+/// assert_eq!(acc.as_slice(), [1, 2, 4, 7, 8, 9, 10]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SortedVecMerge<T, CMP = ByOrd> {
+    items: Vec<T>,
+    comparator: CMP,
+}
+
+impl<T: Ord> SortedVecMerge<T> {
+    /// Creates an empty [`SortedVecMerge`] ordering items by [`Ord`]
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_comparator(ByOrd)
+    }
+}
+
+impl<T, CMP> SortedVecMerge<T, CMP> {
+    /// Creates an empty [`SortedVecMerge`] ordering items by `comparator`
+    #[must_use]
+    #[inline]
+    pub const fn with_comparator(comparator: CMP) -> Self {
+        Self {
+            items: Vec::new(),
+            comparator,
+        }
+    }
+
+    /// Returns the accumulated items
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns the number of the accumulated items
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no accumulated items
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the accumulated items
+    #[inline]
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T, CMP: Comparator<T>> SortedVecMerge<T, CMP> {
+    /// Merges a sorted batch of items into the accumulated ones.
+    ///
+    /// If the batch isn't sorted the order of the accumulated items becomes unspecified.
+    /// If the comparator panics, all of the items are kept, but their order is unspecified.
+    pub fn absorb<I: IntoIterator<Item = T>>(&mut self, batch: I) {
+        // Collecting a vec::IntoIter into a Vec reuses its allocation
+        let mut batch: Vec<T> = batch.into_iter().collect();
+        let (Some(last), Some(first_new)) = (self.items.last(), batch.first()) else {
+            self.items.append(&mut batch);
+            return;
+        };
+        if self.comparator.compare(last, first_new).is_le() {
+            // Ordered concatenation
+            self.items.append(&mut batch);
+            return;
+        }
+        self.items.reserve(batch.len());
+        let mut guard = BackMerge {
+            len: self.items.len() + batch.len(),
+            items: self.items.len(),
+            batch: batch.len(),
+            src: batch.as_ptr(),
+            dst: &mut self.items,
+        };
+        // SAFETY: `dst` has enough capacity for the elements of both vectors. The guard takes
+        // over the elements of both, and restores them into `dst` once it's dropped
+        unsafe {
+            guard.dst.set_len(0);
+            batch.set_len(0);
+            guard.merge(&self.comparator);
+        }
+    }
+}
+
+/// State of the merge from the back, the merged items are moved to the tail of `dst`.
+///
+/// The gap between the accumulated items and the merged tail always has the same size as the
+/// rest of the batch, so when dropped (even during the unwinding), the guard moves the rest
+/// of the batch into the gap and restores the length of `dst`.
+struct BackMerge<'a, T> {
+    // Total number of items
+    len: usize,
+    // Accumulated items in `dst[..items]` are not merged yet
+    items: usize,
+    // Batch items in `src[..batch]` are not merged yet
+    batch: usize,
+    src: *const T,
+    dst: &'a mut Vec<T>,
+}
+
+impl<T> BackMerge<'_, T> {
+    /// # Safety
+    /// `dst` has `items` initialized elements and capacity for `batch` more, `src` has
+    /// `batch` initialized elements
+    unsafe fn merge<CMP: Comparator<T>>(&mut self, comparator: &CMP) {
+        let dst = self.dst.as_mut_ptr();
+        while self.items > 0 && self.batch > 0 {
+            // SAFETY: the pointers are within the initialized elements, destination
+            // is within the gap between items and the merged tail
+            unsafe {
+                let item = dst.add(self.items - 1);
+                let new_item = self.src.add(self.batch - 1);
+                let to = dst.add(self.items + self.batch - 1);
+                if comparator.compare(&*item, &*new_item).is_gt() {
+                    ptr::copy_nonoverlapping(item, to, 1);
+                    self.items -= 1;
+                } else {
+                    // Equal items from the batch go after the accumulated ones
+                    ptr::copy_nonoverlapping(new_item, to, 1);
+                    self.batch -= 1;
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for BackMerge<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: gap dst[items..items + batch] is uninitialized, after the copy all of the
+        // `len` elements are initialized
+        unsafe {
+            let dst = self.dst.as_mut_ptr();
+            ptr::copy_nonoverlapping(self.src, dst.add(self.items), self.batch);
+            self.dst.set_len(self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec, vec::Vec};
+    use core::{cell::Cell, cmp::Ordering};
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    use super::*;
+    use crate::tests::std;
+
+    #[test]
+    fn absorb() {
+        let mut acc = SortedVecMerge::new();
+        acc.absorb(Vec::new());
+        acc.absorb(vec![3, 5]);
+        assert_eq!(acc.as_slice(), [3, 5]);
+        // front, back, interleaved
+        acc.absorb([1, 2]);
+        acc.absorb([6]);
+        acc.absorb([0, 4, 5, 9]);
+        acc.absorb([0; 0]);
+        assert_eq!(acc.len(), 9);
+        assert_eq!(acc.into_vec(), [0, 1, 2, 3, 4, 5, 5, 6, 9]);
+    }
+
+    #[test]
+    fn stable() {
+        struct ByKey;
+        impl Comparator<(u8, u8)> for ByKey {
+            fn compare(&self, a: &(u8, u8), b: &(u8, u8)) -> Ordering {
+                a.0.cmp(&b.0)
+            }
+        }
+        let mut acc = SortedVecMerge::with_comparator(ByKey);
+        acc.absorb([(1, 0), (2, 0)]);
+        acc.absorb([(1, 1), (2, 1)]);
+        acc.absorb([(0, 2), (1, 2)]);
+        assert_eq!(acc.as_slice(), [(0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn panicking_comparator() {
+        struct Panicky(Cell<usize>);
+        impl Comparator<Box<i32>> for Panicky {
+            fn compare(&self, a: &Box<i32>, b: &Box<i32>) -> Ordering {
+                let calls = self.0.get();
+                assert!(calls != 3, "comparator panic");
+                self.0.set(calls + 1);
+                a.cmp(b)
+            }
+        }
+        let mut acc = SortedVecMerge::with_comparator(Panicky(Cell::new(0)));
+        acc.absorb([2, 4, 6, 8].map(Box::new));
+        let res = catch_unwind(AssertUnwindSafe(|| acc.absorb([1, 3, 5, 7].map(Box::new))));
+        assert!(res.is_err());
+        let mut items = acc.into_vec();
+        items.sort();
+        assert_eq!(items, (1..=8).map(Box::new).collect::<Vec<_>>());
+    }
+}
@@ -1,8 +1,19 @@
 //! Storage backends
+//!
+//! Buffers allocated outside of this crate (e.g. by C code or another runtime) must be wrapped
+//! with [`InternalArrayStorage::from_raw_parts`], which never frees them.
+//! `InternalVecStorage::from_raw_parts` (with the `alloc` feature) only accepts the
+//! allocations of a `Vec`, since it frees them with the global allocator.
 
 pub(crate) mod array;
 pub use array::*;
 #[cfg(feature = "alloc")]
+pub(crate) mod heap_key;
+#[cfg(feature = "alloc")]
+pub use heap_key::*;
+#[cfg(feature = "std")]
+pub(crate) mod teardown;
+#[cfg(feature = "alloc")]
 pub(crate) mod vec;
 use core::fmt::Debug;
 
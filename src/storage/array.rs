@@ -4,11 +4,13 @@ use core::{
     marker::{PhantomData, PhantomPinned},
     mem::MaybeUninit,
     pin::Pin,
+    ptr,
 };
 
 use crate::{
+    comparators::Comparator,
     internal::{BaseStorage, PeekIter},
-    merge_iter::{DefaultBuilder, DefaultMergeIter},
+    merge_iter::{DefaultBuilder, DefaultMergeIter, MergeIterWith},
     storage::{Storage as _, debug_formatter},
 };
 
@@ -29,7 +31,10 @@ impl core::error::Error for ArrayCapacityOverflow {}
 pub struct ArrayStorage<const CAP: usize, IT: Iterator> {
     storage: [MaybeUninit<PeekIter<IT>>; CAP],
     heap: [MaybeUninit<*mut PeekIter<IT>>; CAP],
+    // Index of each stored iterator among all pushed iterators (including empty ones)
+    ids: [usize; CAP],
     len: Cell<usize>,
+    empty: usize,
     _p: PhantomPinned,
 }
 
@@ -41,6 +46,7 @@ where
         f.debug_struct("ArrayStorage")
             .field("CAP", &CAP)
             .field("len", &self.len)
+            .field("empty", &self.empty)
             .field("storage", &
                 // SAFETY: array is initialized up to self.len()
                 unsafe {
@@ -92,7 +98,9 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
         Self {
             storage: uninit_array(),
             heap: uninit_array(),
+            ids: [0; CAP],
             len: Cell::new(0),
+            empty: 0,
             _p: PhantomPinned,
         }
     }
@@ -136,6 +144,31 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
         self.len() == 0
     }
 
+    /// Returns the number of empty iterators pushed into this [`ArrayStorage`]
+    ///
+    /// Empty iterators are not stored, but they are accounted for in source indexes
+    /// (see [`MergeIter::source_tagged`](crate::MergeIter::source_tagged)).
+    #[inline]
+    pub const fn empty_count(&self) -> usize {
+        self.empty
+    }
+
+    /// Returns the indexes of the empty iterators among all of the iterators pushed
+    /// into this [`ArrayStorage`], in ascending order.
+    ///
+    /// # Example
+    /// ```
+    /// use iter_merge::ArrayStorage;
+    ///
+    /// let storage = ArrayStorage::<3, _>::from_iter([vec![], vec![1], vec![]]);
+    /// assert_eq!(storage.empty_count(), 2);
+    /// assert!(storage.empty_sources().eq([0, 2]));
+    /// ```
+    pub fn empty_sources(&self) -> impl Iterator<Item = usize> + '_ {
+        let ids = &self.ids[..self.len()];
+        (0..self.len() + self.empty).filter(move |id| ids.binary_search(id).is_err())
+    }
+
     /// Appends an element to the back of a collection.
     ///
     /// # Panics
@@ -161,7 +194,10 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
                 return Err(ArrayCapacityOverflow);
             }
             self.storage[len].write(peek_iter);
+            self.ids[len] = len + self.empty;
             self.len.set(len.checked_add(1).expect("unreachable"));
+        } else {
+            self.empty += 1;
         }
         Ok(())
     }
@@ -185,7 +221,7 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
     pub fn into_builder(self: Pin<&mut Self>) -> DefaultBuilder<InternalArrayStorage<'_, IT>> {
         let len = self.len.replace(0);
         debug_assert!(len <= CAP);
-        let (storage, heap) = {
+        let (storage, heap, ids) = {
             // SAFETY: we're never moving the data out of mut_ref, we're just copying the
             // mut pointers.
             // InternalArrayStorage lives for 'a, same as our pinned pointer
@@ -194,6 +230,7 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
             (
                 mut_ref.storage.as_mut_ptr().cast::<PeekIter<IT>>(),
                 mut_ref.heap.as_mut_ptr().cast::<*mut PeekIter<IT>>(),
+                mut_ref.ids.as_ptr(),
             )
         };
         for i in 0..len {
@@ -206,7 +243,9 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
             }
         }
         InternalArrayStorage {
+            storage,
             heap,
+            ids,
             len,
             _p: PhantomData,
         }
@@ -223,6 +262,59 @@ impl<const CAP: usize, IT: Iterator> ArrayStorage<CAP, IT> {
     {
         self.into_builder().build()
     }
+
+    /// Constructs a [`MergeIter`] from this storage, comparing items with `cmp`.
+    ///
+    /// Unlike [`build`](Self::build) doesn't require the items to implement [`Ord`].
+    /// Equivalent to calling
+    /// <code>[Self::into_builder()].[min_by(cmp)](crate::merge_iter::Builder::min_by).[build()](crate::merge_iter::Builder::build)</code>
+    ///
+    /// # Example
+    /// ```
+    /// use core::{cmp::Ordering, pin::pin};
+    ///
+    /// use iter_merge::{ArrayStorage, comparators::Comparator};
+    ///
+    /// struct TotalOrder;
+    /// impl Comparator<f64> for TotalOrder {
+    ///     fn compare(&self, a: &f64, b: &f64) -> Ordering {
+    ///         a.total_cmp(b)
+    ///     }
+    /// }
+    ///
+    /// let storage = pin!(ArrayStorage::from_arr([[0.5, 2.0], [1.5, 3.0]]));
+    /// let merged = storage.build_with(TotalOrder);
+    /// assert!(merged.eq([0.5, 1.5, 2.0, 3.0]));
+    /// ```
+    #[must_use]
+    pub fn build_with<C>(
+        self: Pin<&mut Self>, cmp: C,
+    ) -> MergeIterWith<InternalArrayStorage<'_, IT>, C>
+    where
+        C: Comparator<IT::Item>,
+    {
+        self.into_builder().min_by(cmp).build()
+    }
+}
+
+impl<const CAP: usize, I: Iterator, F, T> ArrayStorage<CAP, core::iter::Map<I, F>>
+where
+    F: FnMut(I::Item) -> T,
+{
+    /// Appends the `iter`, transforming its items with `func`.
+    ///
+    /// All of the sources share the type of `func`, so per-source state has to be captured
+    /// by the closure (e.g. closures returned by the same function).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the collection is full.
+    pub fn push_with<Iter>(&mut self, iter: Iter, func: F)
+    where
+        Iter: IntoIterator<IntoIter = I>,
+    {
+        self.push(iter.into_iter().map(func));
+    }
 }
 
 impl<const CAP: usize, IT, Item> FromIterator<Item> for ArrayStorage<CAP, IT>
@@ -277,13 +369,89 @@ impl<const CAP: usize, IT: Iterator> Drop for ArrayStorage<CAP, IT> {
 /// Internal representation of the [`ArrayStorage`] that's actually used as the
 /// [`MergeIter`](crate::MergeIter)'s [`Storage`](crate::internal::BaseStorage) backend.
 pub struct InternalArrayStorage<'a, IT: Iterator> {
+    storage: *mut PeekIter<IT>,
     heap: *mut *mut PeekIter<IT>,
+    // Source ids of the stored PeekIters; null if ids are equal to positions
+    ids: *const usize,
     len: usize,
     // represents us holding the pinned ArrayStorage, capacity is irrelevant,
     // this is only for lifetime management
     _p: PhantomData<Pin<&'a mut ArrayStorage<1, IT>>>,
 }
 
+impl<IT: Iterator> InternalArrayStorage<'_, IT> {
+    /// Creates an [`InternalArrayStorage`] directly from an array of [`PeekIter`]s and a heap
+    /// of pointers to them.
+    ///
+    /// This allows [`MergeIter`](crate::MergeIter) to operate over buffers that were
+    /// allocated outside of this crate (i.e. by C code or another runtime) without copying
+    /// them into [`ArrayStorage`] first. [`InternalArrayStorage`] never deallocates the
+    /// buffers, it only drops the [`PeekIter`]s that remain live when it's dropped.
+    ///
+    /// # Safety
+    /// For the whole lifetime `'a` of the returned value:
+    /// * `heap` must be valid for reads and writes of `len` elements
+    /// * `storage` must point to the start of a contiguous array, containing all of the
+    ///   [`PeekIter`]s pointed to by the heap
+    /// * each of the first `len` elements of `heap` must be a unique, properly aligned pointer
+    ///   to an initialized [`PeekIter`]; the pointed-to items must be valid for reads and
+    ///   writes and must not be accessed by anything other than the returned value
+    /// * the [`PeekIter`]s are owned by the returned value: they will be dropped in place
+    ///   (or moved out) by it, so the caller must not drop them
+    /// * `len` must not exceed `isize::MAX`
+    ///
+    /// # Example
+    /// ```
+    /// use core::mem::MaybeUninit;
+    ///
+    /// use iter_merge::{
+    ///     internal::PeekIter,
+    ///     storage::{InternalArrayStorage, Storage},
+    /// };
+    ///
+    /// // Buffers allocated elsewhere. Items will be owned (and dropped) by the storage,
+    /// // so they are wrapped in MaybeUninit
+    /// let mut items = MaybeUninit::new([
+    ///     PeekIter::new(1, [3, 5].into_iter()),
+    ///     PeekIter::new(2, [4, 6].into_iter()),
+    /// ]);
+    /// let base: *mut PeekIter<core::array::IntoIter<i32, 2>> = items.as_mut_ptr().cast();
+    /// let mut heap = [base, unsafe { base.add(1) }];
+    /// let storage = unsafe { InternalArrayStorage::from_raw_parts(base, heap.as_mut_ptr(), 2) };
+    /// assert!(storage.into_builder().build().eq([1, 2, 3, 4, 5, 6]));
+    /// ```
+    #[inline]
+    pub const unsafe fn from_raw_parts(
+        storage: *mut PeekIter<IT>, heap: *mut *mut PeekIter<IT>, len: usize,
+    ) -> Self {
+        Self {
+            storage,
+            heap,
+            ids: ptr::null(),
+            len,
+            _p: PhantomData,
+        }
+    }
+
+    /// Decomposes [`InternalArrayStorage`] into its raw components: the pointer to the array
+    /// of [`PeekIter`]s, the pointer to the heap of pointers and the number of live items
+    /// in it.
+    ///
+    /// After calling this function the caller is responsible for the live [`PeekIter`]s
+    /// pointed to by the first `len` elements of the heap. They could be dropped or
+    /// reassembled back into the [`InternalArrayStorage`] via [`Self::from_raw_parts`].
+    ///
+    /// Note that the order of pointers in the heap reflects the internal state of the
+    /// [`MergeIter`](crate::MergeIter) and is otherwise unspecified. Empty iterators
+    /// accounted for by the [`ArrayStorage`] are not part of the raw components, so source
+    /// indexes of the reassembled storage are the positions of items in `storage`.
+    #[inline]
+    pub fn into_raw_parts(self) -> (*mut PeekIter<IT>, *mut *mut PeekIter<IT>, usize) {
+        let this = core::mem::ManuallyDrop::new(self);
+        (this.storage, this.heap, this.len)
+    }
+}
+
 unsafe impl<IT: Iterator> BaseStorage for InternalArrayStorage<'_, IT> {
     type IT = IT;
 
@@ -297,6 +465,20 @@ unsafe impl<IT: Iterator> BaseStorage for InternalArrayStorage<'_, IT> {
         self.len
     }
 
+    #[inline]
+    fn storage(&self) -> Option<*mut PeekIter<IT>> {
+        Some(self.storage)
+    }
+
+    #[inline]
+    fn source_id(&self, position: usize) -> usize {
+        if self.ids.is_null() {
+            return position;
+        }
+        // SAFETY: ids are initialized for every position in the storage
+        unsafe { self.ids.add(position).read() }
+    }
+
     #[inline]
     unsafe fn set_len(&mut self, new_len: usize) {
         self.len = new_len;
@@ -354,4 +536,41 @@ mod tests {
         assert!(!s.is_empty());
         assert!(matches!(s.try_push([4, 5, 6]), Err(ArrayCapacityOverflow)));
     }
+
+    #[test]
+    fn empty_sources() {
+        let mut s: ArrayStorage<2, _> = ArrayStorage::new();
+        s.push(0..0);
+        s.push(0..2);
+        s.push(0..0);
+        s.push(1..2);
+        s.push(0..0);
+        assert_eq!(s.len(), 2);
+        assert_eq!(s.empty_count(), 3);
+        assert!(s.empty_sources().eq([0, 2, 4]));
+        let s = core::pin::pin!(s);
+        assert!(s.build().source_tagged().eq([(1, 0), (1, 1), (3, 1)]));
+    }
+
+    #[test]
+    fn raw_parts_roundtrip() {
+        type IT = core::array::IntoIter<i32, 2>;
+        let s: ArrayStorage<2, IT> = ArrayStorage::from_arr([[1, 3], [2, 4]]);
+        let s = core::pin::pin!(s);
+        // SAFETY: storage is pinned for the whole test
+        let mut_ref = unsafe { Pin::get_unchecked_mut(s) };
+        let len = mut_ref.len.replace(0);
+        let base = mut_ref.storage.as_mut_ptr().cast::<PeekIter<IT>>();
+        let mut heap = [core::ptr::null_mut(); 2];
+        for (i, ptr) in heap.iter_mut().enumerate().take(len) {
+            *ptr = unsafe { base.add(i) };
+        }
+        let internal =
+            unsafe { InternalArrayStorage::from_raw_parts(base, heap.as_mut_ptr(), len) };
+        let (storage_ptr, heap_ptr, len) = internal.into_raw_parts();
+        assert_eq!(len, 2);
+        assert_eq!(storage_ptr, base);
+        let internal = unsafe { InternalArrayStorage::from_raw_parts(storage_ptr, heap_ptr, len) };
+        assert!(internal.into_builder().build().eq([1, 2, 3, 4]));
+    }
 }
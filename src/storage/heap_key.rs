@@ -0,0 +1,291 @@
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering,
+    fmt::{self, Debug},
+};
+
+use crate::internal::{BaseStorage, Item, PeekIter};
+
+/// Storage that keeps the keys of the peeked items next to the heap, wrapping another storage
+///
+/// The keys are cached in an array by the positions of the items in the heap, and move
+/// together with them. Restoring the heap order compares these keys without following the
+/// pointers into the items, which pays off for small keys of large or boxed records (e.g.
+/// timestamps of log records) merged from many sources, see the `Cached keys` group of the
+/// benchmarks. For items that are cheap to reach the bookkeeping may cost more than it saves.
+/// The keys are computed when the heap is built, or on the first comparison of the items for
+/// the heaps that aren't built, e.g. by
+/// [`build_presorted`](crate::merge_iter::Builder::build_presorted). Items with equal keys are
+/// compared by the comparator of the merge, e.g. by its tie breaker.
+///
+/// Clones of the storage start with no cached keys. The keys stop being cached once the
+/// comparator of the merge is replaced, e.g. by
+/// [`Builder::min_by`](crate::merge_iter::Builder::min_by) or
+/// [`MergeIter::into_parts`](crate::MergeIter::into_parts).
+///
+/// Constructed by [`{min|max}_by_heap_key`](crate::merge_iter::Builder::min_by_heap_key)
+pub struct HeapKeyStorage<S, F, K> {
+    storage: S,
+    func: F,
+    keys: Vec<Option<K>>,
+    descending: bool,
+    caching: bool,
+}
+
+impl<S, F, K> HeapKeyStorage<S, F, K> {
+    /// Wraps the `storage`, caching the keys produced by `func`, largest key first if
+    /// `descending`
+    #[inline]
+    pub(crate) const fn new(storage: S, func: F, descending: bool) -> Self {
+        Self {
+            storage,
+            func,
+            keys: Vec::new(),
+            descending,
+            caching: true,
+        }
+    }
+
+    /// Returns the wrapped storage
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Makes sure that the key at `pos` can be cached
+    #[inline]
+    fn reserve_key(&mut self, pos: usize) {
+        if pos >= self.keys.len() {
+            self.keys.resize_with(pos + 1, || None);
+        }
+    }
+}
+
+// SAFETY: the heap and the storage are the ones of the wrapped storage
+unsafe impl<S, F, K> BaseStorage for HeapKeyStorage<S, F, K>
+where
+    S: BaseStorage,
+    F: Fn(&Item<S>) -> K,
+    K: Ord,
+{
+    type IT = S::IT;
+
+    #[inline]
+    fn heap(&self) -> *mut *mut PeekIter<S::IT> {
+        self.storage.heap()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, new_len: usize) {
+        // SAFETY: the caller guarantees it's safe
+        unsafe {
+            self.storage.set_len(new_len);
+        }
+    }
+
+    #[inline]
+    fn storage(&self) -> Option<*mut PeekIter<S::IT>> {
+        self.storage.storage()
+    }
+
+    #[inline]
+    fn source_id(&self, position: usize) -> usize {
+        self.storage.source_id(position)
+    }
+
+    #[inline]
+    fn source_rank(&self, source_id: usize) -> u64 {
+        self.storage.source_rank(source_id)
+    }
+
+    #[inline]
+    fn caches_keys(&self) -> bool {
+        self.caching
+    }
+
+    fn cache_key(&mut self, pos: usize, item: &Item<S>) {
+        self.reserve_key(pos);
+        self.keys[pos] = Some((self.func)(item));
+    }
+
+    #[inline]
+    fn swap_cached_keys(&mut self, a: usize, b: usize) {
+        self.reserve_key(a.max(b));
+        self.keys.swap(a, b);
+    }
+
+    #[inline]
+    fn compare_cached(
+        &mut self, (a_pos, a): (usize, &Item<S>), (b_pos, b): (usize, &Item<S>),
+    ) -> Option<Ordering> {
+        self.reserve_key(a_pos.max(b_pos));
+        // The heaps that weren't heapified (and the clones) cache the keys on first comparison
+        self.keys[a_pos].get_or_insert_with(|| (self.func)(a));
+        self.keys[b_pos].get_or_insert_with(|| (self.func)(b));
+        match self.keys[a_pos].cmp(&self.keys[b_pos]) {
+            Ordering::Equal => None,
+            ord if self.descending => Some(ord.reverse()),
+            ord => Some(ord),
+        }
+    }
+
+    #[inline]
+    fn forget_keys(&mut self) {
+        self.storage.forget_keys();
+        self.caching = false;
+        self.keys = Vec::new();
+    }
+}
+
+impl<S: Debug, F, K> Debug for HeapKeyStorage<S, F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeapKeyStorage")
+            .field("storage", &self.storage)
+            .field("caching", &self.caching)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Clone, F: Clone, K> Clone for HeapKeyStorage<S, F, K> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            func: self.func.clone(),
+            keys: Vec::new(),
+            descending: self.descending,
+            caching: self.caching,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::{
+        MergeIter, VecStorage,
+        comparators::{ByOrd, Comparator, MaxFirst},
+        storage::Storage,
+    };
+
+    #[test]
+    fn min_by_heap_key_caches_keys() {
+        // Every operation that changes the peeked items has to update their cached keys
+        fn script<S, CMP>(mut m: MergeIter<S, CMP>) -> Vec<(u16, u64)>
+        where
+            S: Storage<IT = vec::IntoIter<(u16, u64)>>,
+            CMP: Comparator<(u16, u64)>,
+        {
+            let mut res: Vec<_> = m.by_ref().take(5).collect();
+            res.extend(m.nth(3));
+            let head = m.peek().map_or(0, |item| item.0);
+            res.extend(m.replace_head((head.saturating_sub(1), 1005)));
+            res.extend(m.into_vec_while(|item| item.0 < 12));
+            res.extend(m.into_vec());
+            res
+        }
+
+        let sources: Vec<Vec<(u16, u64)>> = (0..40_u64)
+            .map(|n| {
+                let mut source: Vec<_> = (0..n % 9)
+                    .map(|i| (((n * 7919 + i * 104_729) % 23) as u16, n * 100 + i))
+                    .collect();
+                source.sort_by_key(|item| item.0);
+                source
+            })
+            .collect();
+        let cached = VecStorage::from_iter(sources.clone())
+            .into_builder()
+            .min_by_heap_key(|item| item.0)
+            .build();
+        let plain = VecStorage::from_iter(sources)
+            .into_builder()
+            .min_by_func(|a, b| a.0.cmp(&b.0))
+            .build();
+        assert_eq!(script(cached), script(plain));
+
+        let by_key = |sources: Vec<Vec<u16>>| {
+            VecStorage::from_iter(sources)
+                .into_builder()
+                .min_by_heap_key(|item| *item)
+                .build()
+        };
+        // The replaced head moves into the heap
+        let mut m = by_key(vec![vec![10, 90], vec![20, 70], vec![30, 80]]);
+        assert_eq!(m.replace_head(50), Ok(10));
+        assert!(m.eq([20, 30, 50, 70, 80, 90]));
+        // The last source moves in place of the exhausted first one
+        let mut m = by_key(vec![vec![10], vec![20, 60], vec![30], vec![40]]);
+        assert_eq!(m.nth(1), Some(20));
+        assert!(m.eq([30, 40, 60]));
+        // Equal keys are ordered by the tie breaker
+        let m = VecStorage::from_iter([vec![(1, 'a'), (2, 'a')], vec![(1, 'b'), (2, 'b')]])
+            .into_builder()
+            .min_by_heap_key(|item| item.0)
+            .build();
+        assert!(m.eq([(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]));
+
+        // Lazy and presorted merges don't compute the keys until the first comparison, the
+        // forced heap caches every key but the first one once, and compares the first item
+        // with the second one by their keys
+        let calls = core::cell::Cell::new(0);
+        let key = |item: &u16| {
+            calls.set(calls.get() + 1);
+            *item
+        };
+        let sources = || vec![vec![10], vec![20], vec![30], vec![40], vec![50]];
+        let mut m = VecStorage::from_iter(sources())
+            .into_builder()
+            .min_by_heap_key(key)
+            .build_lazy();
+        assert_eq!(calls.get(), 0);
+        m.force();
+        assert_eq!(calls.get(), 4 + 2);
+        let m = VecStorage::from_iter(sources())
+            .into_builder()
+            .min_by_heap_key(key)
+            .build_presorted();
+        // Debug builds check the order of the sources
+        if !cfg!(debug_assertions) {
+            assert_eq!(calls.get(), 4 + 2);
+        }
+        assert!(m.eq([10, 20, 30, 40, 50]));
+    }
+
+    #[test]
+    fn replaced_comparator_forgets_keys() {
+        let sources = || VecStorage::from_iter([vec![5, 3, 1], vec![6, 4, 2]]);
+        let merged = sources().into_builder().max_by_heap_key(|&item| item).build();
+        assert!(merged.eq([6, 5, 4, 3, 2, 1]));
+        let merged = sources()
+            .into_builder()
+            .min_by_heap_key(|&item| item)
+            .reverse()
+            .build();
+        assert!(merged.eq([6, 5, 4, 3, 2, 1]));
+        let merged = sources()
+            .into_builder()
+            .min_by_heap_key(|&item| item)
+            .max_by(ByOrd)
+            .build();
+        assert!(merged.eq([6, 5, 4, 3, 2, 1]));
+        let config = sources().into_builder().max().config();
+        let merged = config
+            .apply(sources().into_builder().min_by_heap_key(|&item| item))
+            .build();
+        assert!(merged.eq([6, 5, 4, 3, 2, 1]));
+
+        let merged = VecStorage::from_iter([vec![1], vec![5], vec![3], vec![4]])
+            .into_builder()
+            .min_by_heap_key(|&item| item)
+            .build()
+            .with_comparator(MaxFirst(ByOrd));
+        assert!(merged.eq([5, 4, 3, 1]));
+    }
+}
@@ -0,0 +1,140 @@
+//! Iterative teardown of nested merges
+//!
+//! Dropping a storage drops its sources, and a source that's a merge itself drops its own
+//! sources before returning, so the stack grows with the nesting depth of the merge tree.
+//! Instead, the [`Nested`](crate::nested::Nested) sources are handed to the worklist of the
+//! storage that's being released, and the storage drops them one after another once its own
+//! sources are dropped.
+//!
+//! Only the values that live in the memory of the source being dropped (or of the nested
+//! source being dropped from the worklist) are deferred, i.e. the ones owned by the storage.
+//! Everything else, e.g. a merge built and dropped by the `Drop` of a source, is dropped in
+//! place.
+
+use core::{cell::Cell, mem, ptr};
+use std::{thread_local, vec::Vec};
+
+/// Boxed nested source, with its type and lifetimes erased
+pub(crate) struct Deferred {
+    ptr: *mut (),
+    size: usize,
+    drop: unsafe fn(*mut ()),
+}
+
+impl Deferred {
+    /// Erases the boxed value at `ptr`, dropped by `drop`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` has to come from `Box::into_raw`, `size` has to be the size of its value and
+    /// `drop` has to drop the box.
+    pub(crate) unsafe fn new(ptr: *mut (), size: usize, drop: unsafe fn(*mut ())) -> Self {
+        Self { ptr, size, drop }
+    }
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        // SAFETY: guaranteed by `Deferred::new`, the box is dropped once
+        unsafe { (self.drop)(self.ptr) }
+    }
+}
+
+/// Memory of the value that's being dropped, and the worklist of its owner
+#[derive(Clone, Copy)]
+struct Frame {
+    start: usize,
+    end: usize,
+    worklist: *mut Vec<Deferred>,
+}
+
+impl Frame {
+    #[inline]
+    fn contains<T>(&self, value: *const T) -> bool {
+        (self.start..self.end).contains(&(value as usize))
+    }
+}
+
+thread_local! {
+    static FRAME: Cell<Option<Frame>> = const { Cell::new(None) };
+}
+
+/// Returns the current frame, `None` if thread locals are being destroyed
+#[inline]
+fn current() -> Option<Frame> {
+    FRAME.try_with(Cell::get).ok().flatten()
+}
+
+/// Restores the frame, even if a drop panics
+struct Restore(Option<Frame>);
+
+impl Restore {
+    /// Enters the frame of the value at `start..start + size`
+    fn enter(start: *const (), size: usize, worklist: *mut Vec<Deferred>) -> Self {
+        let start = start as usize;
+        let frame = Frame {
+            start,
+            end: start.saturating_add(size),
+            worklist,
+        };
+        Self(FRAME.try_with(|cell| cell.replace(Some(frame))).ok().flatten())
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let _ = FRAME.try_with(|cell| cell.set(self.0));
+    }
+}
+
+/// Drops the sources of a storage, see [`release`]
+pub(crate) struct Release {
+    worklist: *mut Vec<Deferred>,
+}
+
+impl Release {
+    /// Drops the `source` in place, deferring the nested sources it owns to the worklist
+    ///
+    /// # Safety
+    ///
+    /// Same as [`ptr::drop_in_place`]
+    pub(crate) unsafe fn drop_source<T>(&mut self, source: *mut T) {
+        let _restore = Restore::enter(source.cast(), mem::size_of::<T>(), self.worklist);
+        // SAFETY: the caller guarantees it's safe
+        unsafe { ptr::drop_in_place(source) }
+    }
+}
+
+/// Releases the storage at `storage`: `drop_sources` drops its sources with
+/// [`Release::drop_source`].
+///
+/// If the storage is owned by a value that's being released, the nested sources are left to
+/// the worklist of that release. Otherwise the storage drains its own worklist before
+/// returning.
+pub(crate) fn release<S>(storage: *const S, drop_sources: impl FnOnce(&mut Release)) {
+    if let Some(frame) = current().filter(|frame| frame.contains(storage)) {
+        drop_sources(&mut Release {
+            worklist: frame.worklist,
+        });
+        return;
+    }
+    let mut worklist = Vec::new();
+    let worklist: *mut Vec<Deferred> = &mut worklist;
+    drop_sources(&mut Release { worklist });
+    // SAFETY: the worklist outlives the loop, it's accessed only via this pointer, and the
+    // references to it don't outlive a single push or pop
+    while let Some(deferred) = unsafe { (*worklist).pop() } {
+        let _restore = Restore::enter(deferred.ptr, deferred.size, worklist);
+        drop(deferred);
+    }
+}
+
+/// Defers the drop of the nested source `deferred`, owned by the value at `owner`, if the
+/// owner is being released. Otherwise drops it in place.
+pub(crate) fn defer<O>(owner: *const O, deferred: Deferred) {
+    match current().filter(|frame| frame.contains(owner)) {
+        // SAFETY: the worklist outlives the frame, no references to it exist
+        Some(frame) => unsafe { (*frame.worklist).push(deferred) },
+        None => drop(deferred),
+    }
+}
@@ -1,25 +1,40 @@
 use alloc::{collections::TryReserveError, vec::Vec};
 use core::{
     fmt::Debug,
+    iter,
     mem::{self, ManuallyDrop},
-    slice,
+    ptr, slice,
 };
 
 use crate::{
+    MergeIter,
+    comparators::{
+        ByCachedKey, ByFloat, CachedKeys, Comparator, Float, MaxFirst, NanFiltered, NanPolicy,
+    },
     internal::{
-        BaseStorage, PeekIter,
+        BaseStorage, Item, Iter, PeekIter, StorageOps,
         nums::unchecked_add,
         pointers::{HalfUsize, ptr_to_usize, rebase_ptr},
     },
-    merge_iter::{DefaultBuilder, DefaultMergeIter},
-    storage::{Storage as _, debug_formatter},
+    merge_iter::{Builder, DefaultBuilder, DefaultMergeIter, MergeIterWith},
+    meta::WithMeta,
+    radix::{RadixKey, RadixMerge},
+    storage::{Storage, debug_formatter},
 };
+#[cfg(feature = "std")]
+use super::teardown;
 
 /// [`Vec`]-based storage for [`MergeIter`](crate::MergeIter)
 ///
 /// Most methods mirror corresponding methods on [Vec]
 #[derive(Default)]
-pub struct VecStorage<IT: Iterator>(Vec<PeekIter<IT>>);
+pub struct VecStorage<IT: Iterator>(
+    Vec<PeekIter<IT>>,
+    // Source indexes of the empty iterators, in ascending order
+    Vec<usize>,
+    // Ranks of the sources by source index, the sources past the end have the rank 0
+    Vec<u64>,
+);
 
 impl<IT> Clone for VecStorage<IT>
 where
@@ -27,7 +42,7 @@ where
     Vec<PeekIter<IT>>: Clone,
 {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self(self.0.clone(), self.1.clone(), self.2.clone())
     }
 }
 
@@ -42,14 +57,14 @@ impl<IT: Iterator> VecStorage<IT> {
     #[must_use]
     #[inline]
     pub const fn new() -> Self {
-        Self(Vec::new())
+        Self(Vec::new(), Vec::new(), Vec::new())
     }
 
     /// Constructs a new, empty [`VecStorage`] with at least the specified capacity.
     #[must_use]
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(Vec::with_capacity(capacity))
+        Self(Vec::with_capacity(capacity), Vec::new(), Vec::new())
     }
 
     /// Appends an element to the back of a collection.
@@ -60,9 +75,47 @@ impl<IT: Iterator> VecStorage<IT> {
     where
         Iter: IntoIterator<IntoIter = IT>,
     {
+        self.push_with_priority(iter, 0);
+    }
+
+    /// Appends the `iter` with the `priority`, used by the
+    /// [`ByPriority`](crate::comparators::tie_breaker::ByPriority) tie breaker.
+    ///
+    /// The priority is stored once per source, as its
+    /// [rank](crate::internal::BaseStorage::source_rank), the items of the `iter` are not
+    /// changed.
+    ///
+    /// # Panics
+    /// Panics if the new capacity exceeds `isize::MAX` _bytes_.
+    pub fn push_with_priority<Iter>(&mut self, iter: Iter, priority: u64)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        let source = self.0.len() + self.1.len();
         if let Some(peek_iter) = PeekIter::new_from_iter(iter) {
             self.0.push(peek_iter);
+        } else {
+            self.1.push(source);
         }
+        set_rank(&mut self.2, source, priority);
+    }
+
+    /// Appends the `iter` with the sequence number equal to the number of the iterators
+    /// pushed before it (including the empty ones), used by the
+    /// [`BySequence`](crate::comparators::tie_breaker::BySequence) tie breaker.
+    ///
+    /// The sequence number is stored once per source, as its
+    /// [rank](crate::internal::BaseStorage::source_rank), the items of the `iter` are not
+    /// changed.
+    ///
+    /// # Panics
+    /// Panics if the new capacity exceeds `isize::MAX` _bytes_.
+    pub fn push_sequenced<Iter>(&mut self, iter: Iter)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        let seq = (self.0.len() + self.1.len()) as u64;
+        self.push_with_priority(iter, seq);
     }
 
     /// Appends an element to the back of a collection.
@@ -76,10 +129,37 @@ impl<IT: Iterator> VecStorage<IT> {
         if let Some(peek_iter) = PeekIter::new_from_iter(iter) {
             self.0.try_reserve(1)?;
             self.0.push(peek_iter);
+        } else {
+            self.1.try_reserve(1)?;
+            self.1.push(self.0.len() + self.1.len());
         }
         Ok(())
     }
 
+    /// Returns the number of empty iterators pushed into this [`VecStorage`]
+    ///
+    /// Empty iterators are not stored, but they are accounted for in source indexes
+    /// (see [`MergeIter::source_tagged`](crate::MergeIter::source_tagged)).
+    #[inline]
+    pub fn empty_count(&self) -> usize {
+        self.1.len()
+    }
+
+    /// Returns the indexes of the empty iterators among all of the iterators pushed
+    /// into this [`VecStorage`], in ascending order.
+    ///
+    /// # Example
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let storage = VecStorage::from_iter([vec![], vec![1], vec![]]);
+    /// assert_eq!(storage.empty_count(), 2);
+    /// assert!(storage.empty_sources().eq([0, 2]));
+    /// ```
+    pub fn empty_sources(&self) -> impl Iterator<Item = usize> + '_ {
+        self.1.iter().copied()
+    }
+
     /// Reserves capacity for at least `additional` more elements to be inserted
     /// in the given [`VecStorage`].
     pub fn reserve(&mut self, additional: usize) {
@@ -117,30 +197,59 @@ impl<IT: Iterator> VecStorage<IT> {
     pub fn try_into_builder(
         self,
     ) -> Result<DefaultBuilder<InternalVecStorage<IT>>, TryReserveError> {
-        let Self(mut storage) = self;
-        storage.shrink_to_fit();
-        let len = storage.len();
+        self.try_into_internal()
+            .map(InternalVecStorage::into_builder)
+    }
+
+    /// Allocates the heap and converts into the [`InternalVecStorage`]
+    ///
+    /// Every iterator gets the slot at its source index, the slots of the empty iterators are
+    /// left vacant.
+    fn try_into_internal(self) -> Result<InternalVecStorage<IT>, TryReserveError> {
+        let Self(mut storage, empty, ranks) = self;
+        let live = storage.len();
+        let slots = live + empty.len();
+        if storage.capacity() != slots {
+            // Fallible alternative to `shrink_to_fit`/`reserve_exact`
+            let mut exact = Vec::new();
+            exact.try_reserve_exact(slots)?;
+            exact.append(&mut storage);
+            storage = exact;
+        }
         let mut heap: Vec<*mut PeekIter<IT>> = Vec::new();
-        heap.try_reserve_exact(len)?;
+        heap.try_reserve_exact(slots)?;
         let extra_heap_cap = HalfUsize::try_from(
             heap.capacity()
-                .checked_sub(len)
+                .checked_sub(slots)
                 .expect("Heap capacity is smaller than requested"),
         )
         .expect("Extra heap capacity is too large");
         let extra_storage_cap = HalfUsize::try_from(
             storage
                 .capacity()
-                .checked_sub(len)
-                .expect("Storage capacity is smaller than storage len"),
+                .checked_sub(slots)
+                .expect("Storage capacity is smaller than requested"),
         )
         .expect("Extra storage capacity is too large");
         let storage = ManuallyDrop::new(storage).as_mut_ptr();
         let heap = ManuallyDrop::new(heap).as_mut_ptr();
-        for i in 0..len {
-            // SAFETY: all pointers are valid and within respective allocations
+        // Moving the iterators to their slots back to front: the slot of the iterator is never
+        // before its current position, and every position after it is already final
+        let mut src = live;
+        let mut gaps = empty.len();
+        for slot in (0..slots).rev() {
+            if gaps > 0 && empty[gaps - 1] == slot {
+                gaps -= 1;
+                continue;
+            }
+            src -= 1;
+            // SAFETY: src <= slot < slots <= capacity, element at src is initialized and
+            // wasn't moved yet; heap has the capacity for `slots >= live` pointers
             unsafe {
-                heap.add(i).write(storage.add(i));
+                if src != slot {
+                    ptr::copy_nonoverlapping(storage.add(src), storage.add(slot), 1);
+                }
+                heap.add(src).write(storage.add(slot));
             }
         }
         Ok(InternalVecStorage {
@@ -148,10 +257,54 @@ impl<IT: Iterator> VecStorage<IT> {
             heap,
             extra_storage_cap,
             extra_heap_cap,
-            len,
-            initial_len: len,
+            len: live,
+            initial_len: slots,
+            ranks,
+        })
+    }
+
+    /// Creates the storage from the iterators, returning an error if the allocator reports a
+    /// failure.
+    ///
+    /// # Errors
+    /// Returns an error if the new capacity exceeds `isize::MAX` _bytes_ or the allocation
+    /// fails.
+    pub fn try_from_iter<I>(iters: impl IntoIterator<Item = I>) -> Result<Self, TryReserveError>
+    where
+        I: IntoIterator<IntoIter = IT>,
+    {
+        let mut res = Self::new();
+        for iter in iters {
+            res.try_push(iter)?;
         }
-        .into_builder())
+        Ok(res)
+    }
+
+    /// Constructs a [`MergeIter`](crate::MergeIter) from this storage with default parameters,
+    /// returning an error if the allocator reports a failure.
+    ///
+    /// Together with [`try_from_iter`](Self::try_from_iter) and [`try_push`](Self::try_push)
+    /// this makes the construction of the [`MergeIter`](crate::MergeIter) fallible:
+    /// [`Builder::build`] doesn't allocate, so the builder can be configured via
+    /// [`try_into_builder`](Self::try_into_builder) as well.
+    ///
+    /// # Errors
+    /// Returns error if it fails to allocate a necessary vec for constructing a heap
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::try_from_iter([vec![1, 3], vec![2]])?.try_build()?;
+    /// assert_eq!(merged.into_vec(), vec![1, 2, 3]);
+    /// # Ok::<(), std::collections::TryReserveError>(())
+    /// ```
+    pub fn try_build(self) -> Result<DefaultMergeIter<InternalVecStorage<IT>>, TryReserveError>
+    where
+        IT::Item: Ord,
+    {
+        self.try_into_builder().map(Builder::build)
     }
 
     /// Constructs a [`Builder`] from this storage
@@ -174,6 +327,119 @@ impl<IT: Iterator> VecStorage<IT> {
     {
         self.into_builder().build()
     }
+
+    /// Constructs a [`MergeIter`] from this storage, comparing items with `cmp`.
+    ///
+    /// Unlike [`build`](Self::build) doesn't require the items to implement [`Ord`].
+    /// Equivalent to calling
+    /// <code>[Self::into_builder()].[min_by(cmp)](crate::merge_iter::Builder::min_by).[build()](crate::merge_iter::Builder::build)</code>
+    ///
+    /// # Example
+    /// ```
+    /// use core::cmp::Ordering;
+    ///
+    /// use iter_merge::{VecStorage, comparators::Comparator};
+    ///
+    /// struct TotalOrder;
+    /// impl Comparator<f64> for TotalOrder {
+    ///     fn compare(&self, a: &f64, b: &f64) -> Ordering {
+    ///         a.total_cmp(b)
+    ///     }
+    /// }
+    ///
+    /// let merged = VecStorage::from_iter([vec![0.5, 2.0], vec![1.5]]).build_with(TotalOrder);
+    /// assert_eq!(merged.into_vec(), vec![0.5, 1.5, 2.0]);
+    /// ```
+    #[must_use]
+    pub fn build_with<C>(self, cmp: C) -> MergeIterWith<InternalVecStorage<IT>, C>
+    where
+        C: Comparator<IT::Item>,
+    {
+        self.into_builder().min_by(cmp).build()
+    }
+
+    /// Constructs a [`RadixMerge`] from this storage, see the [`radix`](crate::radix) module.
+    ///
+    /// An alternative merge strategy for sources of integer-like items, that are sorted in
+    /// ascending order. Equal items are yielded in unspecified order.
+    ///
+    /// # Example
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![1_u32, 4], vec![2, 3]]).radix_partitioned();
+    /// assert!(merged.eq([1, 2, 3, 4]));
+    /// ```
+    #[must_use]
+    pub fn radix_partitioned(self) -> RadixMerge<IT, fn(&IT::Item) -> u64>
+    where
+        IT::Item: RadixKey,
+    {
+        self.radix_partitioned_by_key(RadixKey::radix_key)
+    }
+
+    /// Constructs a [`RadixMerge`] from this storage, partitioning the sources by the key
+    /// produced by `func`. Sources must be sorted by that key in ascending order.
+    ///
+    /// # Example
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![(1_u32, 'a'), (4, 'b')], vec![(2, 'c')]])
+    ///     .radix_partitioned_by_key(|item| item.0);
+    /// assert!(merged.eq([(1, 'a'), (2, 'c'), (4, 'b')]));
+    /// ```
+    #[must_use]
+    pub fn radix_partitioned_by_key<F, K>(self, func: F) -> RadixMerge<IT, F>
+    where
+        F: Fn(&IT::Item) -> K,
+        K: RadixKey,
+    {
+        RadixMerge::new(self.0, func)
+    }
+}
+
+impl<I: Iterator, F, T> VecStorage<iter::Map<I, F>>
+where
+    F: FnMut(I::Item) -> T,
+{
+    /// Appends the `iter`, transforming its items with `func`.
+    ///
+    /// All of the sources share the type of `func`, so per-source state has to be captured
+    /// by the closure (e.g. closures returned by the same function).
+    ///
+    /// # Panics
+    /// Panics if the new capacity exceeds `isize::MAX` _bytes_.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let clock_offset = |offset: i64| move |timestamp: i64| timestamp + offset;
+    /// let mut storage = VecStorage::new();
+    /// storage.push_with([100, 120], clock_offset(-10));
+    /// storage.push_with([85, 115], clock_offset(5));
+    /// assert_eq!(storage.build().into_vec(), vec![90, 90, 110, 120]);
+    /// ```
+    pub fn push_with<Iter>(&mut self, iter: Iter, func: F)
+    where
+        Iter: IntoIterator<IntoIter = I>,
+    {
+        self.push(iter.into_iter().map(func));
+    }
+}
+
+impl<IT: Iterator, M> VecStorage<WithMeta<IT, M>> {
+    /// Creates the storage from the `(label, iter)` pairs, see [`WithMeta`].
+    ///
+    /// Use [`Builder::labeled`] to yield every item together with the label of its source.
+    pub fn from_labeled<I>(sources: impl IntoIterator<Item = (M, I)>) -> Self
+    where
+        I: IntoIterator<IntoIter = IT>,
+    {
+        sources.into_iter().map(WithMeta::from).collect()
+    }
 }
 
 impl<IT> Debug for VecStorage<IT>
@@ -182,7 +448,11 @@ where
     PeekIter<IT>: Debug,
 {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_tuple("VecStorage").field(&self.0).finish()
+        f.debug_tuple("VecStorage")
+            .field(&self.0)
+            .field(&self.1)
+            .field(&self.2)
+            .finish()
     }
 }
 
@@ -211,6 +481,28 @@ where
     }
 }
 
+/// Collects iterators into a [`MergeIter`] with default parameters, like
+/// [`merge`](crate::merge).
+///
+/// # Example
+/// ```
+/// use iter_merge::MergeIter;
+///
+/// let files = [vec![1, 4], vec![2, 3], vec![0, 5]];
+/// let merged: MergeIter<_, _> = files.into_iter().collect();
+/// assert!(merged.eq([0, 1, 2, 3, 4, 5]));
+/// ```
+impl<IT, Item> FromIterator<Item> for DefaultMergeIter<InternalVecStorage<IT>>
+where
+    IT: Iterator,
+    IT::Item: Ord,
+    Item: IntoIterator<IntoIter = IT>,
+{
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        VecStorage::from_iter(iter).build()
+    }
+}
+
 /// Internal representation of the [`VecStorage`] that's actually used as the
 /// [`MergeIter`](crate::MergeIter)'s [`Storage`](crate::internal::BaseStorage) backend.
 pub struct InternalVecStorage<IT: Iterator> {
@@ -220,8 +512,12 @@ pub struct InternalVecStorage<IT: Iterator> {
     extra_storage_cap: HalfUsize,
     // Extra heap capacity over the initial_len
     extra_heap_cap: HalfUsize,
+    // Number of slots in the storage, one for every inserted iterator. Slots of the empty and
+    // the exhausted iterators are vacant.
     initial_len: usize,
     len: usize,
+    // Ranks of the sources by source index, the sources past the end have the rank 0
+    ranks: Vec<u64>,
 }
 
 impl<IT: Iterator> InternalVecStorage<IT> {
@@ -237,6 +533,893 @@ impl<IT: Iterator> InternalVecStorage<IT> {
         // as conversion is safe, because the HalfUsize type is guaranteed to be smaller than usize
         unsafe { unchecked_add(self.initial_len, self.extra_heap_cap as usize) }
     }
+
+    /// Creates an [`InternalVecStorage`] directly from its raw components.
+    ///
+    /// See [`VecRawParts`] for the meaning of each component. Components produced by
+    /// [`Self::into_raw_parts`] are always valid input for this function.
+    ///
+    /// Both allocations are freed by the returned storage, so they must come from a [`Vec`].
+    /// Buffers allocated by C code or another runtime can't be passed here, use
+    /// [`InternalArrayStorage::from_raw_parts`](crate::storage::InternalArrayStorage::from_raw_parts)
+    /// for them.
+    ///
+    /// # Safety
+    /// * `storage` must have been allocated by the global allocator as a
+    ///   `Vec<PeekIter<IT>>` with capacity `storage_cap`, and `heap` - as a
+    ///   `Vec<*mut PeekIter<IT>>` with capacity `heap_cap` (see [`Vec::from_raw_parts`])
+    /// * `len <= heap_cap` and `storage_len <= storage_cap`
+    /// * first `len` elements of `heap` must be unique pointers to initialized [`PeekIter`]s
+    ///   located within the first `storage_len` elements of `storage`
+    /// * if `len == storage_len`, all of the first `storage_len` elements of `storage` must
+    ///   be initialized (i.e. pointed to by the heap)
+    /// * the returned [`InternalVecStorage`] takes ownership of both allocations and of the
+    ///   [`PeekIter`]s pointed to by the heap
+    ///
+    /// # Panics
+    /// Panics if `storage_cap - storage_len` or `heap_cap - storage_len` does not fit into
+    /// a half of `usize`.
+    pub unsafe fn from_raw_parts(parts: VecRawParts<IT>) -> Self {
+        let VecRawParts {
+            storage,
+            storage_len,
+            storage_cap,
+            heap,
+            heap_cap,
+            len,
+            ranks,
+        } = parts;
+        debug_assert!(len <= heap_cap && storage_len <= storage_cap);
+        let extra_storage_cap = HalfUsize::try_from(
+            storage_cap
+                .checked_sub(storage_len)
+                .expect("Storage capacity is smaller than storage len"),
+        )
+        .expect("Extra storage capacity is too large");
+        let extra_heap_cap = HalfUsize::try_from(
+            heap_cap
+                .checked_sub(storage_len)
+                .expect("Heap capacity is smaller than storage len"),
+        )
+        .expect("Extra heap capacity is too large");
+        Self {
+            storage,
+            heap,
+            extra_storage_cap,
+            extra_heap_cap,
+            initial_len: storage_len,
+            len,
+            ranks,
+        }
+    }
+
+    /// Decomposes [`InternalVecStorage`] into its raw components.
+    ///
+    /// After calling this function the caller is responsible for both allocations and the
+    /// live [`PeekIter`]s pointed to by the heap. The only way to correctly release them is
+    /// to convert them back into the [`InternalVecStorage`] via [`Self::from_raw_parts`].
+    ///
+    /// Source indexes are the positions of items in `storage`, including the vacant slots of
+    /// the empty and the exhausted iterators.
+    #[must_use]
+    pub fn into_raw_parts(self) -> VecRawParts<IT> {
+        let mut this = ManuallyDrop::new(self);
+        VecRawParts {
+            storage: this.storage,
+            storage_len: this.initial_len,
+            storage_cap: this.storage_cap(),
+            heap: this.heap,
+            heap_cap: this.heap_cap(),
+            len: this.len,
+            ranks: mem::take(&mut this.ranks),
+        }
+    }
+}
+
+impl<IT: Iterator> InternalVecStorage<IT> {
+    /// Appends `iter` to the storage, placing the pointer to its [`PeekIter`] at the end of
+    /// the heap (heap order is *not* restored).
+    ///
+    /// Empty iterators get a vacant slot, so they are accounted for in source indexes.
+    ///
+    /// # Panics
+    /// Panics if it fails to grow the storage or the heap.
+    fn push_iter<Iter>(&mut self, iter: Iter, rank: u64)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        match PeekIter::new_from_iter(iter) {
+            Some(peek_iter) => self.push_peek_iter(peek_iter, rank),
+            None => {
+                self.push_slot();
+                set_rank(&mut self.ranks, self.initial_len - 1, rank);
+            }
+        }
+    }
+
+    /// Appends the `peek_iter` with the `rank` after the last inserted iterator and to the
+    /// end of the heap. Heap order is not restored.
+    fn push_peek_iter(&mut self, peek_iter: PeekIter<IT>, rank: u64) {
+        let slot = self.push_slot();
+        set_rank(&mut self.ranks, self.initial_len - 1, rank);
+        // SAFETY: slot was just appended
+        unsafe {
+            self.fill_slot(slot, peek_iter);
+        }
+    }
+
+    /// Writes the `peek_iter` into the vacant `slot` and appends it to the end of the heap.
+    /// Heap order is not restored.
+    ///
+    /// # Safety
+    /// `slot` must be one of the first `initial_len` slots of the storage, and it must be
+    /// vacant (not pointed to by the heap).
+    unsafe fn fill_slot(&mut self, slot: *mut PeekIter<IT>, peek_iter: PeekIter<IT>) {
+        // SAFETY: the caller guarantees that slot is vacant, so len < initial_len and the
+        // heap has the capacity for another pointer
+        unsafe {
+            slot.write(peek_iter);
+            self.heap.add(self.len).write(slot);
+        }
+        self.len += 1;
+    }
+
+    /// Returns the vacant slot (of an empty or an exhausted source) with the smallest source
+    /// index, if there's one. Takes linear time in the number of slots.
+    fn vacant_slot(&self) -> Option<*mut PeekIter<IT>> {
+        if self.len == self.initial_len {
+            return None;
+        }
+        let mut occupied = alloc::vec![false; self.initial_len];
+        for i in 0..self.len {
+            // SAFETY: pointers up to len are valid
+            let peek_iter = unsafe { self.heap.add(i).read() };
+            let source = crate::storage::StorageOps::source_index(self, peek_iter)
+                .expect("InternalVecStorage always has a storage pointer");
+            occupied[source] = true;
+        }
+        let source = occupied.iter().position(|&occupied| !occupied)?;
+        Some(self.slot(source))
+    }
+
+    /// Appends a vacant slot after the last inserted iterator, returning the pointer to it
+    fn push_slot(&mut self) -> *mut PeekIter<IT> {
+        if self.extra_storage_cap == 0 {
+            self.grow_storage(self.growth());
+        }
+        if self.extra_heap_cap == 0 {
+            self.grow_heap(self.growth());
+        }
+        // SAFETY: there's extra capacity in the storage
+        let slot = unsafe { self.storage.add(self.initial_len) };
+        self.initial_len += 1;
+        self.extra_storage_cap -= 1;
+        self.extra_heap_cap -= 1;
+        slot
+    }
+
+    /// Moves the live [`PeekIter`]s out of the storage in order of insertion, along with their
+    /// source indexes, leaving the storage empty
+    fn take_peek_iters(&mut self) -> Vec<(usize, PeekIter<IT>)> {
+        let mut sources: Vec<*mut PeekIter<IT>> = (0..self.len)
+            // SAFETY: pointers up to len are valid
+            .map(|i| unsafe { *self.heap.add(i) })
+            .collect();
+        sources.sort_unstable_by_key(|&peek_iter| ptr_to_usize(peek_iter));
+        let mut res = Vec::with_capacity(sources.len());
+        for &peek_iter in &sources {
+            let source = crate::storage::StorageOps::source_index(self, peek_iter)
+                .expect("InternalVecStorage always has a storage pointer");
+            res.push((source, peek_iter));
+        }
+        // SAFETY: the storage no longer owns the PeekIters, every one of them is read once
+        unsafe {
+            self.set_len(0);
+            res.into_iter()
+                .map(|(source, peek_iter)| (source, peek_iter.read()))
+                .collect()
+        }
+    }
+
+    /// Converts every [`PeekIter`] via `func`, keeping the order of insertion. Sources for
+    /// which `func` returns `None` are recorded as the empty ones.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    fn filter_map_peek_iters<IT2: Iterator>(
+        mut self, mut func: impl FnMut(PeekIter<IT>) -> Option<PeekIter<IT2>>,
+    ) -> InternalVecStorage<IT2> {
+        let total = self.initial_len;
+        let mut live = Vec::new();
+        let peek_iters = self
+            .take_peek_iters()
+            .into_iter()
+            .filter_map(|(source, peek_iter)| {
+                let peek_iter = func(peek_iter)?;
+                live.push(source);
+                Some(peek_iter)
+            })
+            .collect();
+        VecStorage(peek_iters, empty_list(live, total), mem::take(&mut self.ranks))
+            .try_into_internal()
+            .expect("failed to build vec storage")
+    }
+
+    /// Returns the slot of the source with the `index`, which may be vacant
+    ///
+    /// # Panics
+    /// Panics if there's no such source.
+    fn slot(&self, index: usize) -> *mut PeekIter<IT> {
+        assert!(index < self.initial_len, "no source with index {index}");
+        // SAFETY: index is within the storage
+        unsafe { self.storage.add(index) }
+    }
+
+    /// Number of elements to grow the allocations by: doubles the capacity, while keeping
+    /// the extra capacity representable by the [`HalfUsize`]
+    #[inline]
+    fn growth(&self) -> usize {
+        self.initial_len.clamp(4, HalfUsize::MAX as usize)
+    }
+
+    /// Reserves the capacity for at least `additional` more iterators in both the storage and
+    /// the heap
+    ///
+    /// # Panics
+    /// Panics if it fails to grow the storage or the heap.
+    fn reserve(&mut self, additional: usize) {
+        if (self.extra_storage_cap as usize) < additional {
+            self.grow_storage(additional.max(self.growth()));
+        }
+        if (self.extra_heap_cap as usize) < additional {
+            self.grow_heap(additional.max(self.growth()));
+        }
+    }
+
+    /// Moves the storage into a larger allocation with at least `growth` extra elements,
+    /// rebasing the heap pointers
+    #[cold]
+    fn grow_storage(&mut self, growth: usize) {
+        let storage_len = self.initial_len;
+        // New allocation is constructed before touching self, so a panic leaves self intact
+        let new_storage: Vec<PeekIter<IT>> = Vec::with_capacity(
+            storage_len
+                .checked_add(growth)
+                .expect("capacity overflow"),
+        );
+        let extra_storage_cap = HalfUsize::try_from(new_storage.capacity() - storage_len)
+            .expect("Extra storage capacity is too large");
+        let new_storage = ManuallyDrop::new(new_storage).as_mut_ptr();
+        // SAFETY: live PeekIters are within the first storage_len elements of the old
+        // allocation, they're moved bitwise into the new one; holes are copied as garbage,
+        // but they're never read. Old allocation is released without dropping the elements.
+        unsafe {
+            ptr::copy_nonoverlapping(self.storage, new_storage, storage_len);
+            for i in 0..self.len {
+                let item = self.heap.add(i);
+                item.write(rebase_ptr(self.storage, item.read(), new_storage));
+            }
+            drop(Vec::from_raw_parts(self.storage, 0, self.storage_cap()));
+        }
+        self.storage = new_storage;
+        self.extra_storage_cap = extra_storage_cap;
+    }
+
+    /// Moves the heap into a larger allocation with at least `growth` extra elements
+    #[cold]
+    fn grow_heap(&mut self, growth: usize) {
+        let mut new_heap: Vec<*mut PeekIter<IT>> = Vec::with_capacity(
+            self.initial_len
+                .checked_add(growth)
+                .expect("capacity overflow"),
+        );
+        let extra_heap_cap = HalfUsize::try_from(new_heap.capacity() - self.initial_len)
+            .expect("Extra heap capacity is too large");
+        // SAFETY: first len pointers of the heap are initialized, capacity of the new heap
+        // is larger than len
+        unsafe {
+            new_heap.extend_from_slice(slice::from_raw_parts(self.heap, self.len));
+            drop(Vec::from_raw_parts(self.heap, 0, self.heap_cap()));
+        }
+        self.heap = ManuallyDrop::new(new_heap).as_mut_ptr();
+        self.extra_heap_cap = extra_heap_cap;
+    }
+}
+
+impl<S, CMP> MergeIter<S, CMP>
+where
+    S: Storage,
+    CMP: Comparator<Item<S>> + Clone,
+    Iter<S>: Clone,
+    Item<S>: Clone,
+{
+    /// Clones the remaining state of the [`MergeIter`] into a new one, backed by the
+    /// [`VecStorage`].
+    ///
+    /// Unlike [`Clone`], this works for any storage, e.g. to fork a merge over the
+    /// [`ArrayStorage`](crate::ArrayStorage) that borrows the pinned memory. The clone yields
+    /// the same items in the same order (including the order of equal items), and its sources
+    /// keep their indexes (see [`MergeIter::source_tagged`]), so an index taken before the fork
+    /// refers to the same source in both merges. Exhausted sources after the last live one are
+    /// not carried over.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    ///
+    /// use iter_merge::ArrayStorage;
+    ///
+    /// let storage = pin!(ArrayStorage::from_arr([[1, 4], [2, 3]]));
+    /// let mut merged = storage.build();
+    /// assert_eq!(merged.next(), Some(1));
+    /// let lookahead = merged.clone_to_vec_storage();
+    /// assert_eq!(lookahead.into_vec(), vec![2, 3, 4]);
+    /// assert!(merged.eq([2, 3, 4]));
+    /// ```
+    #[must_use]
+    pub fn clone_to_vec_storage(&self) -> MergeIter<InternalVecStorage<Iter<S>>, CMP> {
+        let mut sources: Vec<&PeekIter<Iter<S>>> = self.sources().collect();
+        // Insertion order tie breakers compare the addresses, keep their relative order
+        sources.sort_unstable_by_key(|peek_iter| ptr_to_usize(*peek_iter));
+        let storage = &self.0.storage;
+        // Storages without the source indexes number the sources in order of insertion
+        let indexes: Vec<usize> = sources
+            .iter()
+            .enumerate()
+            .map(|(rank, &peek_iter)| storage.source_index(peek_iter).unwrap_or(rank))
+            .collect();
+        let total = indexes.last().map_or(0, |&last| last + 1);
+        let mut ranks = Vec::new();
+        for (&source, &peek_iter) in indexes.iter().zip(&sources) {
+            set_rank(&mut ranks, source, storage.rank(peek_iter));
+        }
+        let peek_iters = sources.into_iter().cloned().collect();
+        let storage = VecStorage(peek_iters, empty_list(indexes, total), ranks)
+            .try_into_internal()
+            .expect("failed to build vec storage");
+        MergeIter::from_parts(storage, self.0.comparator.clone())
+    }
+}
+
+impl<IT, CMP> MergeIter<InternalVecStorage<IT>, CMP>
+where
+    IT: Iterator,
+    CMP: Comparator<IT::Item>,
+{
+    /// Adds another iterator to the [`MergeIter`], which may be partially consumed.
+    ///
+    /// The iterator is treated as the last inserted one: it gets the next source index (see
+    /// [`MergeIter::source_tagged`]), and the insertion order tie breakers place its items
+    /// after the equal items from the other iterators.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge;
+    ///
+    /// let mut merged = merge([vec![1, 4], vec![2, 6]]);
+    /// assert_eq!(merged.next(), Some(1));
+    /// merged.add_iter(vec![3, 5]);
+    /// assert_eq!(merged.into_vec(), vec![2, 3, 4, 5, 6]);
+    /// ```
+    pub fn add_iter<Iter>(&mut self, iter: Iter)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        self.add_ranked_iter(iter, 0);
+    }
+
+    /// Adds the `iter` with the [rank](BaseStorage::source_rank), see
+    /// [`add_iter`](Self::add_iter)
+    fn add_ranked_iter<Iter>(&mut self, iter: Iter, rank: u64)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        self.0.storage.push_iter(iter, rank);
+        // SAFETY: heap was in order before the push, no references into the heap exist
+        unsafe {
+            self.0.sift_up_last();
+        }
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        self.0.debug_check_heap();
+    }
+
+    /// Adds the `iter` with the sequence number equal to the number of the iterators added
+    /// before it (including the empty and the exhausted ones), see
+    /// [`VecStorage::push_sequenced`].
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new iterator.
+    pub fn add_iter_sequenced<Iter>(&mut self, iter: Iter)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        let seq = self.0.storage.initial_len as u64;
+        self.add_ranked_iter(iter, seq);
+    }
+
+    /// Adds a single item to the [`MergeIter`].
+    ///
+    /// The item is added as a source with the iterator `IT::default()`, which is empty for
+    /// the iterators like [`vec::IntoIter`](alloc::vec::IntoIter) or [`slice::Iter`]. The
+    /// source reuses the vacant slot of an exhausted (or empty) source with the smallest index,
+    /// taking its source index (see [`MergeIter::source_tagged`]) and its place in the
+    /// insertion order, so the storage only grows with the number of pending items. If there's
+    /// no vacant slot, it's added like with [`add_iter`](Self::add_iter).
+    ///
+    /// Finding the vacant slot takes linear time in the number of sources, so for a large
+    /// number of items prefer collecting them into an iterator first.
+    ///
+    /// # Panics
+    /// Panics if `IT::default()` is not empty, or if it fails to allocate the memory for the
+    /// new item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge;
+    ///
+    /// let mut schedule = merge([vec![10, 40], vec![20, 50]]);
+    /// assert_eq!(schedule.next(), Some(10));
+    /// schedule.push_item(30);
+    /// schedule.push_item(15);
+    /// assert_eq!(schedule.into_vec(), vec![15, 20, 30, 40, 50]);
+    /// ```
+    pub fn push_item(&mut self, item: IT::Item)
+    where
+        IT: Default,
+    {
+        let mut iter = IT::default();
+        assert!(iter.next().is_none(), "push_item requires IT::default() to be empty");
+        let peek_iter = PeekIter::new(item, iter);
+        let storage = &mut self.0.storage;
+        match storage.vacant_slot() {
+            // SAFETY: the slot is vacant
+            Some(slot) => unsafe { storage.fill_slot(slot, peek_iter) },
+            None => storage.push_peek_iter(peek_iter, 0),
+        }
+        // SAFETY: heap was in order before the push, no references into the heap exist
+        unsafe {
+            self.0.sift_up_last();
+        }
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        self.0.debug_check_heap();
+    }
+
+    /// Installs `iter` in place of the source with the `index` (see
+    /// [`MergeIter::source_tagged`]), returning the peeked item and the iterator of the
+    /// replaced source if it wasn't exhausted yet.
+    ///
+    /// The new iterator keeps the index, the insertion order and the
+    /// [rank](BaseStorage::source_rank) of the replaced source. Only its entry is moved within
+    /// the heap. If `iter` is empty the source is removed.
+    ///
+    /// Any source index can be re-filled, including the ones of the sources that were empty
+    /// when inserted.
+    ///
+    /// # Panics
+    /// Panics if there's no source with the `index`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge;
+    ///
+    /// let mut logs = merge([vec![1, 5], vec![2, 3]]);
+    /// assert!(logs.by_ref().take(3).eq([1, 2, 3]));
+    /// // the second segment has ended, splice in the next one
+    /// assert!(logs.swap_source(1, vec![4, 6]).is_none());
+    /// assert!(logs.source_tagged().eq([(1, 4), (0, 5), (1, 6)]));
+    /// ```
+    pub fn swap_source<Iter>(&mut self, index: usize, iter: Iter) -> Option<(IT::Item, IT)>
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        let storage = &mut self.0.storage;
+        let slot = storage.slot(index);
+        let heap = storage.heap;
+        // SAFETY: pointers up to len are valid
+        let heap_pos = (0..storage.len).find(|&i| unsafe { *heap.add(i) } == slot);
+        let replaced = match (heap_pos, PeekIter::new_from_iter(iter)) {
+            (Some(pos), Some(peek_iter)) => {
+                // SAFETY: slot is live, no references into it exist. Only the element at
+                // `pos` changes its item
+                let PeekIter { item, iter } = unsafe {
+                    let old = ptr::replace(slot, peek_iter);
+                    self.0.fix_at(pos);
+                    old
+                };
+                Some((item, iter))
+            }
+            (Some(pos), None) => {
+                let last = storage.len - 1;
+                // SAFETY: pos and last are valid heap positions, swapping pointers within the
+                // heap preserves the storage invariants
+                unsafe {
+                    ptr::swap(heap.add(pos), heap.add(last));
+                }
+                let removed = storage.pop_last_item();
+                if pos < last {
+                    // SAFETY: only the element at `pos` changed
+                    unsafe {
+                        self.0.fix_at(pos);
+                    }
+                }
+                removed
+            }
+            (None, Some(peek_iter)) => {
+                // SAFETY: slot is within initial_len and is vacant (the source is empty or
+                // exhausted)
+                unsafe {
+                    storage.fill_slot(slot, peek_iter);
+                }
+                // SAFETY: heap was in order before the push
+                unsafe {
+                    self.0.sift_up_last();
+                }
+                None
+            }
+            (None, None) => None,
+        };
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        self.0.debug_check_heap();
+        replaced
+    }
+
+    /// Moves the sources of `other` into this [`MergeIter`], merging the two heaps.
+    ///
+    /// The peeked items and the iterators of `other` are moved as is, without rebuilding
+    /// either merge from scratch. Sources of `other` are treated as inserted after the sources
+    /// of `self`, in their original order, so the insertion order tie breakers place their
+    /// items after the equal items from `self`. Comparator of `other` is dropped.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge;
+    ///
+    /// let mut left = merge([vec![1, 5], vec![3, 7]]);
+    /// let right = merge([vec![2, 6], vec![4, 8]]);
+    /// assert_eq!(left.next(), Some(1));
+    /// let merged = left.chain_merge(right);
+    /// assert_eq!(merged.into_vec(), vec![2, 3, 4, 5, 6, 7, 8]);
+    /// ```
+    pub fn chain_merge<S2>(mut self, other: MergeIter<S2, CMP>) -> Self
+    where
+        S2: Storage<IT = IT>,
+    {
+        let (mut storage, _) = other.into_parts();
+        let heap = storage.heap();
+        let mut sources: Vec<*mut PeekIter<IT>> = (0..storage.len())
+            // SAFETY: pointers up to storage.len() are valid
+            .map(|i| unsafe { *heap.add(i) })
+            .collect();
+        // Insertion order tie breakers compare the addresses, keep their relative order
+        sources.sort_unstable_by_key(|&peek_iter| ptr_to_usize(peek_iter));
+        // SAFETY: ownership of the PeekIters is transferred to `sources`, if pushing panics
+        // the rest of them is leaked
+        unsafe {
+            storage.set_len(0);
+        }
+        for peek_iter in sources {
+            let rank = storage.rank(peek_iter);
+            // SAFETY: every PeekIter is read exactly once
+            self.0
+                .storage
+                .push_peek_iter(unsafe { peek_iter.read() }, rank);
+            // SAFETY: heap was in order before the push, no references into the heap exist
+            unsafe {
+                self.0.sift_up_last();
+            }
+        }
+        #[cfg(all(debug_assertions, iter_merge_debug_checks))]
+        self.0.debug_check_heap();
+        self
+    }
+
+    /// Adds several iterators to the [`MergeIter`], see [`add_iter`](Self::add_iter).
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new iterators.
+    pub fn add_iters<Iter>(&mut self, iters: impl IntoIterator<Item = Iter>)
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        for iter in iters {
+            self.add_iter(iter);
+        }
+    }
+}
+
+impl<IT: Iterator> DefaultBuilder<InternalVecStorage<IT>> {
+    /// Constructs a new [`Builder`] with no iterators and the capacity for at least `capacity`
+    /// iterators to be [pushed](Builder::push) without reallocations
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::merge_iter::Builder;
+    ///
+    /// let mut builder = Builder::with_capacity(3);
+    /// for i in 0..3 {
+    ///     builder = builder.push((i..10).step_by(3));
+    /// }
+    /// assert!(builder.build().eq(0..10));
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        VecStorage::new().into_builder().reserve(capacity)
+    }
+}
+
+impl<IT, CMP, TieBreaker> Builder<InternalVecStorage<IT>, CMP, TieBreaker>
+where
+    IT: Iterator,
+{
+    /// Appends `iter` after the iterators already in the builder.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new iterator.
+    #[must_use]
+    pub fn push<Iter>(self, iter: Iter) -> Self
+    where
+        Iter: IntoIterator<IntoIter = IT>,
+    {
+        self.map_storage(|mut storage| {
+            storage.push_iter(iter, 0);
+            storage
+        })
+    }
+
+    /// Reserves the capacity for at least `additional` more iterators to be
+    /// [pushed](Self::push) into the builder or [added](MergeIter::add_iter) to the built
+    /// [`MergeIter`].
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory.
+    #[must_use]
+    pub fn reserve(self, additional: usize) -> Self {
+        self.map_storage(|mut storage| {
+            storage.reserve(additional);
+            storage
+        })
+    }
+
+    /// Applies `func` to every item as it's taken from its iterator, before the items are
+    /// compared.
+    ///
+    /// Every iterator gets its own clone of `func`. Comparator configured before this call
+    /// has to compare the transformed items.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let sources = [vec![" b", "d "], vec!["a ", " c"]];
+    /// let merged = VecStorage::from_iter(sources)
+    ///     .into_builder()
+    ///     .map_items(str::trim)
+    ///     .build();
+    /// assert_eq!(merged.into_vec(), vec!["a", "b", "c", "d"]);
+    /// ```
+    pub fn map_items<T, F>(
+        self, func: F,
+    ) -> Builder<InternalVecStorage<iter::Map<IT, F>>, CMP, TieBreaker>
+    where
+        F: FnMut(IT::Item) -> T + Clone,
+    {
+        self.map_storage(|storage| {
+            storage.filter_map_peek_iters(|PeekIter { item, iter }| {
+                let mut func = func.clone();
+                Some(PeekIter {
+                    item: func(item),
+                    iter: iter.map(func),
+                })
+            })
+        })
+    }
+
+    /// Computes the key of every item once, as it's taken from its iterator, and compares the
+    /// keys, yielding the smallest first. Items are yielded together with their keys as
+    /// `(key, item)` pairs.
+    ///
+    /// Unlike [`min_by_key`](Builder::min_by_key), which calls `func` on every comparison,
+    /// this is suited for the expensive keys (e.g. parsing or hashing).
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec!["3 c", "10 d"], vec!["1 a", "7 b"]])
+    ///     .into_builder()
+    ///     .min_by_cached_key(|line| line.split(' ').next().unwrap().parse::<u32>().unwrap())
+    ///     .build();
+    /// assert!(merged.map(|(_, line)| line).eq(["1 a", "3 c", "7 b", "10 d"]));
+    /// ```
+    pub fn min_by_cached_key<K, F>(
+        self, func: F,
+    ) -> Builder<InternalVecStorage<CachedKeys<IT, F>>, ByCachedKey, TieBreaker>
+    where
+        F: FnMut(&IT::Item) -> K + Clone,
+        K: Ord,
+    {
+        self.with_cached_key(func).min_by(ByCachedKey)
+    }
+
+    /// Computes the key of every item once, as it's taken from its iterator, and compares the
+    /// keys, yielding the largest first, see [`min_by_cached_key`](Self::min_by_cached_key).
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    pub fn max_by_cached_key<K, F>(
+        self, func: F,
+    ) -> Builder<InternalVecStorage<CachedKeys<IT, F>>, MaxFirst<ByCachedKey>, TieBreaker>
+    where
+        F: FnMut(&IT::Item) -> K + Clone,
+        K: Ord,
+    {
+        self.with_cached_key(func).max_by(ByCachedKey)
+    }
+
+    /// Pairs every item with its key
+    fn with_cached_key<K, F>(
+        self, func: F,
+    ) -> Builder<InternalVecStorage<CachedKeys<IT, F>>, CMP, TieBreaker>
+    where
+        F: FnMut(&IT::Item) -> K + Clone,
+    {
+        self.map_storage(|storage| {
+            storage.filter_map_peek_iters(|PeekIter { item, iter }| {
+                let mut key = func.clone();
+                Some(PeekIter {
+                    item: (key(&item), item),
+                    iter: CachedKeys { iter, key },
+                })
+            })
+        })
+    }
+
+    /// Skips the items that don't match the `predicate` as they're taken from their
+    /// iterators. Sources left without matching items are treated as the empty ones.
+    ///
+    /// Every iterator gets its own clone of `predicate`.
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::VecStorage;
+    ///
+    /// let merged = VecStorage::from_iter([vec![1, 2, 5], vec![3], vec![4, 6]])
+    ///     .into_builder()
+    ///     .filter_items(|item| item % 2 == 0)
+    ///     .build();
+    /// assert!(merged.source_tagged().eq([(0, 2), (2, 4), (2, 6)]));
+    /// ```
+    pub fn filter_items<P>(
+        self, predicate: P,
+    ) -> Builder<InternalVecStorage<iter::Filter<IT, P>>, CMP, TieBreaker>
+    where
+        P: FnMut(&IT::Item) -> bool + Clone,
+    {
+        self.map_storage(|storage| {
+            storage.filter_map_peek_iters(|PeekIter { item, mut iter }| {
+                let mut predicate = predicate.clone();
+                let item = if predicate(&item) {
+                    item
+                } else {
+                    iter.find(&mut predicate)?
+                };
+                Some(PeekIter {
+                    item,
+                    iter: iter.filter(predicate),
+                })
+            })
+        })
+    }
+
+    /// Compare the floats, yielding smallest item first and placing the NaNs according to the
+    /// `policy`
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iter_merge::{VecStorage, comparators::NanPolicy};
+    ///
+    /// let readings = VecStorage::from_iter([vec![0.5, f64::NAN, 2.0], vec![1.0, 3.0]]);
+    /// let merged = readings.into_builder().min_by_float(NanPolicy::Skip).build();
+    /// assert_eq!(merged.into_vec(), vec![0.5, 1.0, 2.0, 3.0]);
+    ///
+    /// let readings = VecStorage::from_iter([vec![1.0, f64::NAN], vec![0.5]]);
+    /// let merged = readings.into_builder().min_by_float(NanPolicy::Last).build();
+    /// assert!(merged.into_vec()[2].is_nan());
+    /// ```
+    pub fn min_by_float(
+        self, policy: NanPolicy,
+    ) -> Builder<InternalVecStorage<NanFiltered<IT>>, ByFloat, TieBreaker>
+    where
+        IT::Item: Float,
+    {
+        self.nan_filtered(policy).min_by(ByFloat::new(policy, false))
+    }
+
+    /// Compare the floats, yielding largest item first and placing the NaNs according to the
+    /// `policy`, see [`min_by_float`](Self::min_by_float)
+    ///
+    /// # Panics
+    /// Panics if it fails to allocate the memory for the new storage.
+    pub fn max_by_float(
+        self, policy: NanPolicy,
+    ) -> Builder<InternalVecStorage<NanFiltered<IT>>, ByFloat, TieBreaker>
+    where
+        IT::Item: Float,
+    {
+        self.nan_filtered(policy).min_by(ByFloat::new(policy, true))
+    }
+
+    fn nan_filtered(
+        self, policy: NanPolicy,
+    ) -> Builder<InternalVecStorage<NanFiltered<IT>>, CMP, TieBreaker>
+    where
+        IT::Item: Float,
+    {
+        let predicate: fn(&IT::Item) -> bool = if policy == NanPolicy::Skip {
+            |item| !item.is_nan()
+        } else {
+            |_| true
+        };
+        self.filter_items(predicate)
+    }
+}
+
+/// Raw components of the [`InternalVecStorage`]
+///
+/// Produced by [`InternalVecStorage::into_raw_parts`] and consumed by
+/// [`InternalVecStorage::from_raw_parts`].
+#[derive(Debug)]
+pub struct VecRawParts<IT: Iterator> {
+    /// Pointer to the allocation of [`PeekIter`]s
+    pub storage: *mut PeekIter<IT>,
+    /// Number of elements of `storage` that were initialized when the heap was constructed.
+    /// Live [`PeekIter`]s are located within `storage[..storage_len]`.
+    pub storage_len: usize,
+    /// Capacity of the `storage` allocation
+    pub storage_cap: usize,
+    /// Pointer to the heap of pointers into `storage`
+    pub heap: *mut *mut PeekIter<IT>,
+    /// Capacity of the `heap` allocation
+    pub heap_cap: usize,
+    /// Number of live pointers in the `heap`
+    pub len: usize,
+    /// [Ranks](BaseStorage::source_rank) of the sources by source index, the sources past
+    /// its end have the rank 0
+    pub ranks: Vec<u64>,
 }
 
 unsafe impl<IT: Iterator> BaseStorage for InternalVecStorage<IT> {
@@ -252,6 +1435,16 @@ unsafe impl<IT: Iterator> BaseStorage for InternalVecStorage<IT> {
         self.len
     }
 
+    #[inline]
+    fn storage(&self) -> Option<*mut PeekIter<IT>> {
+        Some(self.storage)
+    }
+
+    #[inline]
+    fn source_rank(&self, source_id: usize) -> u64 {
+        self.ranks.get(source_id).copied().unwrap_or(0)
+    }
+
     #[inline]
     unsafe fn set_len(&mut self, new_len: usize) {
         self.len = new_len;
@@ -282,6 +1475,20 @@ impl<IT: Iterator> Drop for InternalVecStorage<IT> {
             storage = Vec::from_raw_parts(self.storage, 0, self.storage_cap());
             heap = Vec::from_raw_parts(self.heap, 0, self.heap_cap());
         }
+        // The nested sources are dropped one after another instead of recursively, see
+        // `storage::teardown`
+        #[cfg(feature = "std")]
+        teardown::release(ptr::addr_of!(*self), |release| {
+            let len = self.len;
+            self.len = 0;
+            for i in 0..len {
+                // SAFETY: the first `len` heap items are valid, each is dropped once
+                unsafe {
+                    release.drop_source(self.heap.add(i).read());
+                }
+            }
+        });
+        #[cfg(not(feature = "std"))]
         crate::storage::StorageOps::clear(self);
         drop(heap);
         drop(storage);
@@ -313,146 +1520,468 @@ where
     PeekIter<IT>: Clone,
 {
     fn clone(&self) -> Self {
-        let len = self.len;
-        if len == 0 {
-            let storage = Vec::new();
-            let heap = Vec::new();
-            // Create an empty storage
-            return Self {
-                extra_heap_cap: HalfUsize::try_from(heap.capacity())
-                    .expect("Extra heap capacity is too large"),
-                extra_storage_cap: HalfUsize::try_from(storage.capacity())
-                    .expect("Extra storage capacity is too large"),
-                storage: ManuallyDrop::new(storage).as_mut_ptr(),
-                heap: ManuallyDrop::new(heap).as_mut_ptr(),
-                initial_len: len,
-                len,
-            };
-        }
-        let mut storage: Vec<PeekIter<IT>> = Vec::with_capacity(len);
-        let extra_storage_cap = HalfUsize::try_from(
-            storage
-                .capacity()
-                .checked_sub(len)
-                .expect("Storage capacity is smaller than requested"),
-        )
-        .expect("Extra storage capacity is too large");
+        // Every PeekIter is cloned into the same slot of the new storage, so the source
+        // indexes and the insertion order (compared by the tie breakers) are preserved
+        let slots = self.initial_len;
+        let storage: Vec<PeekIter<IT>> = Vec::with_capacity(slots);
+        let heap: Vec<*mut PeekIter<IT>> = Vec::with_capacity(slots);
+        let mut res = Self {
+            extra_storage_cap: HalfUsize::try_from(storage.capacity() - slots)
+                .expect("Extra storage capacity is too large"),
+            extra_heap_cap: HalfUsize::try_from(heap.capacity() - slots)
+                .expect("Extra heap capacity is too large"),
+            storage: ManuallyDrop::new(storage).as_mut_ptr(),
+            heap: ManuallyDrop::new(heap).as_mut_ptr(),
+            initial_len: slots,
+            len: 0,
+            ranks: self.ranks.clone(),
+        };
+        for i in 0..self.len {
+            // SAFETY: pointers up to self.len are valid and point into the first `slots`
+            // elements of the storage; res has the capacity for `slots` elements in both
+            // allocations. If clone panics, res drops only the clones up to its len.
+            unsafe {
+                let source = self.heap.add(i).read();
+                let slot = rebase_ptr(self.storage, source, res.storage);
+                slot.write((*source).clone());
+                res.heap.add(i).write(slot);
+            }
+            res.len += 1;
+        }
+        res
+    }
+}
 
-        let mut heap: Vec<usize> = Vec::with_capacity(len);
-        let extra_heap_cap = HalfUsize::try_from(
-            heap.capacity()
-                .checked_sub(len)
-                .expect("Heap capacity is smaller than requested"),
-        )
-        .expect("Extra heap capacity is too large");
+/// Sets the rank of the `source` in `ranks`, which leaves out the trailing zero ranks
+fn set_rank(ranks: &mut Vec<u64>, source: usize, rank: u64) {
+    if source >= ranks.len() {
+        if rank == 0 {
+            return;
+        }
+        ranks.resize(source + 1, 0);
+    }
+    ranks[source] = rank;
+}
 
-        if len == self.initial_len {
-            // no holes in the storage, just clone all of the items
-            storage.extend_from_slice(
-                // Storage does not contain any uninit values
-                unsafe { slice::from_raw_parts(self.storage, len) },
-            );
-            let storage = ManuallyDrop::new(storage).as_mut_ptr();
-            // inner pointers are uninitialized
-            let heap: *mut *mut PeekIter<IT> = ManuallyDrop::new(heap).as_mut_ptr().cast();
-            for i in 0..len {
-                unsafe {
-                    heap.add(i).write(rebase_ptr(
-                        self.storage,
-                        // add offset of i'th element of the self.heap to the new storage
-                        self.heap.add(i).read(),
-                        storage,
-                    ));
-                }
-            }
-            return Self {
-                storage,
-                heap,
-                extra_heap_cap,
-                extra_storage_cap,
-                len,
-                initial_len: len,
-            };
-        }
-
-        heap.extend(0..len);
-        // Heap is a vec of indexes 0..len
-
-        // Sort the heap in the order of the original storage
-        heap.sort_unstable_by_key(|&pos|
-            // SAFETY: self.heap is valid for reads from 0 to len
-            unsafe { self.heap.add(pos).read() });
-
-        // Now heap is a vec of indexes into the original heap,
-        // such that self.heap[heap[N]] is the N'th live iterator in order of insertion
-
-        // Filling the storage with cloned items, preserving the order of insertion
-        storage.extend(heap.iter().map(|&offset|
-            // SAFETY: self.heap is valid for reads from 0 to len, and only points to
-            // live elements; heap consists only of indexes 0..len
-            unsafe { (&*self.heap.add(offset).read()).clone() }));
-
-        // This is a bit of a complex operation.
-        // Right now heap[N] is the index into the original heap, i.e.
-        // heap[3] == 5 means that pointer to (the original of the cloned) storage[3]
-        // is at the self.heap[5].
-        // So in the final heap `heap[5]` should be `*mut storage[3]`
-        // In order to do this in-place we must perform a cyclic shift:
-        // heap[pos] <- idx <- pos <- heap[pos]
-        // But we need to differentiate between already rotated elements and new once,
-        // so we're doing
-        // heap[pos] <- idx | MARKER; idx <- pos <- heap[pos]
-        // and checking for marker's presence in our pos values.
-
-        const MARKER: usize = {
-            let marker = 1_usize << (usize::BITS - 1);
-            assert!((marker > isize::MAX as usize) && (marker & (isize::MAX as usize)) == 0);
-            // MARKER is good to use as no valid vector offset can set the last bit (since Vec
-            // can't hold more than isize::MAX bytes/non-ZST-elements)
-            marker
-        };
+/// Lists the source indexes out of `total` sources that are not `live` (in increasing order)
+fn empty_list(live: impl IntoIterator<Item = usize>, total: usize) -> Vec<usize> {
+    let mut empty = Vec::new();
+    let mut next_source = 0;
+    for source in live {
+        empty.extend(next_source..source);
+        next_source = source + 1;
+    }
+    empty.extend(next_source..total);
+    empty
+}
 
-        for mut idx in 0..len {
-            // SAFETY: idx in range 0..len
-            let mut pos = *unsafe { heap.get_unchecked(idx) };
-            if pos & MARKER != 0 {
-                continue;
-            }
-            loop {
-                pos = mem::replace(
-                    // SAFETY: pos in range 0..len
-                    unsafe { heap.get_unchecked_mut(pos) },
-                    mem::replace(&mut idx, pos) | MARKER,
-                );
-                if pos & MARKER != 0 {
-                    break;
-                }
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use core::mem::ManuallyDrop;
+
+    use super::*;
+    use crate::storage::Storage;
+
+    #[test]
+    fn add_iter() {
+        let mut m = VecStorage::from_iter([vec![(3, 'a'), (5, 'a')], vec![]]).build();
+        assert_eq!(m.next(), Some((3, 'a')));
+        // grows both allocations several times
+        for i in 0..10_i32 {
+            m.add_iter(vec![(i - 10, 'b'), (5, 'b')]);
+        }
+        m.add_iter(vec![]);
+        m.add_iter(vec![(5, 'c')]);
+        assert_eq!(m.iter_count(), 12);
+        let mut m = m.source_tagged();
+        let expected_heads = (0..10).map(|i: i32| (i as usize + 2, (i - 10, 'b')));
+        assert!(m.by_ref().take(10).eq(expected_heads));
+        // equal items are yielded in the order of insertion of their sources
+        assert_eq!(m.next(), Some((0, (5, 'a'))));
+        assert!(m.by_ref().take(10).eq((2..12).map(|source| (source, (5, 'b')))));
+        assert_eq!(m.next(), Some((13, (5, 'c'))));
+        assert_eq!(m.next(), None);
+    }
+
+    #[test]
+    fn add_iter_exhausted() {
+        let mut m = VecStorage::from_iter([vec![2]]).build();
+        assert_eq!(m.next(), Some(2));
+        assert_eq!(m.next(), None);
+        m.add_iters([vec![4, 6], vec![1, 5]]);
+        let copy = m.clone();
+        assert_eq!(m.into_vec(), [1, 4, 5, 6]);
+        assert_eq!(copy.into_vec(), [1, 4, 5, 6]);
+    }
+
+    #[test]
+    fn push_item() {
+        let mut m = VecStorage::from_iter([vec![3, 6]]).build();
+        for item in [5, 1, 6, 8] {
+            m.push_item(item);
+        }
+        assert_eq!(m.next(), Some(1));
+        // reuses the slot of the popped item
+        m.push_item(2);
+        assert!(m.source_tagged().eq([(2, 2), (0, 3), (1, 5), (0, 6), (3, 6), (4, 8)]));
+
+        let mut m = VecStorage::from_iter([vec![], vec![1000]]).build();
+        m.add_iter(vec![]);
+        // the slot count stays bounded by the number of pending items
+        for item in 0..100 {
+            m.push_item(item);
+            assert_eq!(m.peek_source(), Some(0));
+            assert_eq!(m.next(), Some(item));
+        }
+        assert_eq!(m.0.storage.initial_len, 3);
+        assert!(m.source_tagged().eq([(1, 1000)]));
+    }
+
+    #[test]
+    #[should_panic = "push_item requires IT::default() to be empty"]
+    fn push_item_nonempty_default() {
+        #[derive(Default)]
+        struct Once(bool);
+        impl Iterator for Once {
+            type Item = i32;
+            fn next(&mut self) -> Option<i32> {
+                (!core::mem::replace(&mut self.0, true)).then_some(0)
             }
         }
-        // Right now heap contains correct indexes into storage (in the order of the
-        // self.heap), or'ed with MARKER. We're replacing them with pointers to storage
-        // and we're done!
+        VecStorage::from_iter([Once(true)]).build().push_item(1);
+    }
 
-        let storage = ManuallyDrop::new(storage).as_mut_ptr();
-        // We just casted plain usizes to *mut PeekIter<IT>, but we're only reading their
-        // addresses as usizes and re-initializing them with pointers to new storage
-        let heap: *mut *mut PeekIter<IT> = ManuallyDrop::new(heap).as_mut_ptr().cast();
-        for i in 0..len {
-            // SAFETY: contents of heap are offsets in range 0..len, they are within the allocated
-            // storage range
-            unsafe {
-                let p = heap.add(i);
-                p.write(storage.add(ptr_to_usize(p.read()) & !MARKER));
+    #[test]
+    fn swap_source() {
+        let mut m = VecStorage::from_iter([vec![1, 7], vec![], vec![2, 3], vec![4, 5]]).build();
+        let (item, iter) = m.swap_source(2, vec![0, 8]).unwrap();
+        assert_eq!((item, iter.as_slice()), (2, &[3][..]));
+        assert_eq!(m.next(), Some(0));
+        assert!(m.swap_source(3, vec![]).is_some());
+        assert_eq!(m.next(), Some(1));
+        assert!(m.next_if_eq(&7).is_some());
+        assert!(m.swap_source(0, vec![6, 9]).is_none());
+        assert!(m.source_tagged().eq([(0, 6), (2, 8), (0, 9)]));
+    }
+
+    #[test]
+    fn swap_empty_source() {
+        let mut m = VecStorage::from_iter([vec![], vec![3], vec![]]).build();
+        m.add_iter(vec![]);
+        assert!(m.swap_source(0, vec![1, 4]).is_none());
+        assert!(m.swap_source(2, vec![2]).is_none());
+        assert!(m.swap_source(3, vec![]).is_none());
+        assert!(m.source_tagged().eq([(0, 1), (2, 2), (1, 3), (0, 4)]));
+    }
+
+    #[test]
+    #[should_panic = "no source with index 2"]
+    fn swap_missing_source() {
+        let mut m = VecStorage::from_iter([vec![1], vec![]]).build();
+        m.swap_source(2, vec![2]);
+    }
+
+    #[test]
+    fn swap_source_after_clone() {
+        let mut m = VecStorage::from_iter([vec![1, 5], vec![2, 3]]).build();
+        assert!(m.by_ref().take(3).eq([1, 2, 3]));
+        let mut copy = m.clone();
+        assert!(copy.swap_source(1, vec![4]).is_none());
+        assert!(copy.source_tagged().eq([(1, 4), (0, 5)]));
+
+        let checkpoint = m.mark();
+        assert_eq!(m.next(), Some(5));
+        m.rewind(&checkpoint);
+        assert!(m.swap_source(1, vec![6]).is_none());
+        assert!(m.source_tagged().eq([(0, 5), (1, 6)]));
+    }
+
+    #[test]
+    fn clone_keeps_source_indexes() {
+        let mut m = VecStorage::from_iter([vec![], vec![1], vec![4, 5], vec![], vec![2, 3]]).build();
+        assert_eq!(m.nth(1), Some(2));
+        m.add_iter(vec![]);
+        m.add_iter(vec![6]);
+        let copy = m.clone();
+        assert!(copy.source_tagged().eq([(4, 3), (2, 4), (2, 5), (6, 6)]));
+        assert!(m.source_tagged().eq([(4, 3), (2, 4), (2, 5), (6, 6)]));
+    }
+
+    #[test]
+    fn clone_to_vec_storage() {
+        use core::pin::pin;
+
+        use crate::{ArrayStorage, comparators::tie_breaker};
+
+        let data = [[(1, 'a'), (3, 'a')], [(1, 'b'), (2, 'b')], [(3, 'c'), (4, 'c')]];
+        for skip in 0..6 {
+            let s = pin!(ArrayStorage::from_arr(data));
+            let mut m = s
+                .into_builder()
+                .min_by_key(|item: &(i32, char)| item.0)
+                .tie_breaker(tie_breaker::ReverseInsertionOrder)
+                .build();
+            for _ in 0..skip {
+                m.next();
             }
+            let copy = m.clone_to_vec_storage();
+            assert_eq!(copy.into_vec(), m.collect::<Vec<_>>());
         }
 
-        Self {
-            storage,
-            heap,
-            extra_heap_cap,
-            extra_storage_cap,
-            len,
-            initial_len: len,
+        let s = pin!(ArrayStorage::from_arr([vec![], vec![1, 4], vec![2], vec![3, 5]]));
+        let mut m = s.build();
+        assert_eq!(m.nth(1), Some(2));
+        let mut fork = m.clone_to_vec_storage();
+        assert!(fork.swap_source(2, vec![6]).is_none());
+        assert!(fork.source_tagged().eq([(3, 3), (1, 4), (3, 5), (2, 6)]));
+        assert!(m.source_tagged().eq([(3, 3), (1, 4), (3, 5)]));
+    }
+
+    #[test]
+    fn collect_merge_iter() {
+        let merged: MergeIter<_, _> = [vec![3], vec![], vec![1, 2]].into_iter().collect();
+        assert!(merged.source_tagged().eq([(2, 1), (2, 2), (0, 3)]));
+    }
+
+    #[test]
+    fn map_items() {
+        let calls = core::cell::Cell::new(0);
+        let merged = VecStorage::from_iter([vec![4, 1], vec![], vec![3, 2, 0]])
+            .into_builder()
+            .map_items(|item: i32| 5 - item)
+            .map_items(|item| {
+                calls.set(calls.get() + 1);
+                item * 10
+            })
+            .build();
+        assert!(merged.source_tagged().eq([(0, 10), (2, 20), (2, 30), (0, 40), (2, 50)]));
+        assert_eq!(calls.get(), 5);
+    }
+
+    #[test]
+    fn filter_items() {
+        let merged = VecStorage::from_iter([vec![1, 3], vec![2, 4], vec![], vec![5, 6, 8]])
+            .into_builder()
+            .filter_items(|item| item % 2 == 0)
+            .filter_items(|&item| item != 6)
+            .build();
+        assert_eq!(merged.iter_count(), 2);
+        assert!(merged.source_tagged().eq([(1, 2), (1, 4), (3, 8)]));
+    }
+
+    #[test]
+    fn builder_push() {
+        let builder = Builder::with_capacity(5)
+            .push(vec![2, 5])
+            .push(vec![])
+            .map_storage(|storage| {
+                assert!(storage.storage_cap() >= 5 && storage.heap_cap() >= 5);
+                storage
+            })
+            .push(vec![1, 3])
+            .reserve(100)
+            .map_storage(|storage| {
+                assert!(storage.storage_cap() >= 102 && storage.heap_cap() >= 102);
+                storage
+            });
+        let mut merged = builder.push(vec![4]).build();
+        assert_eq!(merged.next(), Some(1));
+        merged.add_iter(vec![0, 6]);
+        assert!(merged.source_tagged().eq([(4, 0), (0, 2), (2, 3), (3, 4), (0, 5), (4, 6)]));
+    }
+
+    #[test]
+    fn by_float() {
+        let nan = f32::NAN;
+        let skip = VecStorage::from_iter([vec![nan, 1.0, nan], vec![], vec![nan], vec![0.5]]);
+        let res = skip.into_builder().min_by_float(NanPolicy::Skip).build();
+        assert!(res.source_tagged().eq([(3, 0.5), (0, 1.0)]));
+        let last = || VecStorage::from_iter([vec![1.0, nan], vec![], vec![nan], vec![0.5, 2.0]]);
+        let res = last().into_builder().min_by_float(NanPolicy::Last).build();
+        assert!(res.source_tagged().map(|(s, v)| (s, v.is_nan())).eq([
+            (3, false),
+            (0, false),
+            (3, false),
+            (0, true),
+            (2, true)
+        ]));
+        let first = || VecStorage::from_iter([vec![nan, 1.0], vec![nan, 3.0], vec![2.0, 0.5]]);
+        let res = first().into_builder().max_by_float(NanPolicy::First).build();
+        assert!(res.skip(2).eq([3.0, 2.0, 1.0, 0.5]));
+        let res = first().into_builder().max_by_float(NanPolicy::Skip).build();
+        assert_eq!(res.into_vec(), vec![3.0, 2.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn sequenced() {
+        use crate::comparators::tie_breaker::BySequence;
+
+        let mut storage = VecStorage::with_capacity(1);
+        storage.push_sequenced(vec![(2, 0), (5, 0)]);
+        storage.push_sequenced(vec![]);
+        storage.push_sequenced(vec![(2, 2)]);
+        let mut merged = storage
+            .into_builder()
+            .min_by_key(|item| item.0)
+            .tie_breaker(BySequence)
+            .build();
+        merged.add_iter_sequenced(vec![]);
+        // Forces the reallocation of the storage
+        for seq in 4..12 {
+            merged.add_iter_sequenced(vec![(5, seq)]);
         }
+        let seqs: Vec<_> = merged.take(5).collect();
+        assert_eq!(seqs, [(2, 0), (2, 2), (5, 0), (5, 4), (5, 5)]);
+    }
+
+    #[test]
+    fn priorities() {
+        use crate::comparators::tie_breaker::ByPriority;
+
+        let key = |item: &(i32, char)| item.0;
+        let builder = |sources: [(Vec<(i32, char)>, u64); 2]| {
+            let mut storage = VecStorage::new();
+            for (source, priority) in sources {
+                storage.push_with_priority(source, priority);
+            }
+            storage
+                .into_builder()
+                .min_by_key(key)
+                .tie_breaker(ByPriority)
+        };
+        let mut merged = builder([(vec![(1, 'a'), (2, 'a')], 0), (vec![], 3)]).build();
+        // The empty source keeps its priority
+        assert!(merged.swap_source(1, vec![(1, 'b'), (2, 'b')]).is_none());
+        let other = builder([(vec![(1, 'c'), (2, 'c')], 2), (vec![(2, 'd')], 4)]).build();
+        let merged = merged.chain_merge(other);
+        let expected = [(1, 'b'), (1, 'c'), (1, 'a'), (2, 'd'), (2, 'b'), (2, 'c'), (2, 'a')];
+        assert!(merged.clone_to_vec_storage().eq(expected));
+        assert!(merged.eq(expected));
+        let filtered = builder([(vec![(0, 'a'), (1, 'a')], 0), (vec![(0, 'b'), (1, 'b')], 1)])
+            .filter_items(|item| item.0 != 0)
+            .build();
+        assert!(filtered.eq([(1, 'b'), (1, 'a')]));
+    }
+
+    #[test]
+    fn push_with() {
+        let prefix = |shard: u8| move |key: u32| (shard, key);
+        let mut storage = VecStorage::new();
+        storage.push_with(vec![1, 3], prefix(1));
+        storage.push_with(vec![], prefix(2));
+        storage.push_with(vec![2, 5], prefix(0));
+        let merged = storage.build();
+        assert!(merged.source_tagged().eq([(2, (0, 2)), (2, (0, 5)), (0, (1, 1)), (0, (1, 3))]));
+    }
+
+    #[test]
+    fn cached_key() {
+        let calls = core::cell::Cell::new(0);
+        let key = |item: &i32| {
+            calls.set(calls.get() + 1);
+            -item
+        };
+        let merged = VecStorage::from_iter([vec![5, 3, 1], vec![], vec![4, 2]])
+            .into_builder()
+            .min_by_cached_key(key)
+            .build();
+        assert!(merged.eq([(-5, 5), (-4, 4), (-3, 3), (-2, 2), (-1, 1)]));
+        assert_eq!(calls.get(), 5);
+
+        let merged = VecStorage::from_iter([vec![(1, 'a'), (2, 'a')], vec![(2, 'b')]])
+            .into_builder()
+            .max_by_cached_key(|item| item.0)
+            .reverse()
+            .build();
+        assert!(merged.map(|(_, item)| item).eq([(1, 'a'), (2, 'a'), (2, 'b')]));
+    }
+
+    #[test]
+    fn chain_merge() {
+        use core::pin::pin;
+
+        use crate::ArrayStorage;
+
+        let key = (|item: &(i32, char)| item.0) as fn(&(i32, char)) -> i32;
+        let left = VecStorage::from_iter([vec![(1, 'a'), (3, 'a')], vec![(2, 'b')]])
+            .into_builder()
+            .min_by_key(key)
+            .build();
+        let right = pin!(ArrayStorage::from_arr([
+            vec![(3, 'c'), (4, 'c')],
+            vec![(1, 'd'), (3, 'd')],
+            vec![(1, 'e'), (2, 'e')],
+        ]));
+        let mut right = right.into_builder().min_by_key(key).build();
+        assert_eq!(right.next(), Some((1, 'd')));
+        let merged = left.chain_merge(right);
+        assert_eq!(merged.iter_count(), 5);
+        assert!(merged.eq([
+            (1, 'a'),
+            (1, 'e'),
+            (2, 'b'),
+            (2, 'e'),
+            (3, 'a'),
+            (3, 'c'),
+            (3, 'd'),
+            (4, 'c')
+        ]));
+    }
+
+    #[test]
+    fn raw_parts_roundtrip() {
+        use crate::comparators::tie_breaker::ByPriority;
+
+        let mut storage = ManuallyDrop::new(vec![
+            PeekIter::new(1, vec![4, 7].into_iter()),
+            PeekIter::new(2, vec![5, 8].into_iter()),
+            PeekIter::new(3, vec![6, 9].into_iter()),
+        ]);
+        let base = storage.as_mut_ptr();
+        let mut heap = ManuallyDrop::new(
+            (0..storage.len())
+                .map(|i| unsafe { base.add(i) })
+                .collect::<Vec<_>>(),
+        );
+        let parts = VecRawParts {
+            storage: base,
+            storage_len: storage.len(),
+            storage_cap: storage.capacity(),
+            heap: heap.as_mut_ptr(),
+            heap_cap: heap.capacity(),
+            len: heap.len(),
+            ranks: Vec::new(),
+        };
+        let s = unsafe { InternalVecStorage::from_raw_parts(parts) };
+        let parts = s.into_raw_parts();
+        assert_eq!(parts.len, 3);
+        assert_eq!(parts.storage_len, 3);
+        let s = unsafe { InternalVecStorage::from_raw_parts(parts) };
+        assert!(s.into_builder().build().eq(1..=9));
+
+        // The ranks survive the round trip
+        let mut s = VecStorage::new();
+        s.push_with_priority([(1, 'a')], 0);
+        s.push_with_priority([(1, 'b')], 5);
+        let parts = s.try_into_internal().unwrap().into_raw_parts();
+        assert_eq!(parts.ranks, [0, 5]);
+        let s = unsafe { InternalVecStorage::from_raw_parts(parts) };
+        let merged = s.into_builder().min_by_key(|item| item.0).tie_breaker(ByPriority);
+        assert!(merged.build().eq([(1, 'b'), (1, 'a')]));
+    }
+
+    #[test]
+    fn empty_sources() {
+        let mut s = VecStorage::new();
+        s.push(0..0);
+        s.push(0..0);
+        s.push(0..2);
+        s.push(0..0);
+        s.try_push(1..2).unwrap();
+        s.try_push(0..0).unwrap();
+        assert_eq!(s.empty_count(), 4);
+        assert!(s.empty_sources().eq([0, 1, 3, 5]));
+        assert!(s.build().source_tagged().eq([(2, 0), (2, 1), (4, 1)]));
     }
 }
@@ -5,7 +5,7 @@ pub(crate) extern crate std;
 
 use std::{
     mem::ManuallyDrop,
-    panic::{UnwindSafe, catch_unwind, panic_any},
+    panic::{RefUnwindSafe, UnwindSafe, catch_unwind, panic_any},
     pin::pin,
     sync::atomic::{AtomicUsize, Ordering::SeqCst},
 };
@@ -43,17 +43,6 @@ fn correct_on_cmp_panic<TD: TestData>(iters: &TD) {
     const PANIC_MSG: &'static str = "PanicyCmp panic";
     static CMP_CALLS: AtomicUsize = AtomicUsize::new(0);
 
-    let s = ArrayStorage::<MAX_TEST_VEC_LEN, _>::from_iter(iters.as_iters());
-    pin!(s)
-        .into_builder()
-        .min_by_func(|a, b| {
-            CMP_CALLS.fetch_add(1, SeqCst);
-            a.cmp(b)
-        })
-        .build()
-        .for_each(consume);
-    let max_num_cmp = CMP_CALLS.swap(0, SeqCst);
-
     let panicky_cmp = |panic_at| {
         move |a: &TD::Item, b: &TD::Item| {
             if CMP_CALLS.fetch_add(1, SeqCst) == panic_at {
@@ -64,43 +53,154 @@ fn correct_on_cmp_panic<TD: TestData>(iters: &TD) {
         }
     };
 
-    for panic_at in 0..max_num_cmp {
-        #[cfg(feature = "alloc")]
-        {
-            assert_panics_with(PANIC_MSG, || {
-                VecStorage::from_iter(iters.as_iters())
-                    .into_builder()
-                    .min_by_func(panicky_cmp(panic_at))
-                    .build()
-                    .into_vec();
-            });
-            assert_panics_with(PANIC_MSG, || {
-                VecStorage::from_iter(iters.as_iters())
-                    .into_builder()
-                    .min_by_func(panicky_cmp(panic_at))
-                    .build()
-                    .for_each(consume);
-            });
-            assert_panics_with(PANIC_MSG, || {
-                let mut s = ArrayStorage::with_capacity::<MAX_TEST_VEC_LEN>();
-                s.extend(iters.as_iters());
-                let s = pin!(s);
-                s.into_builder()
-                    .min_by_func(panicky_cmp(panic_at))
-                    .build()
-                    .into_vec();
-            });
+    // The number of the comparisons depends on the way the merge is consumed (the debug checks
+    // compare the popped items), so it's counted for every merge with a comparator that never
+    // panics
+    let check = |merge: &(dyn Fn(usize) + RefUnwindSafe)| {
+        merge(usize::MAX);
+        let max_num_cmp = CMP_CALLS.swap(0, SeqCst);
+        for panic_at in 0..max_num_cmp {
+            assert_panics_with(PANIC_MSG, || merge(panic_at));
         }
-        assert_panics_with(PANIC_MSG, || {
+    };
+
+    #[cfg(feature = "alloc")]
+    {
+        check(&|panic_at| {
+            VecStorage::from_iter(iters.as_iters())
+                .into_builder()
+                .min_by_func(panicky_cmp(panic_at))
+                .build()
+                .into_vec();
+        });
+        check(&|panic_at| {
+            VecStorage::from_iter(iters.as_iters())
+                .into_builder()
+                .min_by_func(panicky_cmp(panic_at))
+                .build()
+                .for_each(consume);
+        });
+        check(&|panic_at| {
             let mut s = ArrayStorage::with_capacity::<MAX_TEST_VEC_LEN>();
             s.extend(iters.as_iters());
             let s = pin!(s);
             s.into_builder()
                 .min_by_func(panicky_cmp(panic_at))
                 .build()
-                .for_each(consume);
+                .into_vec();
         });
     }
+    check(&|panic_at| {
+        let mut s = ArrayStorage::with_capacity::<MAX_TEST_VEC_LEN>();
+        s.extend(iters.as_iters());
+        let s = pin!(s);
+        s.into_builder()
+            .min_by_func(panicky_cmp(panic_at))
+            .build()
+            .for_each(consume);
+    });
+}
+
+// After catching the comparator panic the merge resumes, losing only the yielded item.
+// Test vectors aren't necessarily sorted, so only the set of the yielded items is checked
+#[cfg(feature = "alloc")]
+fn resumes_on_cmp_panic<TD: TestData>(iters: &TD) {
+    use alloc::vec::Vec;
+    use std::panic::AssertUnwindSafe;
+
+    const PANIC_MSG: &'static str = "PanicyCmp panic";
+    static CMP_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let expected = VecStorage::from_iter(iters.as_iters()).build().into_vec();
+    let max_num_cmp = iters.length() * (iters.item_count() + 1);
+    for panic_at in 0..max_num_cmp {
+        CMP_CALLS.store(0, SeqCst);
+        let Ok(mut merged) = catch_unwind(|| {
+            VecStorage::from_iter(iters.as_iters())
+                .into_builder()
+                .min_by_func(move |a: &TD::Item, b: &TD::Item| {
+                    if CMP_CALLS.fetch_add(1, SeqCst) == panic_at {
+                        panic_any(PANIC_MSG);
+                    }
+                    a.cmp(b)
+                })
+                .build()
+        }) else {
+            continue;
+        };
+        let mut res = Vec::new();
+        let mut panicked = false;
+        loop {
+            match catch_unwind(AssertUnwindSafe(|| merged.next())) {
+                Ok(Some(item)) => res.push(item),
+                Ok(None) => break,
+                Err(_) => {
+                    assert!(!panicked);
+                    panicked = true;
+                    merged.rebuild();
+                }
+            }
+        }
+        if !panicked {
+            assert_eq!(res, expected);
+            continue;
+        }
+        assert_eq!(res.len() + 1, expected.len());
+        let mut rest = expected.clone();
+        for item in &res {
+            let pos = rest.iter().position(|it| it == item).unwrap();
+            rest.remove(pos);
+        }
+        assert_eq!(rest.len(), 1, "exactly one item is lost");
+    }
+}
+
+// After catching the panic of a component iterator the merge resumes without losing anything:
+// the peeked item stays in place until the iterator yields the next one
+#[cfg(feature = "alloc")]
+fn resumes_on_next_panic<TD: TestData>(iters: &TD) {
+    use alloc::vec::Vec;
+    use std::panic::AssertUnwindSafe;
+
+    const PANIC_MSG: &'static str = "PanickyNext panic";
+    static NEXT_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut expected = VecStorage::from_iter(iters.as_iters()).build().into_vec();
+    expected.sort_unstable();
+    let max_next_calls = iters.length() + iters.item_count();
+    for panic_at in 0..max_next_calls {
+        NEXT_CALLS.store(0, SeqCst);
+        let make_iter = || {
+            iters.as_iters().map(move |mut iter| {
+                core::iter::from_fn(move || {
+                    if NEXT_CALLS.fetch_add(1, SeqCst) == panic_at {
+                        panic_any(PANIC_MSG)
+                    }
+                    iter.next()
+                })
+            })
+        };
+        let Ok(mut merged) = catch_unwind(AssertUnwindSafe(|| {
+            VecStorage::from_iter(make_iter()).build()
+        })) else {
+            continue;
+        };
+        let mut res = Vec::new();
+        let mut panicked = false;
+        loop {
+            match catch_unwind(AssertUnwindSafe(|| merged.next())) {
+                Ok(Some(item)) => res.push(item),
+                Ok(None) => break,
+                Err(_) => {
+                    assert!(!panicked);
+                    panicked = true;
+                    merged.rebuild();
+                }
+            }
+        }
+        res.sort_unstable();
+        assert_eq!(res, expected, "no items are lost");
+    }
 }
 
 struct PanickyDropIter<IT> {
@@ -248,49 +348,230 @@ fn correct_on_clone_mid_consumption(iters: &impl TestData) {
 // Under Miri tests both for UB and for memory leaks
 #[test]
 fn cmp_panic() {
-    TEST_VECTORS.iter().for_each(correct_on_cmp_panic);
+    test_vectors(ByOrd).for_each(|data| correct_on_cmp_panic(&data));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cmp_panic_resume() {
+    test_vectors(ByOrd).for_each(|data| resumes_on_cmp_panic(&data));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn next_panic_resume() {
+    test_vectors(ByOrd).for_each(|data| resumes_on_next_panic(&data));
 }
 
 #[test]
 fn iter_drop_panic() {
-    TEST_VECTORS.iter().for_each(correct_on_iter_drop_panic);
+    test_vectors(ByOrd).for_each(|data| correct_on_iter_drop_panic(&data));
 }
 
 #[test]
 fn next_panic() {
-    TEST_VECTORS.iter().for_each(correct_on_next_panic);
+    test_vectors(ByOrd).for_each(|data| correct_on_next_panic(&data));
+}
+
+// Nested merges are torn down one after another, not recursively: a 100k-level merge tree
+// drops on a small stack
+#[cfg(feature = "std")]
+#[test]
+fn deeply_nested_drop() {
+    use alloc::vec;
+
+    use crate::nested::Nested;
+    let depth = if cfg!(miri) { 20 } else { 100_000 };
+    let teardown = move || {
+        let mut iter = Nested::new(vec![depth]);
+        for level in (0..depth).rev() {
+            // Every level yields its own item first, so building the next level doesn't
+            // recurse into the nested merges
+            iter = Nested::new(VecStorage::from_iter([iter, Nested::new(vec![level])]).build());
+        }
+        drop(iter);
+    };
+    std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(teardown)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+// A merge that's built and dropped while another one is torn down is dropped in place, before
+// the data it borrows
+#[cfg(feature = "std")]
+#[test]
+fn drop_during_teardown() {
+    use alloc::vec;
+    use core::cell::Cell;
+
+    use crate::nested::Nested;
+
+    struct Reader<'a>(&'a [usize], &'a Cell<usize>);
+    impl Iterator for Reader<'_> {
+        type Item = usize;
+        fn next(&mut self) -> Option<usize> {
+            self.0.first().copied()
+        }
+    }
+    impl Drop for Reader<'_> {
+        fn drop(&mut self) {
+            self.1.set(self.0.iter().sum());
+        }
+    }
+
+    struct Source<'a>(&'a Cell<usize>);
+    impl Iterator for Source<'_> {
+        type Item = usize;
+        fn next(&mut self) -> Option<usize> {
+            Some(0)
+        }
+    }
+    impl Drop for Source<'_> {
+        fn drop(&mut self) {
+            let data = vec![448];
+            drop(VecStorage::from_iter([Nested::new(Reader(&data, self.0))]).build());
+            assert_eq!(self.0.get(), 448);
+            drop(data);
+        }
+    }
+
+    let read = Cell::new(0);
+    let inner = Nested::new(VecStorage::from_iter([Source(&read)]).build());
+    drop(VecStorage::from_iter([inner, Nested::new(vec![1])]).build());
+    assert_eq!(read.get(), 448);
 }
 
 #[cfg(feature = "alloc")]
 #[test]
 fn clone() {
-    TEST_VECTORS
-        .iter()
-        .for_each(correct_on_clone_mid_consumption);
+    test_vectors(ByOrd).for_each(|data| correct_on_clone_mid_consumption(&data));
 }
 
 #[cfg(feature = "alloc")]
 #[test]
 fn correct_order() {
     use self::order::assert_correct_order;
-    for data in TEST_VECTORS {
-        assert_correct_order(data, ByOrd, tie_breaker::InsertionOrder);
-        assert_correct_order(data, ByOrd, tie_breaker::ReverseInsertionOrder);
-        assert_correct_order(data, ByOrd, tie_breaker::Unspecified);
+    for data in test_vectors(ByOrd) {
+        assert_correct_order(&data, ByOrd, tie_breaker::InsertionOrder);
+        assert_correct_order(&data, ByOrd, tie_breaker::ReverseInsertionOrder);
+        assert_correct_order(&data, ByOrd, tie_breaker::Unspecified);
+    }
+    for data in test_vectors(MaxFirst::new::<TestItemType>(ByOrd)) {
         assert_correct_order(
-            data,
+            &data,
             MaxFirst::new::<TestItemType>(ByOrd),
             tie_breaker::InsertionOrder,
         );
         assert_correct_order(
-            data,
+            &data,
             MaxFirst::new::<TestItemType>(ByOrd),
             tie_breaker::ReverseInsertionOrder,
         );
         assert_correct_order(
-            data,
+            &data,
             MaxFirst::new::<TestItemType>(ByOrd),
             tie_breaker::Unspecified,
         );
     }
 }
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks))]
+#[test]
+#[should_panic(expected = "source 1 yielded items out of order")]
+fn debug_check_unsorted_source() {
+    let s = pin!(ArrayStorage::from_arr([[1, 2], [3, 0]]));
+    s.build().for_each(consume);
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks))]
+#[test]
+#[should_panic(expected = "merged output is out of order")]
+fn debug_check_inconsistent_comparator() {
+    let s = pin!(ArrayStorage::from_arr([[1], [2], [3]]));
+    let m = s
+        .into_builder()
+        .min_by_func(|_, _| core::cmp::Ordering::Less)
+        .build();
+    m.for_each(consume);
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks))]
+#[test]
+#[should_panic(expected = "source 0 yielded items out of order")]
+fn debug_check_nth() {
+    let s = pin!(ArrayStorage::from_arr([[1, 2, 0, 3], [10, 11, 12, 13]]));
+    // the first source is skipped in bulk, without popping
+    s.build().nth(3);
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks))]
+#[test]
+#[should_panic(expected = "source 0 yielded items out of order")]
+fn debug_check_nth_last_source() {
+    let s = pin!(ArrayStorage::from_arr([[3, 5, 6, 2]]));
+    s.build().nth(3);
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks, feature = "alloc"))]
+#[test]
+#[should_panic(expected = "source 1 yielded items out of order")]
+fn debug_check_into_vec() {
+    let s = pin!(ArrayStorage::from_arr([[10, 11, 12], [1, 3, 2]]));
+    let _ = s.build().into_vec();
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks, feature = "alloc"))]
+#[test]
+#[should_panic(expected = "source 0 yielded items out of order")]
+fn debug_check_into_vec_while() {
+    let s = pin!(ArrayStorage::from_arr([[1, 3, 2]]));
+    let _ = s.build().into_vec_while(|_| true);
+}
+
+// The comparator flips the order after the merge is built, so the sources inserted into the
+// heap break its order
+#[cfg(all(debug_assertions, iter_merge_debug_checks, feature = "alloc"))]
+#[test]
+#[should_panic(expected = "heap order is broken, the comparator is inconsistent")]
+fn debug_check_push_item() {
+    use alloc::vec;
+    use core::cell::Cell;
+
+    let flipped = Cell::new(false);
+    let mut m = VecStorage::from_iter([vec![1, 4], vec![2, 5], vec![3, 6]])
+        .into_builder()
+        .min_by_func(|a, b| if flipped.get() { b.cmp(a) } else { a.cmp(b) })
+        .build();
+    flipped.set(true);
+    m.push_item(0);
+}
+
+#[cfg(all(debug_assertions, iter_merge_debug_checks, feature = "alloc"))]
+#[test]
+#[should_panic(expected = "heap order is broken, the comparator is inconsistent")]
+fn debug_check_swap_source() {
+    use alloc::vec;
+    use core::cell::Cell;
+
+    let flipped = Cell::new(false);
+    let mut m = VecStorage::from_iter([vec![1, 4], vec![2, 5], vec![3, 6]])
+        .into_builder()
+        .min_by_func(|a, b| if flipped.get() { b.cmp(a) } else { a.cmp(b) })
+        .build();
+    flipped.set(true);
+    m.swap_source(2, vec![0]);
+}
+
+#[test]
+fn zero_sized() {
+    let s = pin!(ArrayStorage::from_arr([
+        core::iter::repeat(()).take(2),
+        core::iter::repeat(()).take(1),
+    ]));
+    let mut counts = [0; 2];
+    s.build().source_tagged().for_each(|(source, ())| counts[source] += 1);
+    assert_eq!(counts, [2, 1]);
+}
@@ -121,6 +121,8 @@ const ALL_TEST_VECTORS: &[&[&[TestItemType]]] = &[
     ],
 ];
 
+// Tests are not bound by the MSRV
+#[allow(clippy::incompatible_msrv)]
 pub(crate) const TEST_VECTORS: &[&[&[TestItemType]]] = {
     if cfg!(miri) {
         // Run miri on a smaller number of tests
@@ -134,6 +136,36 @@ pub(crate) const TEST_VECTORS: &[&[&[TestItemType]]] = {
     }
 };
 
+/// Test vectors for the merges that order the items by `cmp`.
+///
+/// The debug checks reject the sources that aren't sorted, so with them every source is sorted
+/// by `cmp` first. The merges of the unsorted sources are tested without the debug checks.
+#[cfg(not(all(debug_assertions, iter_merge_debug_checks)))]
+pub(crate) fn test_vectors(
+    _cmp: impl Comparator<TestItemType>,
+) -> impl Iterator<Item = impl TestData<Item = MaybeBoxed<TestItemType>>> {
+    TEST_VECTORS.iter().copied()
+}
+
+/// Test vectors for the merges that order the items by `cmp`.
+///
+/// The debug checks reject the sources that aren't sorted, so with them every source is sorted
+/// by `cmp` first. The merges of the unsorted sources are tested without the debug checks.
+#[cfg(all(debug_assertions, iter_merge_debug_checks))]
+pub(crate) fn test_vectors(
+    cmp: impl Comparator<TestItemType>,
+) -> impl Iterator<Item = impl TestData<Item = MaybeBoxed<TestItemType>>> {
+    TEST_VECTORS.iter().map(move |data| {
+        data.iter()
+            .map(|source| {
+                let mut source = source.to_vec();
+                source.sort_by(|a, b| cmp.compare(a, b));
+                source
+            })
+            .collect::<Vec<_>>()
+    })
+}
+
 pub(crate) const MAX_TEST_VEC_LEN: usize = {
     let mut val = 0;
     let mut i = 0;
@@ -192,6 +224,8 @@ use core::{
     panic::{RefUnwindSafe, UnwindSafe},
 };
 
+use crate::comparators::Comparator;
+
 use super::std::vec::Vec;
 impl<T> TestData for Vec<Vec<T>>
 where
@@ -18,6 +18,10 @@ where
     fn compare<'a>(&self, a: &'a TaggedItem<T>, b: &'a TaggedItem<T>) -> core::cmp::Ordering {
         self.0.compare(&a.item, &b.item)
     }
+
+    fn equivalent(&self, a: &TaggedItem<T>, b: &TaggedItem<T>) -> bool {
+        self.0.equivalent(&a.item, &b.item)
+    }
 }
 use super::data::TestData;
 pub fn assert_correct_order<T: Clone + Ord + Debug>(